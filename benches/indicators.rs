@@ -0,0 +1,105 @@
+//! Indicators on ten years of minute bars, computed on the bars or on their columns
+//!
+//! Run with `cargo bench --bench indicators`
+
+use chrono::{Duration, NaiveDate};
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion};
+use market_data::{Adjustment, Interval, MarketSeries, Series, Tz};
+use std::collections::VecDeque;
+use std::hint::black_box;
+
+// 10 years * 252 trading days * 390 minutes
+const BARS: usize = 982_800;
+
+fn series() -> MarketSeries {
+    let start = NaiveDate::from_ymd_opt(2014, 1, 2)
+        .unwrap()
+        .and_hms_opt(9, 30, 0)
+        .unwrap();
+    MarketSeries {
+        symbol: "AAPL".to_string(),
+        interval: Interval::Min1,
+        timezone: Tz::America__New_York,
+        data: (0..BARS)
+            .map(|i| {
+                let close = 100.0 + (i as f64 / 500.0).sin() * 10.0 + (i % 7) as f64 * 0.1;
+                Series {
+                    datetime: start + Duration::minutes(i as i64),
+                    open: close - 0.05,
+                    close,
+                    high: close + 0.2,
+                    low: close - 0.2,
+                    volume: 1000.0 + (i % 100) as f64,
+                    adj_close: None,
+                }
+            })
+            .collect(),
+        adjustment: Adjustment::Raw,
+        actions: Vec::new(),
+    }
+}
+
+/// The moving average walking the bars, as it was computed before the columnar view
+fn sma_on_bars(series: &[Series], period: usize) -> VecDeque<f64> {
+    let mut sma_values: VecDeque<f64> = series
+        .windows(period)
+        .map(|window| window.iter().map(|item| item.close).sum::<f64>() / period as f64)
+        .collect();
+
+    for _ in 1..period {
+        sma_values.push_front(0.0);
+    }
+
+    sma_values
+}
+
+fn indicators(c: &mut Criterion) {
+    let series = series();
+    let columns = series.to_columns();
+
+    let mut group = c.benchmark_group("sma 200");
+    group.sample_size(10);
+    group.bench_function("bars", |b| {
+        b.iter(|| sma_on_bars(black_box(&series.data), 200))
+    });
+    group.bench_function("columns", |b| b.iter(|| black_box(&columns).sma(200)));
+    group.finish();
+
+    let mut group = c.benchmark_group("all indicators");
+    group.sample_size(10);
+    group.bench_function("calculate", |b| {
+        b.iter_batched(
+            || series.clone(),
+            |series| {
+                series
+                    .enhance_data()
+                    .with_sma(20)
+                    .with_ema(20)
+                    .with_rsi(14)
+                    .with_stochastic(14)
+                    .with_macd(12, 26, 9)
+                    .with_bb(20, 2)
+                    .calculate()
+            },
+            BatchSize::LargeInput,
+        )
+    });
+    group.bench_function("columns", |b| {
+        b.iter(|| {
+            let columns = black_box(&columns);
+            (
+                columns.sma(20),
+                columns.ema(20),
+                columns.rsi(14),
+                columns.stochastic(14),
+                columns.macd(12, 26, 9),
+                columns.bollinger_bands(20, 2),
+            )
+        })
+    });
+    group.bench_function("to_columns", |b| b.iter(|| black_box(&series).to_columns()));
+    group.finish();
+}
+
+criterion_group!(benches, indicators);
+criterion_main!(benches);
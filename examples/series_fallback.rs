@@ -0,0 +1,39 @@
+use anyhow::Result;
+use chrono::{Duration, Utc};
+use lazy_static::lazy_static;
+use market_data::{
+    FallbackClient, Finnhub, Interval, MarketClient, SeriesRequest, Twelvedata, YahooFin,
+};
+use std::env::var;
+
+lazy_static! {
+    static ref FINNHUB_TOKEN: String =
+        var("Finnhub_TOKEN").expect("Finnhub_TOKEN env variable is required");
+    static ref TWELVEDATA_TOKEN: String =
+        var("Twelvedata_TOKEN").expect("Twelvedata_TOKEN env variable is required");
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    // The publishers are tried in this order
+    let client = FallbackClient::new()
+        .with(MarketClient::new(Finnhub::new(FINNHUB_TOKEN.to_string())))
+        .with(MarketClient::new(Twelvedata::new(
+            TWELVEDATA_TOKEN.to_string(),
+        )))
+        .with(MarketClient::new(YahooFin::new()));
+
+    // Create a provider-neutral request
+    let request =
+        SeriesRequest::new("AAPL", Interval::Daily).with_start(Utc::now() - Duration::days(30));
+
+    // Fetch the data from the first publisher that succeeds
+    let served = client.fetch(&request).await?;
+
+    for (publisher, error) in &served.skipped {
+        println!("Skipped {}: {}", publisher, error);
+    }
+    println!("Served by {}:\n{}", served.publisher, served.series);
+
+    Ok(())
+}
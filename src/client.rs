@@ -1,46 +1,339 @@
 //! Market-Data client implementation
 
 use crate::{
-    errors::MarketResult, indicators::EnhancedMarketSeries, publishers::Publisher, MarketError,
+    errors::MarketResult,
+    indicators::EnhancedMarketSeries,
+    publishers::{Publisher, SeriesRequest},
+    series::merge::ConflictPolicy,
+    MarketError,
 };
-use chrono::NaiveDateTime;
+use chrono::{DateTime, NaiveDateTime, TimeZone};
+use chrono_tz::Tz;
+use futures::stream::{self, Stream, StreamExt};
+use reqwest::StatusCode;
 use serde::{Deserialize, Serialize};
 use std::fmt;
-use std::str::FromStr;
+use std::time::Duration;
+use url::Url;
+
+pub(crate) mod actions;
+pub(crate) mod builder;
+pub(crate) mod cache;
+pub(crate) mod cassette;
+pub(crate) mod fallback;
+pub(crate) mod interval;
+pub(crate) mod rate_limit;
+pub(crate) mod retry;
+
+pub use self::interval::Interval;
+
+use self::{
+    actions::{Adjustment, CorporateAction},
+    builder::MarketClientBuilder,
+    cache::FileCache,
+    cassette::{Cassette, CassetteMode},
+    rate_limit::{RateLimit, RateLimiter},
+    retry::{retry_after, RetryPolicy},
+};
+
+/// The default user agent, sent with every request
+pub(crate) const USER_AGENT: &str = concat!("market-data-rust/", env!("CARGO_PKG_VERSION"));
 
 /// MarketClient holds the Publisher and reqwest::Client
 pub struct MarketClient<T: Publisher> {
     pub site: T,
     inner: reqwest::Client,
+    retry: RetryPolicy,
+    limiter: Option<RateLimiter>,
+    cache: Option<FileCache>,
+    cassette: Option<Cassette>,
 }
 
 impl<T: Publisher> MarketClient<T> {
     pub fn new(site: T) -> Self {
+        let limiter = site.rate_limit().map(RateLimiter::new);
         Self {
             site,
             inner: reqwest::Client::builder()
-                .user_agent("market-data-rust/0.4.0")
+                .user_agent(USER_AGENT)
                 .build()
                 .unwrap_or_default(),
+            retry: RetryPolicy::none(),
+            limiter,
+            cache: None,
+            cassette: None,
         }
     }
 
+    /// Configure the HTTP transport: an existing reqwest::Client, timeouts, proxy, headers, connection pool
+    pub fn builder(site: T) -> MarketClientBuilder<T> {
+        MarketClientBuilder::new(site)
+    }
+
+    /// Override the Publisher's default rate limit, `None` disables client-side limiting
+    pub fn with_rate_limit(mut self, limit: Option<RateLimit>) -> Self {
+        self.limiter = limit.map(RateLimiter::new);
+        self
+    }
+
+    /// Retry transient failures (HTTP 429/5xx, timeouts, connection errors) according to the policy
+    ///
+    /// Every retry is a request of its own for the rate limit and the daily quota.
+    pub fn with_retry(mut self, policy: RetryPolicy) -> Self {
+        self.retry = policy;
+        self
+    }
+
+    /// Cache the fetched series on disk, later fetches only request the bars newer than the cached ones
+    pub fn with_cache(mut self, cache: FileCache) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+
+    /// Record the raw responses to a cassette file, or replay them without network
+    pub fn with_cassette(mut self, cassette: Cassette) -> Self {
+        self.cassette = Some(cassette);
+        self
+    }
+
     /// Fetches the data for a single request
+    ///
+    /// If the request still fails after more than one attempt, the error is wrapped
+    /// in `MarketError::RetriesExhausted` which records the number of attempts.
     pub async fn fetch(&self, request: T::Request) -> MarketResult<MarketSeries> {
-        let url = self.site.create_endpoint(&request)?;
-        let response = self.inner.get(url).send().await?;
+        match &self.cache {
+            Some(cache) => self.fetch_cached(cache, &request).await,
+            None => self.fetch_retrying(&request).await,
+        }
+    }
+
+    /// Fetches the bars described by a provider-neutral request, within its date range and bar count
+    pub async fn fetch_series(&self, request: &SeriesRequest) -> MarketResult<MarketSeries> {
+        let native = self.site.series_request(request)?;
+        let mut series = self.fetch(native).await?;
+
+        let timezone = series.timezone;
+        if let Some(start) = request.start {
+            series.data.retain(|s| s.datetime_in(timezone) >= start);
+        }
+        if let Some(end) = request.end {
+            series.data.retain(|s| s.datetime_in(timezone) <= end);
+        }
+        if let Some(bars) = request.bars {
+            let excess = series.data.len().saturating_sub(bars);
+            series.data.drain(..excess);
+        }
+        Ok(series)
+    }
+
+    async fn fetch_cached(
+        &self,
+        cache: &FileCache,
+        request: &T::Request,
+    ) -> MarketResult<MarketSeries> {
+        let publisher = self.site.name();
+        let key = self.site.cache_key(request);
+
+        let cached = match cache.load(publisher, &key).await {
+            Some((cached, true)) => return Ok(cached),
+            Some((cached, false)) => Some(cached),
+            None => None,
+        };
+        let refresh = cached.and_then(|cached| {
+            let last = cached.data.last()?.datetime;
+            let refresh = self.site.refresh_request(request, last)?;
+            Some((cached, last, refresh))
+        });
+
+        // without a narrowed request, the whole series is fetched again and replaces the cached one,
+        // so a rolling window like the last 100 bars doesn't keep growing
+        let series = match refresh {
+            Some((cached, last, refresh)) => {
+                let fetched = self.fetch_retrying(&refresh).await?;
+                match extend_cached(cached, last, fetched) {
+                    Some(series) => series,
+                    // the cache only saves requests, it never fails a fetch: the entry is rewritten
+                    None => self.fetch_retrying(request).await?,
+                }
+            }
+            None => self.fetch_retrying(request).await?,
+        };
+
+        cache.store(publisher, &key, &series).await?;
+        Ok(series)
+    }
+
+    async fn fetch_retrying(&self, request: &T::Request) -> MarketResult<MarketSeries> {
+        let mut attempt = 1;
+        loop {
+            match self.fetch_once(request).await {
+                Ok(series) => return Ok(series),
+                Err(e) if self.retry.is_retryable(&e) && attempt < self.retry.max_attempts() => {
+                    // a Retry-After above the max delay gives up with the error
+                    let Some(delay) = self.retry.delay(attempt, e.retry_after()) else {
+                        return Err(retries_exhausted(attempt, e));
+                    };
+                    // the replayed responses don't change by waiting
+                    if !self.replaying() {
+                        tokio::time::sleep(delay).await;
+                    }
+                    attempt += 1;
+                }
+                Err(e) => return Err(retries_exhausted(attempt, e)),
+            }
+        }
+    }
+
+    /// Fetches the data for multiple requests, running at most `concurrency` of them at a time
+    ///
+    /// The results are keyed by symbol and returned in the order of the requests,
+    /// a failed request does not abort the rest of the batch.
+    pub async fn fetch_many<I>(
+        &self,
+        requests: I,
+        concurrency: usize,
+    ) -> Vec<(String, MarketResult<MarketSeries>)>
+    where
+        I: IntoIterator<Item = T::Request>,
+    {
+        stream::iter(requests)
+            .map(|request| self.fetch_keyed(request))
+            .buffered(concurrency.max(1))
+            .collect()
+            .await
+    }
+
+    /// Same as `fetch_many`, but yields every result as soon as its request completes
+    pub fn fetch_stream<'a, I>(
+        &'a self,
+        requests: I,
+        concurrency: usize,
+    ) -> impl Stream<Item = (String, MarketResult<MarketSeries>)> + 'a
+    where
+        I: IntoIterator<Item = T::Request>,
+        I::IntoIter: 'a,
+    {
+        stream::iter(requests)
+            .map(|request| self.fetch_keyed(request))
+            .buffer_unordered(concurrency.max(1))
+    }
+
+    async fn fetch_keyed(&self, request: T::Request) -> (String, MarketResult<MarketSeries>) {
+        let symbol = self.site.request_symbol(&request).to_string();
+        (symbol, self.fetch(request).await)
+    }
+
+    fn replaying(&self) -> bool {
+        self.cassette
+            .as_ref()
+            .is_some_and(|cassette| *cassette.mode() == CassetteMode::Replay)
+    }
+
+    async fn fetch_once(&self, request: &T::Request) -> MarketResult<MarketSeries> {
+        let url = self.site.create_endpoint(request)?;
+
+        let (status, retry_after, body) = match &self.cassette {
+            Some(cassette) if self.replaying() => {
+                let interaction = cassette.lookup(&url)?;
+                let status = StatusCode::from_u16(interaction.status)
+                    .map_err(|e| MarketError::ParsingError(e.to_string()))?;
+                (status, None, interaction.body)
+            }
+            _ => self.send(url.clone()).await?,
+        };
+
+        if let Some(cassette) = &self.cassette {
+            cassette
+                .record_response(&url, status.as_u16(), &body)
+                .await?;
+        }
 
-        if !response.status().is_success() {
-            let status = response.status();
-            let body = response.text().await.unwrap_or_default();
-            return Err(MarketError::HttpError(format!(
-                "HTTP Error: status code {}, body: {}",
-                status, body
-            )));
+        if !status.is_success() {
+            // the error payload parsed by the publisher is more precise than the status code
+            let error = match self.site.transform_data(body.clone(), request) {
+                Err(MarketError::RateLimited {
+                    publisher,
+                    retry_after: None,
+                }) => MarketError::RateLimited {
+                    publisher,
+                    retry_after,
+                },
+                Err(e) if e.publisher().is_some() => e,
+                _ => MarketError::from_status(
+                    self.site.name(),
+                    status.as_u16(),
+                    retry_after,
+                    body,
+                    self.site.request_symbol(request),
+                ),
+            };
+            return Err(error);
         }
 
+        self.site.transform_data(body, request)
+    }
+
+    /// Sends the request over the network, once the rate limiter allows it
+    async fn send(&self, url: Url) -> MarketResult<(StatusCode, Option<Duration>, String)> {
+        if let Some(limiter) = &self.limiter {
+            limiter.acquire().await?;
+        }
+
+        let response = self.inner.get(url).send().await?;
+        let status = response.status();
+        let retry_after = retry_after(response.headers());
         let body = response.text().await?;
-        self.site.transform_data(body, &request)
+
+        Ok((status, retry_after, body))
+    }
+}
+
+/// The cached series extended with the refreshed bars, `None` when they don't line up with the cached ones
+/// and the whole series has to be fetched again
+fn extend_cached(
+    cached: MarketSeries,
+    last: NaiveDateTime,
+    mut fetched: MarketSeries,
+) -> Option<MarketSeries> {
+    // the narrowed request doesn't reach back to the cached bars, e.g. the last 100 bars
+    // after a long pause, so the bars in between would be missing
+    let before = cached.data.last()?;
+    let after = fetched.data.iter().find(|s| s.datetime == last)?;
+    // a split or a dividend since the last refresh moves the whole adjusted history, the open of
+    // an incomplete bar doesn't change and its adjusted close follows the close by the same factor
+    let factor = |s: &Series| s.adj_close.map(|adj_close| adj_close / s.close);
+    let same_basis = close_enough(before.open, after.open)
+        && match (factor(before), factor(after)) {
+            (Some(before), Some(after)) => close_enough(before, after),
+            (before, after) => before.is_none() && after.is_none(),
+        };
+    let new_actions = fetched
+        .actions
+        .iter()
+        .any(|action| !cached.actions.contains(action));
+    if !same_basis || new_actions {
+        return None;
+    }
+
+    // the last cached bar may have been incomplete, so it is replaced as well
+    fetched.data.retain(|s| s.datetime >= last);
+    // e.g. a series cached by a previous version, with another adjustment
+    cached.merge(fetched, ConflictPolicy::PreferRight).ok()
+}
+
+fn close_enough(a: f64, b: f64) -> bool {
+    (a - b).abs() <= 1e-9 * a.abs().max(b.abs())
+}
+
+/// The error of the last attempt, wrapped when the request was sent more than once
+fn retries_exhausted(attempts: u32, error: MarketError) -> MarketError {
+    if attempts > 1 {
+        MarketError::RetriesExhausted {
+            attempts,
+            source: Box::new(error),
+        }
+    } else {
+        error
     }
 }
 
@@ -51,51 +344,41 @@ pub struct MarketSeries {
     pub symbol: String,
     /// inteval from intraday to monthly
     pub interval: Interval,
+    /// the timezone of the datetimes, exchange-local or UTC depending on the publisher
+    #[serde(default = "utc")]
+    pub timezone: Tz,
     /// the original series downloaded and parsed from publishers
     pub data: Vec<Series>,
+    /// the corporate actions applied by the publisher to the values
+    #[serde(default)]
+    pub adjustment: Adjustment,
+    /// the dividends and splits within the series, when provided by the publisher
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub actions: Vec<CorporateAction>,
+}
+
+pub(crate) fn utc() -> Tz {
+    Tz::UTC
 }
 
 /// Series part of the MarketSeries
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub struct Series {
-    /// the date and time of the stock price
+    /// the date and time of the stock price, in the timezone of the MarketSeries
     pub datetime: NaiveDateTime,
     /// the opening price of the stock for the selected interval
-    pub open: f32,
+    pub open: f64,
     /// the closing price of the stock for the selected interval
-    pub close: f32,
+    pub close: f64,
     /// the highest price of the stock for the selected interval
-    pub high: f32,
+    pub high: f64,
     /// the lowest price of the stock for the selected interval
-    pub low: f32,
+    pub low: f64,
     /// the number of shares traded in the selected interval
     pub volume: f64,
-}
-
-/// The time interval between two data points
-#[derive(Debug, Serialize, Deserialize, Default, Clone)]
-pub enum Interval {
-    /// 1 minute interval
-    Min1,
-    /// 5 minutes interval
-    Min5,
-    /// 15 minutes interval
-    Min15,
-    /// 30 minutes interval
-    Min30,
-    /// 1 hour interval
-    Hour1,
-    /// 2 hours interval
-    Hour2,
-    /// 4 hours interval
-    Hour4,
-    /// daily interval
-    #[default]
-    Daily,
-    /// weekly interval
-    Weekly,
-    /// monthly interval
-    Monthly,
+    /// the closing price adjusted for splits and dividends, when provided by the publisher
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub adj_close: Option<f64>,
 }
 
 impl MarketSeries {
@@ -103,6 +386,7 @@ impl MarketSeries {
         EnhancedMarketSeries {
             symbol: self.symbol,
             interval: self.interval,
+            timezone: self.timezone,
             series: self.data,
             asks: Vec::new(),
             indicators: Default::default(),
@@ -110,12 +394,48 @@ impl MarketSeries {
     }
 }
 
+impl MarketSeries {
+    /// The datetimes of the bars, in the timezone of the series
+    pub fn datetimes(&self) -> impl Iterator<Item = DateTime<Tz>> + '_ {
+        self.data.iter().map(|s| s.datetime_in(self.timezone))
+    }
+
+    /// Convert the datetimes of all the bars to UTC
+    pub fn to_utc(self) -> MarketSeries {
+        self.to_timezone(Tz::UTC)
+    }
+
+    /// Convert the datetimes of all the bars to an IANA timezone, like `chrono_tz::Europe::London`
+    pub fn to_timezone(mut self, timezone: Tz) -> MarketSeries {
+        for series in self.data.iter_mut() {
+            series.datetime = series
+                .datetime_in(self.timezone)
+                .with_timezone(&timezone)
+                .naive_local();
+        }
+        self.timezone = timezone;
+        self
+    }
+}
+
+impl Series {
+    /// The datetime of the bar, given the timezone of its MarketSeries
+    ///
+    /// An ambiguous local time, repeated when the clocks go back, resolves to the earliest one.
+    pub fn datetime_in(&self, timezone: Tz) -> DateTime<Tz> {
+        timezone
+            .from_local_datetime(&self.datetime)
+            .earliest()
+            .unwrap_or_else(|| timezone.from_utc_datetime(&self.datetime))
+    }
+}
+
 impl fmt::Display for MarketSeries {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         writeln!(
             f,
-            "MarketSeries: Symbol = {}, Interval = {}",
-            self.symbol, self.interval
+            "MarketSeries: Symbol = {}, Interval = {}, Timezone = {}",
+            self.symbol, self.interval, self.timezone
         )?;
         for series in &self.data {
             writeln!(f, "  {}", series)?;
@@ -133,51 +453,3 @@ impl fmt::Display for Series {
         )
     }
 }
-
-impl fmt::Display for Interval {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let interval_str = match self {
-            Interval::Min1 => "1 minute",
-            Interval::Min5 => "5 minutes",
-            Interval::Min15 => "15 minutes",
-            Interval::Min30 => "30 minutes",
-            Interval::Hour1 => "1 hour",
-            Interval::Hour2 => "2 hours",
-            Interval::Hour4 => "4 hours",
-            Interval::Daily => "Daily",
-            Interval::Weekly => "Weekly",
-            Interval::Monthly => "Monthly",
-        };
-
-        write!(f, "{}", interval_str)
-    }
-}
-
-/// AlphaVantage response interval: 1min, 5min, 15min, 30min, 60min,
-/// Twelvedata response interval: 1min, 5min, 15min, 30min, 1h, 2h, 4h, 1day, 1week, 1month
-impl std::str::FromStr for Interval {
-    type Err = &'static str;
-
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match s.to_lowercase().as_str() {
-            "1min" => Ok(Interval::Min1),
-            "5min" => Ok(Interval::Min5),
-            "15min" => Ok(Interval::Min15),
-            "30min" => Ok(Interval::Min30),
-            "60min" => Ok(Interval::Hour1),
-            "1h" => Ok(Interval::Hour1),
-            "2h" => Ok(Interval::Hour2),
-            "4h" => Ok(Interval::Hour4),
-            "1day" => Ok(Interval::Daily),
-            "1week" => Ok(Interval::Weekly),
-            "1month" => Ok(Interval::Monthly),
-            _ => Err("Invalid interval string"),
-        }
-    }
-}
-
-impl From<String> for Interval {
-    fn from(s: String) -> Self {
-        Interval::from_str(&s).unwrap_or(Interval::Daily)
-    }
-}
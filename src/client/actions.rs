@@ -0,0 +1,122 @@
+//! Corporate actions (dividends, splits) and the back-adjustment of a MarketSeries
+
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+use super::MarketSeries;
+use crate::errors::MarketError;
+
+/// A dividend or a split, dated on its ex-date in the timezone of the MarketSeries
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum CorporateAction {
+    /// cash dividend per share
+    Dividend { date: NaiveDate, amount: f64 },
+    /// stock split, `ratio` is the number of new shares for one old share, e.g. 4.0 for a 4:1 split
+    Split { date: NaiveDate, ratio: f64 },
+}
+
+impl CorporateAction {
+    pub fn date(&self) -> NaiveDate {
+        match self {
+            CorporateAction::Dividend { date, .. } | CorporateAction::Split { date, .. } => *date,
+        }
+    }
+}
+
+/// The corporate actions already applied by the publisher to the OHLCV values
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Adjustment {
+    /// the prices as traded
+    #[default]
+    Raw,
+    /// the prices and volumes are adjusted for splits, but not for dividends
+    Splits,
+    /// the prices are adjusted for splits and dividends
+    SplitsAndDividends,
+}
+
+/// The variant name, as stored in the Arrow metadata and the SQLite store
+impl fmt::Display for Adjustment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Adjustment::Raw => "Raw",
+            Adjustment::Splits => "Splits",
+            Adjustment::SplitsAndDividends => "SplitsAndDividends",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// Parses the Display output, an unknown name fails instead of being read as `Raw`
+impl FromStr for Adjustment {
+    type Err = MarketError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "Raw" => Ok(Adjustment::Raw),
+            "Splits" => Ok(Adjustment::Splits),
+            "SplitsAndDividends" => Ok(Adjustment::SplitsAndDividends),
+            _ => Err(MarketError::ParsingError(format!(
+                "Unknown adjustment: {}",
+                s
+            ))),
+        }
+    }
+}
+
+impl MarketSeries {
+    /// Back-adjusts the OHLCV values for the corporate actions not yet applied by the publisher
+    ///
+    /// The bars before a split are divided by its ratio, and their volume multiplied by it.
+    /// The bars before a dividend ex-date are multiplied by `1 - dividend / previous close`.
+    /// The latest bars are left as traded, and `adj_close` is set to the adjusted close.
+    pub fn adjust(mut self) -> MarketSeries {
+        let apply_splits = self.adjustment == Adjustment::Raw;
+        let apply_dividends = self.adjustment != Adjustment::SplitsAndDividends;
+
+        // (ex-date, price factor, volume factor), computed on the unadjusted closes
+        let mut factors: Vec<(NaiveDate, f64, f64)> = Vec::new();
+        for action in &self.actions {
+            match *action {
+                CorporateAction::Split { date, ratio } if apply_splits && ratio > 0.0 => {
+                    factors.push((date, 1.0 / ratio, ratio));
+                }
+                CorporateAction::Dividend { date, amount } if apply_dividends => {
+                    let previous_close = self
+                        .data
+                        .iter()
+                        .rev()
+                        .find(|s| s.datetime.date() < date)
+                        .map(|s| s.close);
+                    if let Some(close) = previous_close.filter(|close| *close > amount) {
+                        factors.push((date, 1.0 - amount / close, 1.0));
+                    }
+                }
+                _ => {}
+            }
+        }
+        factors.sort_by_key(|(date, _, _)| std::cmp::Reverse(*date));
+
+        let mut factors = factors.into_iter().peekable();
+        let (mut price_factor, mut volume_factor) = (1.0, 1.0);
+        for series in self.data.iter_mut().rev() {
+            while let Some((_, price, volume)) =
+                factors.next_if(|(date, _, _)| series.datetime.date() < *date)
+            {
+                price_factor *= price;
+                volume_factor *= volume;
+            }
+            series.open *= price_factor;
+            series.high *= price_factor;
+            series.low *= price_factor;
+            series.close *= price_factor;
+            series.volume *= volume_factor;
+            series.adj_close = Some(series.close);
+        }
+
+        self.adjustment = Adjustment::SplitsAndDividends;
+        self
+    }
+}
@@ -0,0 +1,131 @@
+//! Builder for MarketClient, to configure the HTTP transport
+
+use reqwest::{header::HeaderMap, Proxy};
+use std::time::Duration;
+
+use super::{
+    cache::FileCache,
+    cassette::Cassette,
+    rate_limit::{RateLimit, RateLimiter},
+    retry::RetryPolicy,
+    MarketClient, USER_AGENT,
+};
+use crate::{errors::MarketResult, publishers::Publisher};
+
+/// Configures the HTTP transport, retries and rate limiting of a MarketClient
+///
+/// Either provide an existing `reqwest::Client` (e.g. to share a connection pool),
+/// or let the builder create one from the transport settings.
+pub struct MarketClientBuilder<T: Publisher> {
+    site: T,
+    client: Option<reqwest::Client>,
+    transport: reqwest::ClientBuilder,
+    retry: RetryPolicy,
+    rate_limit: Option<RateLimit>,
+    cache: Option<FileCache>,
+    cassette: Option<Cassette>,
+}
+
+impl<T: Publisher> MarketClientBuilder<T> {
+    pub(crate) fn new(site: T) -> Self {
+        let rate_limit = site.rate_limit();
+        MarketClientBuilder {
+            site,
+            client: None,
+            transport: reqwest::Client::builder().user_agent(USER_AGENT),
+            retry: RetryPolicy::none(),
+            rate_limit,
+            cache: None,
+            cassette: None,
+        }
+    }
+
+    /// Use an existing reqwest::Client, the transport settings of this builder are then ignored
+    pub fn with_client(mut self, client: reqwest::Client) -> Self {
+        self.client = Some(client);
+        self
+    }
+
+    /// Timeout for the whole request, from connecting until the response body is read
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.transport = self.transport.timeout(timeout);
+        self
+    }
+
+    /// Timeout for establishing the connection
+    pub fn with_connect_timeout(mut self, timeout: Duration) -> Self {
+        self.transport = self.transport.connect_timeout(timeout);
+        self
+    }
+
+    /// Route the requests through a proxy, can be called multiple times
+    pub fn with_proxy(mut self, proxy: Proxy) -> Self {
+        self.transport = self.transport.proxy(proxy);
+        self
+    }
+
+    /// Headers sent with every request
+    pub fn with_headers(mut self, headers: HeaderMap) -> Self {
+        self.transport = self.transport.default_headers(headers);
+        self
+    }
+
+    /// Override the default user agent: market-data-rust/<version>
+    pub fn with_user_agent(mut self, user_agent: impl Into<String>) -> Self {
+        self.transport = self.transport.user_agent(user_agent.into());
+        self
+    }
+
+    /// Maximum number of idle connections kept per host
+    pub fn with_pool_max_idle_per_host(mut self, max: usize) -> Self {
+        self.transport = self.transport.pool_max_idle_per_host(max);
+        self
+    }
+
+    /// How long an idle connection is kept in the pool
+    pub fn with_pool_idle_timeout(mut self, timeout: Duration) -> Self {
+        self.transport = self.transport.pool_idle_timeout(timeout);
+        self
+    }
+
+    /// Retry transient failures according to the policy
+    pub fn with_retry(mut self, policy: RetryPolicy) -> Self {
+        self.retry = policy;
+        self
+    }
+
+    /// Override the Publisher's default rate limit, `None` disables client-side limiting
+    pub fn with_rate_limit(mut self, limit: Option<RateLimit>) -> Self {
+        self.rate_limit = limit;
+        self
+    }
+
+    /// Cache the fetched series on disk
+    pub fn with_cache(mut self, cache: FileCache) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+
+    /// Record the raw responses to a cassette file, or replay them without network
+    pub fn with_cassette(mut self, cassette: Cassette) -> Self {
+        self.cassette = Some(cassette);
+        self
+    }
+
+    /// Build the MarketClient, fails if the reqwest::Client can't be created
+    pub fn build(self) -> MarketResult<MarketClient<T>> {
+        let inner = match self.client {
+            Some(client) => client,
+            None => self.transport.build()?,
+        };
+
+        Ok(MarketClient {
+            site: self.site,
+            inner,
+            retry: self.retry,
+            limiter: self.rate_limit.map(RateLimiter::new),
+            cache: self.cache,
+            cassette: self.cassette,
+        })
+    }
+}
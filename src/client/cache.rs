@@ -0,0 +1,101 @@
+//! Opt-in on-disk cache for the series fetched by MarketClient
+
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use crate::{client::MarketSeries, errors::MarketResult};
+
+/// Stores every fetched series as a JSON file: `<dir>/<publisher>/<request key>.json`
+///
+/// A cached series younger than `max_age` is served without any network call.
+/// Older entries are refreshed incrementally: only the bars newer than the last cached one are requested,
+/// when the Publisher supports it, and merged into the cached series. The requests for a rolling window,
+/// like the last 100 bars, are sent again as a whole, so the cached series keeps the size of the window.
+/// When the refreshed bars don't line up with the cached ones, after a new split or dividend moved an
+/// adjusted series or with an entry that can't be merged, the whole series is fetched and rewritten.
+#[derive(Debug, Clone)]
+pub struct FileCache {
+    dir: PathBuf,
+    max_age: Option<Duration>,
+}
+
+impl FileCache {
+    /// Cache the series within the given directory, created if missing
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        FileCache {
+            dir: dir.into(),
+            max_age: None,
+        }
+    }
+
+    /// Serve the cached series without refreshing it, if it was updated less than `max_age` ago
+    pub fn with_max_age(mut self, max_age: Duration) -> Self {
+        self.max_age = Some(max_age);
+        self
+    }
+
+    /// The directory holding the cached series
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Remove all the cached series
+    pub async fn clear(&self) -> MarketResult<()> {
+        match tokio::fs::remove_dir_all(&self.dir).await {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        }
+    }
+
+    fn path(&self, publisher: &str, key: &str) -> PathBuf {
+        let file_name: String = key
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.') {
+                    c
+                } else {
+                    '_'
+                }
+            })
+            .collect();
+        self.dir.join(publisher).join(format!("{}.json", file_name))
+    }
+
+    /// Returns the cached series, and whether it is still fresh.
+    /// A missing or unreadable entry is reported as a cache miss.
+    pub(crate) async fn load(&self, publisher: &str, key: &str) -> Option<(MarketSeries, bool)> {
+        let path = self.path(publisher, key);
+        let content = tokio::fs::read_to_string(&path).await.ok()?;
+        let series: MarketSeries = serde_json::from_str(&content).ok()?;
+
+        let fresh = match self.max_age {
+            Some(max_age) => tokio::fs::metadata(&path)
+                .await
+                .and_then(|m| m.modified())
+                .ok()
+                .and_then(|modified| SystemTime::now().duration_since(modified).ok())
+                .is_some_and(|age| age < max_age),
+            None => false,
+        };
+
+        Some((series, fresh))
+    }
+
+    /// Write the series atomically, through a temporary file
+    pub(crate) async fn store(
+        &self,
+        publisher: &str,
+        key: &str,
+        series: &MarketSeries,
+    ) -> MarketResult<()> {
+        let path = self.path(publisher, key);
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+
+        let tmp = path.with_extension("json.tmp");
+        tokio::fs::write(&tmp, serde_json::to_vec(series)?).await?;
+        tokio::fs::rename(&tmp, &path).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,153 @@
+//! Record/replay of the raw HTTP responses, for deterministic offline tests
+
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use url::Url;
+
+use crate::errors::{MarketError, MarketResult};
+
+/// The query parameters holding API keys, removed from the recorded urls
+const SECRET_PARAMS: [&str; 4] = ["token", "apikey", "api_key", "key"];
+
+/// Records the responses received by MarketClient to a JSON file, or replays them without network
+///
+/// The API keys are stripped from the recorded urls, so the cassettes can be committed.
+/// In replay mode a request is matched against the recorded urls, a request that was never recorded
+/// fails with `MarketError::NoRecordedResponse`.
+#[derive(Debug)]
+pub struct Cassette {
+    mode: CassetteMode,
+    interactions: Mutex<Vec<Interaction>>,
+    // serializes the file writes, so the last recorded response is never overwritten by an older snapshot
+    writing: tokio::sync::Mutex<()>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum CassetteMode {
+    /// Send the requests and append the responses to the file
+    Record(PathBuf),
+    /// Serve the recorded responses, nothing is sent over the network
+    Replay,
+}
+
+/// A recorded request/response pair
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Interaction {
+    /// the request url, without the API key
+    pub url: String,
+    /// the HTTP status code of the response
+    pub status: u16,
+    /// the raw response body
+    pub body: String,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct CassetteFile {
+    interactions: Vec<Interaction>,
+}
+
+impl Cassette {
+    /// Record the responses to the file, the interactions already recorded there are kept
+    pub async fn record(path: impl Into<PathBuf>) -> MarketResult<Self> {
+        let path = path.into();
+        let interactions = match tokio::fs::read_to_string(&path).await {
+            Ok(content) => serde_json::from_str::<CassetteFile>(&content)?.interactions,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+            Err(e) => return Err(e.into()),
+        };
+        Ok(Cassette {
+            mode: CassetteMode::Record(path),
+            interactions: Mutex::new(interactions),
+            writing: tokio::sync::Mutex::new(()),
+        })
+    }
+
+    /// Replay the responses recorded in the file
+    pub async fn replay(path: impl AsRef<Path>) -> MarketResult<Self> {
+        Self::replay_from_str(&tokio::fs::read_to_string(path).await?)
+    }
+
+    /// Replay the responses from the content of a cassette file, e.g. embedded with `include_str!`
+    pub fn replay_from_str(content: &str) -> MarketResult<Self> {
+        let file: CassetteFile = serde_json::from_str(content)?;
+        Ok(Cassette {
+            mode: CassetteMode::Replay,
+            interactions: Mutex::new(file.interactions),
+            writing: tokio::sync::Mutex::new(()),
+        })
+    }
+
+    pub fn mode(&self) -> &CassetteMode {
+        &self.mode
+    }
+
+    /// A copy of the recorded interactions
+    pub fn interactions(&self) -> Vec<Interaction> {
+        self.lock().clone()
+    }
+
+    /// Finds the response recorded for the url
+    pub(crate) fn lookup(&self, url: &Url) -> MarketResult<Interaction> {
+        let url = redact(url);
+        self.lock()
+            .iter()
+            .find(|interaction| interaction.url == url)
+            .cloned()
+            .ok_or(MarketError::NoRecordedResponse(url))
+    }
+
+    /// Appends the response and rewrites the cassette file
+    pub(crate) async fn record_response(
+        &self,
+        url: &Url,
+        status: u16,
+        body: &str,
+    ) -> MarketResult<()> {
+        let CassetteMode::Record(path) = &self.mode else {
+            return Ok(());
+        };
+
+        let _writing = self.writing.lock().await;
+        let content = {
+            let url = redact(url);
+            let mut interactions = self.lock();
+            interactions.retain(|interaction| interaction.url != url);
+            interactions.push(Interaction {
+                url,
+                status,
+                body: body.to_string(),
+            });
+            let file = CassetteFile {
+                interactions: interactions.clone(),
+            };
+            serde_json::to_string_pretty(&file)?
+        };
+
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        tokio::fs::write(path, content).await?;
+        Ok(())
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Vec<Interaction>> {
+        self.interactions.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// The url without the query parameters holding API keys
+fn redact(url: &Url) -> String {
+    let pairs: Vec<(String, String)> = url
+        .query_pairs()
+        .filter(|(name, _)| !SECRET_PARAMS.contains(&name.to_lowercase().as_str()))
+        .map(|(name, value)| (name.into_owned(), value.into_owned()))
+        .collect();
+
+    let mut url = url.clone();
+    url.set_query(None);
+    if !pairs.is_empty() {
+        url.query_pairs_mut().extend_pairs(pairs);
+    }
+    url.to_string()
+}
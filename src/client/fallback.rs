@@ -0,0 +1,119 @@
+//! Composite client that falls back across an ordered list of publishers
+
+use futures::future::BoxFuture;
+
+use crate::{
+    client::{MarketClient, MarketSeries},
+    errors::{MarketError, MarketResult},
+    publishers::{Capabilities, Publisher, SeriesRequest},
+};
+
+/// A MarketClient of any Publisher, able to serve a provider-neutral request
+pub trait SeriesSource: Send + Sync {
+    /// Short name of the publisher, like "finnhub"
+    fn name(&self) -> &'static str;
+
+    /// What the publisher provides
+    fn capabilities(&self) -> Capabilities;
+
+    /// Fetches the bars described by the provider-neutral request
+    fn fetch_series<'a>(
+        &'a self,
+        request: &'a SeriesRequest,
+    ) -> BoxFuture<'a, MarketResult<MarketSeries>>;
+}
+
+impl<T> SeriesSource for MarketClient<T>
+where
+    T: Publisher + Send + Sync,
+    T::Request: Send + Sync,
+{
+    fn name(&self) -> &'static str {
+        self.site.name()
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        self.site.capabilities()
+    }
+
+    fn fetch_series<'a>(
+        &'a self,
+        request: &'a SeriesRequest,
+    ) -> BoxFuture<'a, MarketResult<MarketSeries>> {
+        Box::pin(MarketClient::fetch_series(self, request))
+    }
+}
+
+/// Tries an ordered list of publishers, the first one returning the bars wins
+///
+/// A publisher that doesn't support the requested interval is skipped without sending any request,
+/// one that fails, e.g. with an HTTP error or an exhausted quota, is skipped as well and the next one is tried.
+#[derive(Default)]
+pub struct FallbackClient {
+    sources: Vec<Box<dyn SeriesSource>>,
+}
+
+/// The series returned by a FallbackClient, with the publisher that served it
+#[derive(Debug)]
+pub struct FallbackSeries {
+    /// the name of the publisher that served the series
+    pub publisher: &'static str,
+    /// the fetched series
+    pub series: MarketSeries,
+    /// the publishers tried before, with the reason they were skipped
+    pub skipped: Vec<(&'static str, MarketError)>,
+}
+
+impl FallbackClient {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a publisher at the end of the chain
+    pub fn with(mut self, source: impl SeriesSource + 'static) -> Self {
+        self.sources.push(Box::new(source));
+        self
+    }
+
+    /// The names of the publishers, in the order they are tried
+    pub fn publishers(&self) -> Vec<&'static str> {
+        self.sources.iter().map(|source| source.name()).collect()
+    }
+
+    /// Fetches the bars from the first publisher that succeeds
+    pub async fn fetch(&self, request: &SeriesRequest) -> MarketResult<FallbackSeries> {
+        let mut skipped = Vec::new();
+
+        for source in &self.sources {
+            if !source.capabilities().supports(&request.interval) {
+                skipped.push((
+                    source.name(),
+                    MarketError::UnsuportedInterval(format!(
+                        "{} interval is not supported by {}",
+                        request.interval,
+                        source.name()
+                    )),
+                ));
+                continue;
+            }
+
+            match source.fetch_series(request).await {
+                Ok(series) => {
+                    return Ok(FallbackSeries {
+                        publisher: source.name(),
+                        series,
+                        skipped,
+                    })
+                }
+                Err(e) => skipped.push((source.name(), e)),
+            }
+        }
+
+        Err(MarketError::AllPublishersFailed(
+            skipped
+                .into_iter()
+                .map(|(name, e)| (name.to_string(), e))
+                .collect(),
+        ))
+    }
+}
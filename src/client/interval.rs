@@ -0,0 +1,278 @@
+//! The time interval between two bars, any multiple of a calendar unit
+
+use chrono::{Datelike, Duration, Months, NaiveDate, NaiveDateTime, NaiveTime};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+use std::str::FromStr;
+
+use crate::errors::{MarketError, MarketResult};
+
+/// The time interval between two data points, like 5 minutes or 1 week
+///
+/// The usual intervals are available as constants (`Interval::Min5`, `Interval::Daily`, ...),
+/// any other multiple is created with `Interval::new`. Minutes that add up to whole hours are
+/// stored as hours, so `"60min"` and `"1h"` parse to the same `Interval::Hour1`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Interval {
+    multiplier: u32,
+    unit: IntervalUnit,
+}
+
+/// The calendar unit of an Interval
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum IntervalUnit {
+    Minute,
+    Hour,
+    Day,
+    Week,
+    Month,
+    Quarter,
+    Year,
+}
+
+#[allow(non_upper_case_globals)]
+impl Interval {
+    /// 1 minute interval
+    pub const Min1: Interval = Interval::of(1, IntervalUnit::Minute);
+    /// 5 minutes interval
+    pub const Min5: Interval = Interval::of(5, IntervalUnit::Minute);
+    /// 15 minutes interval
+    pub const Min15: Interval = Interval::of(15, IntervalUnit::Minute);
+    /// 30 minutes interval
+    pub const Min30: Interval = Interval::of(30, IntervalUnit::Minute);
+    /// 1 hour interval
+    pub const Hour1: Interval = Interval::of(1, IntervalUnit::Hour);
+    /// 2 hours interval
+    pub const Hour2: Interval = Interval::of(2, IntervalUnit::Hour);
+    /// 4 hours interval
+    pub const Hour4: Interval = Interval::of(4, IntervalUnit::Hour);
+    /// daily interval
+    pub const Daily: Interval = Interval::of(1, IntervalUnit::Day);
+    /// weekly interval
+    pub const Weekly: Interval = Interval::of(1, IntervalUnit::Week);
+    /// monthly interval
+    pub const Monthly: Interval = Interval::of(1, IntervalUnit::Month);
+    /// quarterly interval
+    pub const Quarterly: Interval = Interval::of(1, IntervalUnit::Quarter);
+    /// yearly interval
+    pub const Yearly: Interval = Interval::of(1, IntervalUnit::Year);
+
+    /// Any multiple of a unit, fails if the multiplier is 0 or too large to be counted in months or days
+    pub fn new(multiplier: u32, unit: IntervalUnit) -> MarketResult<Self> {
+        if multiplier == 0 {
+            return Err(MarketError::InvalidInterval(
+                "the multiplier must be positive".to_string(),
+            ));
+        }
+        let interval = Interval::of(multiplier, unit);
+        if interval.checked_nominal_duration().is_none() {
+            return Err(MarketError::InvalidInterval(format!(
+                "the multiplier {} is too large",
+                multiplier
+            )));
+        }
+        Ok(interval)
+    }
+
+    pub(crate) const fn of(multiplier: u32, unit: IntervalUnit) -> Self {
+        match unit {
+            IntervalUnit::Minute if multiplier.is_multiple_of(60) => Interval {
+                multiplier: multiplier / 60,
+                unit: IntervalUnit::Hour,
+            },
+            _ => Interval { multiplier, unit },
+        }
+    }
+
+    pub fn multiplier(&self) -> u32 {
+        self.multiplier
+    }
+
+    pub fn unit(&self) -> IntervalUnit {
+        self.unit
+    }
+
+    /// Whether the bars are shorter than a day
+    pub fn is_intraday(&self) -> bool {
+        matches!(self.unit, IntervalUnit::Minute | IntervalUnit::Hour)
+    }
+
+    /// The fixed length of the interval, `None` for months, quarters and years which vary in length
+    pub fn to_duration(&self) -> Option<Duration> {
+        let multiplier = self.multiplier as i64;
+        match self.unit {
+            IntervalUnit::Minute => Some(Duration::minutes(multiplier)),
+            IntervalUnit::Hour => Some(Duration::hours(multiplier)),
+            IntervalUnit::Day => Some(Duration::days(multiplier)),
+            IntervalUnit::Week => Some(Duration::weeks(multiplier)),
+            IntervalUnit::Month | IntervalUnit::Quarter | IntervalUnit::Year => None,
+        }
+    }
+
+    /// The number of months of a month, quarter or year based interval
+    pub(crate) fn months(&self) -> Option<u32> {
+        match self.unit {
+            IntervalUnit::Month => Some(self.multiplier),
+            IntervalUnit::Quarter => self.multiplier.checked_mul(3),
+            IntervalUnit::Year => self.multiplier.checked_mul(12),
+            _ => None,
+        }
+    }
+
+    /// The datetime one interval later, the day of the month is clamped for month based intervals
+    pub(crate) fn advance(&self, datetime: NaiveDateTime) -> Option<NaiveDateTime> {
+        match (self.to_duration(), self.months()) {
+            (Some(duration), _) => datetime.checked_add_signed(duration),
+            (None, Some(months)) => datetime.checked_add_months(Months::new(months)),
+            (None, None) => None,
+        }
+    }
+
+    /// The start of the calendar period holding the datetime: the day, the ISO week or the month
+    /// of the daily and longer bars, the datetime itself for the intraday ones
+    ///
+    /// The daily and longer bars are compared by period rather than by datetime, as their time of day moves:
+    /// midnight in New York is 04:00 or 05:00 UTC depending on DST, and a week or a month
+    /// may be labelled by its first or last trading day.
+    pub(crate) fn period(&self, datetime: NaiveDateTime) -> NaiveDateTime {
+        let date = datetime.date();
+        let start = match self.unit {
+            IntervalUnit::Minute | IntervalUnit::Hour => return datetime,
+            IntervalUnit::Day => date,
+            IntervalUnit::Week => date.week(chrono::Weekday::Mon).first_day(),
+            IntervalUnit::Month | IntervalUnit::Quarter | IntervalUnit::Year => {
+                NaiveDate::from_ymd_opt(date.year(), date.month(), 1).unwrap_or(date)
+            }
+        };
+        start.and_time(NaiveTime::MIN)
+    }
+
+    /// The approximate length of the interval, with 31 days per month, to compare the intervals
+    pub(crate) fn nominal_duration(&self) -> Duration {
+        self.checked_nominal_duration().unwrap_or_default()
+    }
+
+    /// `None` if the months of the interval overflow
+    fn checked_nominal_duration(&self) -> Option<Duration> {
+        match self.to_duration() {
+            Some(duration) => Some(duration),
+            None => Duration::try_days(31 * self.months()? as i64),
+        }
+    }
+}
+
+impl Default for Interval {
+    fn default() -> Self {
+        Interval::Daily
+    }
+}
+
+impl fmt::Display for Interval {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (name, single) = match self.unit {
+            IntervalUnit::Minute => ("minute", None),
+            IntervalUnit::Hour => ("hour", None),
+            IntervalUnit::Day => ("day", Some("Daily")),
+            IntervalUnit::Week => ("week", Some("Weekly")),
+            IntervalUnit::Month => ("month", Some("Monthly")),
+            IntervalUnit::Quarter => ("quarter", Some("Quarterly")),
+            IntervalUnit::Year => ("year", Some("Yearly")),
+        };
+        match (self.multiplier, single) {
+            (1, Some(single)) => write!(f, "{}", single),
+            (1, None) => write!(f, "1 {}", name),
+            (n, _) => write!(f, "{} {}s", n, name),
+        }
+    }
+}
+
+/// Parses the notation of every publisher, and the Display output:
+/// - AlphaVantage: 1min, 5min, 15min, 30min, 60min, daily, weekly, monthly
+/// - Twelvedata: 1min, 45min, 1h, 8h, 1day, 1week, 1month
+/// - Yahoo Finance: 1m, 90m, 1h, 1d, 5d, 1wk, 1mo, 3mo
+/// - Finnhub: 1, 5, 15, 30, 60, D, W, M
+/// - Massive: a multiplier and a timespan, like "5 minute" or "1 quarter"
+impl FromStr for Interval {
+    type Err = MarketError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || MarketError::InvalidInterval(s.to_string());
+        let trimmed = s.trim();
+        if trimmed.is_empty() {
+            return Err(invalid());
+        }
+
+        // the variant names of the former Interval enum, kept for the series serialized with it
+        let legacy = match trimmed {
+            "Min1" => Some(Interval::Min1),
+            "Min5" => Some(Interval::Min5),
+            "Min15" => Some(Interval::Min15),
+            "Min30" => Some(Interval::Min30),
+            "Hour1" => Some(Interval::Hour1),
+            "Hour2" => Some(Interval::Hour2),
+            "Hour4" => Some(Interval::Hour4),
+            _ => None,
+        };
+        if let Some(interval) = legacy {
+            return Ok(interval);
+        }
+
+        let split = trimmed
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(trimmed.len());
+        let (digits, unit) = trimmed.split_at(split);
+        let multiplier = match digits {
+            "" => 1,
+            digits => digits.parse().map_err(|_| invalid())?,
+        };
+
+        let unit = match unit.trim() {
+            // Finnhub resolutions, a bare number of minutes
+            "" => IntervalUnit::Minute,
+            // the upper case M stands for month, the lower case m for minute
+            "M" => IntervalUnit::Month,
+            "m" => IntervalUnit::Minute,
+            unit => match unit.to_lowercase().as_str() {
+                "min" | "mins" | "minute" | "minutes" => IntervalUnit::Minute,
+                "h" | "hr" | "hour" | "hours" => IntervalUnit::Hour,
+                "d" | "day" | "days" | "daily" => IntervalUnit::Day,
+                "w" | "wk" | "week" | "weeks" | "weekly" => IntervalUnit::Week,
+                "mo" | "month" | "months" | "monthly" => IntervalUnit::Month,
+                "q" | "quarter" | "quarters" | "quarterly" => IntervalUnit::Quarter,
+                "y" | "year" | "years" | "yearly" => IntervalUnit::Year,
+                _ => return Err(invalid()),
+            },
+        };
+
+        Interval::new(multiplier, unit).map_err(|_| invalid())
+    }
+}
+
+impl TryFrom<&str> for Interval {
+    type Error = MarketError;
+
+    fn try_from(s: &str) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl TryFrom<String> for Interval {
+    type Error = MarketError;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl Serialize for Interval {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Interval {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
@@ -0,0 +1,134 @@
+//! Client-side token bucket rate limiter used by MarketClient
+
+use chrono::{DateTime, NaiveDate, Utc};
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::time::Instant;
+
+use crate::errors::{MarketError, MarketResult};
+
+/// Client-side request quota, the default one is provided by each Publisher
+///
+/// The short-term limit is enforced with a token bucket: requests wait until a token is available.
+/// The daily quota is counted per UTC day: once it is used up, requests fail with `MarketError::QuotaExhausted`.
+/// Every request sent counts, including the retries of a `RetryPolicy`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RateLimit {
+    window: Option<(u32, Duration)>,
+    daily: Option<u32>,
+}
+
+impl RateLimit {
+    /// Allow `requests` requests for every `per` time window
+    pub fn new(requests: u32, per: Duration) -> Self {
+        RateLimit {
+            window: Some((requests.max(1), per)),
+            daily: None,
+        }
+    }
+
+    /// Allow `requests` requests per minute
+    pub fn per_minute(requests: u32) -> Self {
+        Self::new(requests, Duration::from_secs(60))
+    }
+
+    /// Allow `requests` requests per UTC day, without a short-term limit
+    pub fn daily(requests: u32) -> Self {
+        RateLimit {
+            window: None,
+            daily: Some(requests),
+        }
+    }
+
+    /// Add a daily quota on top of the short-term limit
+    pub fn with_daily_quota(mut self, requests: u32) -> Self {
+        self.daily = Some(requests);
+        self
+    }
+}
+
+/// Token bucket + daily counter, shared by all the requests of a MarketClient
+///
+/// The time is measured with the tokio clock, the UTC day is derived from it
+/// so that a paused clock drives both the refill and the daily reset.
+#[derive(Debug)]
+pub(crate) struct RateLimiter {
+    limit: RateLimit,
+    created: (DateTime<Utc>, Instant),
+    state: Mutex<State>,
+}
+
+#[derive(Debug)]
+struct State {
+    tokens: f64,
+    last_refill: Instant,
+    day: NaiveDate,
+    used_today: u32,
+}
+
+impl RateLimiter {
+    pub(crate) fn new(limit: RateLimit) -> Self {
+        let tokens = limit.window.map(|(n, _)| n as f64).unwrap_or_default();
+        let created = (Utc::now(), Instant::now());
+        RateLimiter {
+            limit,
+            created,
+            state: Mutex::new(State {
+                tokens,
+                last_refill: created.1,
+                day: created.0.date_naive(),
+                used_today: 0,
+            }),
+        }
+    }
+
+    fn today(&self) -> NaiveDate {
+        let (utc, instant) = self.created;
+        (utc + instant.elapsed()).date_naive()
+    }
+
+    /// Wait until a request can be sent, or fail if the daily quota is used up
+    pub(crate) async fn acquire(&self) -> MarketResult<()> {
+        loop {
+            let wait = {
+                let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+
+                let today = self.today();
+                if state.day != today {
+                    state.day = today;
+                    state.used_today = 0;
+                }
+
+                if let Some(daily) = self.limit.daily {
+                    if state.used_today >= daily {
+                        return Err(MarketError::QuotaExhausted { limit: daily });
+                    }
+                }
+
+                match self.limit.window {
+                    None => {
+                        state.used_today += 1;
+                        return Ok(());
+                    }
+                    Some((requests, per)) => {
+                        let rate = requests as f64 / per.as_secs_f64();
+                        let now = Instant::now();
+                        let elapsed = now.duration_since(state.last_refill).as_secs_f64();
+                        state.tokens = (state.tokens + elapsed * rate).min(requests as f64);
+                        state.last_refill = now;
+
+                        if state.tokens >= 1.0 {
+                            state.tokens -= 1.0;
+                            state.used_today += 1;
+                            return Ok(());
+                        }
+
+                        Duration::from_secs_f64((1.0 - state.tokens) / rate)
+                    }
+                }
+            };
+
+            tokio::time::sleep(wait).await;
+        }
+    }
+}
@@ -0,0 +1,149 @@
+//! Retry policy with exponential backoff used by MarketClient
+
+use chrono::{DateTime, Utc};
+use reqwest::header::HeaderMap;
+use std::time::Duration;
+
+use crate::errors::MarketError;
+
+/// Controls how MarketClient retries a request that failed with a transient error
+///
+/// The delay before attempt `n + 1` is `base_delay * 2^(n - 1)`, capped at `max_delay`.
+/// When jitter is enabled the delay is randomized between half and the full computed value.
+/// A `Retry-After` header sent by the server takes precedence over the computed delay, when it is longer
+/// than `max_delay` the request is not retried and fails with the rate limit error.
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    max_attempts: u32,
+    base_delay: Duration,
+    max_delay: Duration,
+    jitter: bool,
+    retry_statuses: Vec<u16>,
+    retry_on_timeout: bool,
+    retry_on_connect: bool,
+}
+
+impl Default for RetryPolicy {
+    /// 3 attempts, 500ms base delay, 30s max delay, jitter enabled,
+    /// retries on 429, 500, 502, 503, 504, timeouts and connection errors
+    fn default() -> Self {
+        RetryPolicy {
+            max_attempts: 3,
+            base_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(30),
+            jitter: true,
+            retry_statuses: vec![429, 500, 502, 503, 504],
+            retry_on_timeout: true,
+            retry_on_connect: true,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// A policy that never retries, every request is sent exactly once
+    pub fn none() -> Self {
+        RetryPolicy {
+            max_attempts: 1,
+            ..Default::default()
+        }
+    }
+
+    /// The total number of attempts, including the first one (minimum 1)
+    pub fn with_max_attempts(mut self, max_attempts: u32) -> Self {
+        self.max_attempts = max_attempts.max(1);
+        self
+    }
+
+    /// The delay before the first retry, doubled on every following retry
+    pub fn with_base_delay(mut self, base_delay: Duration) -> Self {
+        self.base_delay = base_delay;
+        self
+    }
+
+    /// The upper bound of the delay between two attempts, a longer `Retry-After` is not waited for
+    pub fn with_max_delay(mut self, max_delay: Duration) -> Self {
+        self.max_delay = max_delay;
+        self
+    }
+
+    /// Randomize the delay between half and the full computed value
+    pub fn with_jitter(mut self, jitter: bool) -> Self {
+        self.jitter = jitter;
+        self
+    }
+
+    /// The HTTP status codes that are considered transient
+    pub fn with_retry_statuses(mut self, statuses: impl IntoIterator<Item = u16>) -> Self {
+        self.retry_statuses = statuses.into_iter().collect();
+        self
+    }
+
+    /// Retry when the request timed out
+    pub fn with_retry_on_timeout(mut self, retry: bool) -> Self {
+        self.retry_on_timeout = retry;
+        self
+    }
+
+    /// Retry when the connection to the server could not be established
+    pub fn with_retry_on_connect(mut self, retry: bool) -> Self {
+        self.retry_on_connect = retry;
+        self
+    }
+
+    /// The total number of attempts, including the first one
+    pub fn max_attempts(&self) -> u32 {
+        self.max_attempts
+    }
+
+    /// Whether the error is transient according to the policy
+    pub(crate) fn is_retryable(&self, error: &MarketError) -> bool {
+        match error {
+            MarketError::RetriveDataError(e) => {
+                (self.retry_on_timeout && e.is_timeout())
+                    || (self.retry_on_connect && e.is_connect())
+            }
+            MarketError::RateLimited { .. } => self.retry_statuses.contains(&429),
+            MarketError::Http { status, .. } => self.retry_statuses.contains(status),
+            _ => false,
+        }
+    }
+
+    /// The delay to wait after the given failed attempt (1-based), `None` when `Retry-After` exceeds `max_delay`
+    pub fn delay(&self, attempt: u32, retry_after: Option<Duration>) -> Option<Duration> {
+        if let Some(retry_after) = retry_after {
+            return (retry_after <= self.max_delay).then_some(retry_after);
+        }
+
+        let exponent = attempt.saturating_sub(1).min(31);
+        let delay = self
+            .base_delay
+            .saturating_mul(1u32 << exponent)
+            .min(self.max_delay);
+
+        if self.jitter {
+            let half = delay / 2;
+            Some(half + Duration::from_millis(fastrand::u64(0..=half.as_millis() as u64)))
+        } else {
+            Some(delay)
+        }
+    }
+}
+
+/// Parse the `Retry-After` header, given either as seconds or as an HTTP date
+pub(crate) fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    let value = headers
+        .get(reqwest::header::RETRY_AFTER)?
+        .to_str()
+        .ok()?
+        .trim();
+
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+
+    let date = DateTime::parse_from_rfc2822(value).ok()?;
+    (date.with_timezone(&Utc) - Utc::now())
+        .to_std()
+        .ok()
+        .or(Some(Duration::ZERO))
+}
@@ -1,3 +1,4 @@
+use std::time::Duration;
 use thiserror::Error;
 
 pub type MarketResult<T> = std::result::Result<T, MarketError>;
@@ -14,8 +15,42 @@ pub enum MarketError {
     #[error("Unable to deserialize: {0}")]
     UnableToDeserialize(#[from] serde_json::error::Error),
 
-    #[error("Http error: {0}")]
-    HttpError(String),
+    #[error("I/O error: {0}")]
+    IoError(#[from] std::io::Error),
+
+    #[error("{publisher}: rate limited{}", after(.retry_after))]
+    RateLimited {
+        publisher: &'static str,
+        retry_after: Option<Duration>,
+    },
+
+    #[error("{publisher}: unauthorized, check the API key: {message}")]
+    Unauthorized {
+        publisher: &'static str,
+        message: String,
+    },
+
+    #[error("{publisher}: symbol not found: {symbol}")]
+    SymbolNotFound {
+        publisher: &'static str,
+        symbol: String,
+    },
+
+    #[error("{publisher}: HTTP error, status code {status}, body: {body}")]
+    Http {
+        publisher: &'static str,
+        status: u16,
+        body: String,
+    },
+
+    #[error("{publisher}: no data returned")]
+    NoData { publisher: &'static str },
+
+    #[error("{publisher}: {message}")]
+    ProviderMessage {
+        publisher: &'static str,
+        message: String,
+    },
 
     #[error("Problem with downloaded data: {0}")]
     DownloadedData(String),
@@ -26,6 +61,102 @@ pub enum MarketError {
     #[error("Unable to write to: {0}")]
     ToWriter(String),
 
+    #[error("Request failed after {attempts} attempts: {source}")]
+    RetriesExhausted {
+        attempts: u32,
+        source: Box<MarketError>,
+    },
+
+    #[error("Daily quota of {limit} requests is exhausted")]
+    QuotaExhausted { limit: u32 },
+
+    #[error("No recorded response for: {0}")]
+    NoRecordedResponse(String),
+
+    #[error("All publishers failed: {}", failures(.0))]
+    AllPublishersFailed(Vec<(String, MarketError)>),
+
     #[error("Unsuported Interval for selected publisher: {0}")]
     UnsuportedInterval(String),
+
+    #[error("Invalid interval: {0}")]
+    InvalidInterval(String),
+
+    #[error("Unable to resample: {0}")]
+    ResampleError(String),
+
+    #[error("Unable to merge: {0}")]
+    MergeError(String),
+
+    #[error("Storage error: {0}")]
+    StorageError(String),
+}
+
+impl MarketError {
+    /// The publisher that returned the error, for the errors reported by a publisher
+    pub fn publisher(&self) -> Option<&'static str> {
+        match self {
+            MarketError::RateLimited { publisher, .. }
+            | MarketError::Unauthorized { publisher, .. }
+            | MarketError::SymbolNotFound { publisher, .. }
+            | MarketError::Http { publisher, .. }
+            | MarketError::NoData { publisher }
+            | MarketError::ProviderMessage { publisher, .. } => Some(publisher),
+            MarketError::RetriesExhausted { source, .. } => source.publisher(),
+            _ => None,
+        }
+    }
+
+    /// How long the publisher asked to wait before the next request
+    pub fn retry_after(&self) -> Option<Duration> {
+        match self {
+            MarketError::RateLimited { retry_after, .. } => *retry_after,
+            MarketError::RetriesExhausted { source, .. } => source.retry_after(),
+            _ => None,
+        }
+    }
+
+    /// Maps an unsuccessful HTTP status code, when the body has no error payload known by the publisher
+    pub(crate) fn from_status(
+        publisher: &'static str,
+        status: u16,
+        retry_after: Option<Duration>,
+        body: String,
+        symbol: &str,
+    ) -> Self {
+        match status {
+            429 => MarketError::RateLimited {
+                publisher,
+                retry_after,
+            },
+            401 | 403 => MarketError::Unauthorized {
+                publisher,
+                message: body,
+            },
+            404 => MarketError::SymbolNotFound {
+                publisher,
+                symbol: symbol.to_string(),
+            },
+            _ => MarketError::Http {
+                publisher,
+                status,
+                body,
+            },
+        }
+    }
+}
+
+fn after(retry_after: &Option<Duration>) -> String {
+    match retry_after {
+        Some(duration) => format!(", retry after {}s", duration.as_secs()),
+        None => String::new(),
+    }
+}
+
+fn failures(errors: &[(String, MarketError)]) -> String {
+    errors
+        .iter()
+        .map(|(publisher, e)| format!("{}: {}", publisher, e))
+        .collect::<Vec<_>>()
+        .join("; ")
 }
@@ -1,16 +1,10 @@
-use crate::{Interval, Series};
+use crate::{series::columns::SeriesColumns, Interval, Series};
+use chrono_tz::Tz;
 use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, VecDeque};
 use std::fmt;
 
-use self::{
-    bollinger::calculate_bollinger_bands,
-    ema::{calculate_ema, calculate_ema_slice},
-    macd::calculate_macd,
-    rsi::calculate_rsi,
-    sma::calculate_sma,
-    stochastic::calculate_stochastic,
-};
+use self::ema::calculate_ema;
 
 pub(crate) mod bollinger;
 pub(crate) mod ema;
@@ -20,7 +14,7 @@ pub(crate) mod sma;
 pub(crate) mod stochastic;
 
 /// Type alias for indicators that return three values (e.g., MACD, Bollinger Bands)
-pub type TripleIndicatorData = (VecDeque<f32>, VecDeque<f32>, VecDeque<f32>);
+pub type TripleIndicatorData = (VecDeque<f64>, VecDeque<f64>, VecDeque<f64>);
 
 /// Holds the MarketSeries + the calculation for the supported indicators
 #[derive(Debug, Serialize, Deserialize)]
@@ -29,6 +23,9 @@ pub struct EnhancedMarketSeries {
     pub symbol: String,
     /// inteval from intraday to monthly
     pub interval: Interval,
+    /// the timezone of the datetimes
+    #[serde(default = "crate::client::utc")]
+    pub timezone: Tz,
     /// the original series downloaded and parsed from publishers
     pub series: Vec<Series>,
     /// the request for technical indicators
@@ -51,13 +48,13 @@ pub enum Ask {
 #[derive(Debug, Default, Serialize, Deserialize)]
 pub struct Indicators {
     /// Simple Moving Average
-    pub sma: HashMap<String, VecDeque<f32>>,
+    pub sma: HashMap<String, VecDeque<f64>>,
     /// Exponential Moving Average
-    pub ema: HashMap<String, VecDeque<f32>>,
+    pub ema: HashMap<String, VecDeque<f64>>,
     /// Relative Strength Index
-    pub rsi: HashMap<String, VecDeque<f32>>,
+    pub rsi: HashMap<String, VecDeque<f64>>,
     ///  Stochastic Oscillator
-    pub stochastic: HashMap<String, VecDeque<f32>>,
+    pub stochastic: HashMap<String, VecDeque<f64>>,
     /// Moving average convergence/divergence (MACD)
     pub macd: HashMap<String, TripleIndicatorData>,
     /// Bollinger Band (BB)
@@ -103,32 +100,32 @@ impl EnhancedMarketSeries {
 
     /// Calculate the indicators and populate within the EnhancedMarketSeries struct
     pub fn calculate(mut self) -> Self {
-        let series = self.series.clone();
+        let columns = SeriesColumns::prices(&self.series);
         for ind in self.asks.iter() {
             match ind {
                 Ask::Sma(period) => {
-                    let calc_sma = calculate_sma(&series, *period);
+                    let calc_sma = columns.sma(*period);
                     self.indicators
                         .sma
                         .insert(format!("SMA {}", period), calc_sma);
                 }
 
                 Ask::Ema(period) => {
-                    let calc_ema = calculate_ema(&series, *period);
+                    let calc_ema = columns.ema(*period);
                     self.indicators
                         .ema
                         .insert(format!("EMA {}", period), calc_ema);
                 }
 
                 Ask::Rsi(period) => {
-                    let calc_rsi = calculate_rsi(&series, *period);
+                    let calc_rsi = columns.rsi(*period);
                     self.indicators
                         .rsi
                         .insert(format!("RSI {}", period), calc_rsi);
                 }
 
                 Ask::Stochastic(period) => {
-                    let calc_stoch = calculate_stochastic(&series, *period);
+                    let calc_stoch = columns.stochastic(*period);
                     self.indicators
                         .stochastic
                         .insert(format!("STO {}", period), calc_stoch);
@@ -136,7 +133,7 @@ impl EnhancedMarketSeries {
 
                 Ask::Macd(fast, slow, signal) => {
                     let (calc_macd, calc_signal, calc_histogram) =
-                        calculate_macd(&series, *fast, *slow, *signal);
+                        columns.macd(*fast, *slow, *signal);
 
                     self.indicators.macd.insert(
                         format!("MACD ({}, {}, {})", fast, slow, signal),
@@ -145,7 +142,7 @@ impl EnhancedMarketSeries {
                 }
                 Ask::Bb(period, std_dev) => {
                     let (upper_band, mid_band, lower_band) =
-                        calculate_bollinger_bands(&series, *period, *std_dev);
+                        columns.bollinger_bands(*period, *std_dev);
 
                     self.indicators.bb.insert(
                         format!("BB ({}, {})", period, std_dev),
@@ -157,6 +154,110 @@ impl EnhancedMarketSeries {
 
         self
     }
+
+    /// The computed indicators as columns aligned with the series, in the order they were asked for
+    ///
+    /// The three values indicators are split into one column each, named like "MACD (12, 26, 9)/signal".
+    pub(crate) fn columns(&self) -> Vec<IndicatorColumn<'_>> {
+        let mut columns: Vec<IndicatorColumn> = Vec::new();
+        for ask in &self.asks {
+            match *ask {
+                Ask::Sma(period) => {
+                    let name = format!("SMA {}", period);
+                    push_column(
+                        &mut columns,
+                        name.clone(),
+                        self.indicators.sma.get(&name),
+                        period.saturating_sub(1),
+                    );
+                }
+                Ask::Ema(period) => {
+                    let name = format!("EMA {}", period);
+                    push_column(
+                        &mut columns,
+                        name.clone(),
+                        self.indicators.ema.get(&name),
+                        0,
+                    );
+                }
+                Ask::Rsi(period) => {
+                    let name = format!("RSI {}", period);
+                    push_column(
+                        &mut columns,
+                        name.clone(),
+                        self.indicators.rsi.get(&name),
+                        period,
+                    );
+                }
+                Ask::Stochastic(period) => {
+                    let name = format!("STO {}", period);
+                    push_column(
+                        &mut columns,
+                        name.clone(),
+                        self.indicators.stochastic.get(&name),
+                        period,
+                    );
+                }
+                Ask::Macd(fast, slow, signal) => {
+                    let name = format!("MACD ({}, {}, {})", fast, slow, signal);
+                    if let Some((macd, signal, histogram)) = self.indicators.macd.get(&name) {
+                        push_column(&mut columns, format!("{}/macd", name), Some(macd), 0);
+                        push_column(&mut columns, format!("{}/signal", name), Some(signal), 0);
+                        push_column(
+                            &mut columns,
+                            format!("{}/histogram", name),
+                            Some(histogram),
+                            0,
+                        );
+                    }
+                }
+                Ask::Bb(period, std_dev) => {
+                    let name = format!("BB ({}, {})", period, std_dev);
+                    if let Some((upper, middle, lower)) = self.indicators.bb.get(&name) {
+                        push_column(&mut columns, format!("{}/upper", name), Some(upper), period);
+                        push_column(&mut columns, format!("{}/middle", name), Some(middle), 0);
+                        push_column(&mut columns, format!("{}/lower", name), Some(lower), period);
+                    }
+                }
+            }
+        }
+        columns
+    }
+}
+
+fn push_column<'a>(
+    columns: &mut Vec<IndicatorColumn<'a>>,
+    name: String,
+    values: Option<&'a VecDeque<f64>>,
+    warm_up: usize,
+) {
+    if let Some(values) = values {
+        // the same indicator may have been asked for twice
+        if !columns.iter().any(|column| column.name == name) {
+            columns.push(IndicatorColumn {
+                name,
+                values,
+                warm_up,
+            });
+        }
+    }
+}
+
+/// A computed indicator, with the number of leading warm-up rows that hold a placeholder instead of a value
+pub(crate) struct IndicatorColumn<'a> {
+    pub(crate) name: String,
+    pub(crate) values: &'a VecDeque<f64>,
+    pub(crate) warm_up: usize,
+}
+
+impl IndicatorColumn<'_> {
+    /// The value of a row, `None` during the warm-up or if the series was too short for the indicator
+    pub(crate) fn value(&self, row: usize) -> Option<f64> {
+        if row < self.warm_up {
+            return None;
+        }
+        self.values.get(row).copied()
+    }
 }
 
 impl fmt::Display for Ask {
@@ -1,4 +1,3 @@
-use crate::Series;
 use std::collections::VecDeque;
 
 use super::ema::calculate_ema;
@@ -7,34 +6,40 @@ use super::ema::calculate_ema;
 // https://www.investopedia.com/terms/b/bollingerbands.asp
 
 pub(crate) fn calculate_bollinger_bands(
-    series: &[Series],
+    high: &[f64],
+    low: &[f64],
+    close: &[f64],
     period: usize,
     std_dev: usize,
-) -> (VecDeque<f32>, VecDeque<f32>, VecDeque<f32>) {
-    if series.len() <= period {
+) -> (VecDeque<f64>, VecDeque<f64>, VecDeque<f64>) {
+    if close.len() <= period {
         return (VecDeque::new(), VecDeque::new(), VecDeque::new());
     }
 
-    let middle_band_values = calculate_ema(series, period);
+    let middle_band_values = calculate_ema(close, period);
 
     let mut upper_band_values = VecDeque::new();
     let mut lower_band_values = VecDeque::new();
 
+    // Calculate typical prices (TP)
+    let typical_prices: Vec<f64> = (0..close.len())
+        .map(|i| (high[i] + low[i] + close[i]) / 3.0)
+        .collect();
+
     // Iterate over the series to calculate upper and lower bands
-    for i in period..series.len() {
-        // Calculate typical price (TP)
-        let typical_price = (series[i].high + series[i].low + series[i].close) / 3.0;
+    for i in period..close.len() {
+        let typical_price = typical_prices[i];
 
         // Calculate standard deviation over last n periods of TP
-        let sum_squares: f32 = series[i - period + 1..=i]
+        let sum_squares: f64 = typical_prices[i - period + 1..=i]
             .iter()
-            .map(|s| ((s.high + s.low + s.close) / 3.0 - typical_price).powi(2))
+            .map(|tp| (tp - typical_price).powi(2))
             .sum();
-        let std_deviation = (sum_squares / period as f32).sqrt();
+        let std_deviation = (sum_squares / period as f64).sqrt();
 
         // Calculate upper and lower bands
-        let upper_band = middle_band_values.back().unwrap_or(&0.0) + std_dev as f32 * std_deviation;
-        let lower_band = middle_band_values.back().unwrap_or(&0.0) - std_dev as f32 * std_deviation;
+        let upper_band = middle_band_values.back().unwrap_or(&0.0) + std_dev as f64 * std_deviation;
+        let lower_band = middle_band_values.back().unwrap_or(&0.0) - std_dev as f64 * std_deviation;
 
         // Store upper and lower bands in the respective vectors
         upper_band_values.push_back(upper_band);
@@ -46,7 +51,7 @@ pub(crate) fn calculate_bollinger_bands(
         lower_band_values.push_front(0.0);
     }
 
-    assert!((series.len() == upper_band_values.len()) && (series.len() == lower_band_values.len()));
+    assert!((close.len() == upper_band_values.len()) && (close.len() == lower_band_values.len()));
 
     (upper_band_values, middle_band_values, lower_band_values)
 }
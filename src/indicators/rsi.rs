@@ -1,10 +1,9 @@
-use crate::Series;
 use std::collections::VecDeque;
 
 // calculation based on:
 // https://www.investopedia.com/terms/r/rsi.asp
-pub(crate) fn calculate_rsi(series: &[Series], period: usize) -> VecDeque<f32> {
-    if series.len() <= period {
+pub(crate) fn calculate_rsi(close: &[f64], period: usize) -> VecDeque<f64> {
+    if close.len() <= period {
         return VecDeque::new();
     }
 
@@ -13,7 +12,7 @@ pub(crate) fn calculate_rsi(series: &[Series], period: usize) -> VecDeque<f32> {
 
     // Calculate initial average gain and loss
     for i in 1..=period {
-        let price_diff = series[i].close - series[i - 1].close;
+        let price_diff = close[i] - close[i - 1];
         if price_diff > 0.0 {
             gain_sum += price_diff;
         } else {
@@ -24,8 +23,8 @@ pub(crate) fn calculate_rsi(series: &[Series], period: usize) -> VecDeque<f32> {
     let mut rsi_values = VecDeque::new();
 
     // Calculate RSI for the remaining data
-    for i in period..series.len() {
-        let price_diff = series[i].close - series[i - 1].close;
+    for i in period..close.len() {
+        let price_diff = close[i] - close[i - 1];
 
         if price_diff > 0.0 {
             gain_sum += price_diff;
@@ -34,14 +33,14 @@ pub(crate) fn calculate_rsi(series: &[Series], period: usize) -> VecDeque<f32> {
         }
 
         // Calculate average gain and loss
-        let avg_gain = gain_sum / period as f32;
-        let avg_loss = loss_sum / period as f32;
+        let avg_gain = gain_sum / period as f64;
+        let avg_loss = loss_sum / period as f64;
 
         // Calculate relative strength (RS)
         let rs = if avg_loss != 0.0 {
             avg_gain / avg_loss
         } else {
-            f32::INFINITY
+            f64::INFINITY
         };
 
         // Calculate RSI
@@ -50,15 +49,15 @@ pub(crate) fn calculate_rsi(series: &[Series], period: usize) -> VecDeque<f32> {
         rsi_values.push_back(rsi);
 
         // Update gain and loss sums for the next iteration
-        gain_sum -= gain_sum / period as f32;
-        loss_sum -= loss_sum / period as f32;
+        gain_sum -= gain_sum / period as f64;
+        loss_sum -= loss_sum / period as f64;
     }
 
     for _ in 1..period + 1 {
         rsi_values.push_front(0.0);
     }
 
-    assert!(rsi_values.len() == series.len());
+    assert!(rsi_values.len() == close.len());
 
     rsi_values
 }
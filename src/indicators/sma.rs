@@ -1,19 +1,18 @@
-use crate::Series;
 use std::collections::VecDeque;
 
 // calculate based on:
 // https://www.investopedia.com/terms/s/sma.asp
-pub(crate) fn calculate_sma(series: &[Series], period: usize) -> VecDeque<f32> {
-    let mut sma_values: VecDeque<f32> = series
+pub(crate) fn calculate_sma(close: &[f64], period: usize) -> VecDeque<f64> {
+    let mut sma_values: VecDeque<f64> = close
         .windows(period)
-        .map(|window| window.iter().map(|item| item.close).sum::<f32>() / period as f32)
+        .map(|window| window.iter().sum::<f64>() / period as f64)
         .collect();
 
     for _ in 1..period {
         sma_values.push_front(0.0);
     }
 
-    assert!(sma_values.len() == series.len());
+    assert!(sma_values.len() == close.len());
 
     sma_values
 }
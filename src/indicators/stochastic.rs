@@ -1,27 +1,31 @@
-use crate::Series;
 use std::collections::VecDeque;
 
 // calculation based on:
 // https://www.investopedia.com/terms/s/stochasticoscillator.asp
 
-pub(crate) fn calculate_stochastic(series: &[Series], period: usize) -> VecDeque<f32> {
-    if series.len() <= period {
+pub(crate) fn calculate_stochastic(
+    high: &[f64],
+    low: &[f64],
+    close: &[f64],
+    period: usize,
+) -> VecDeque<f64> {
+    if close.len() <= period {
         return VecDeque::new();
     }
 
     let mut stochastic_values = VecDeque::new();
 
     // This loop calculates %K for each data point.
-    for i in period..series.len() {
-        let closing_price = series[i].close;
-        let mut lowest_low = f32::INFINITY;
-        let mut highest_high = f32::NEG_INFINITY;
+    for i in period..close.len() {
+        let closing_price = close[i];
+        let mut lowest_low = f64::INFINITY;
+        let mut highest_high = f64::NEG_INFINITY;
 
         //Find Lowest Low and Highest High:
-        for s in &series[i - period + 1..=i] {
-            let current_low = s.low;
-            let current_high = s.high;
-
+        for (&current_low, &current_high) in low[i - period + 1..=i]
+            .iter()
+            .zip(&high[i - period + 1..=i])
+        {
             if current_low < lowest_low {
                 lowest_low = current_low;
             }
@@ -45,7 +49,7 @@ pub(crate) fn calculate_stochastic(series: &[Series], period: usize) -> VecDeque
         stochastic_values.push_front(0.0);
     }
 
-    assert!(stochastic_values.len() == series.len());
+    assert!(stochastic_values.len() == close.len());
 
     stochastic_values
 }
@@ -11,7 +11,17 @@
 //! Check the [Readme file](https://github.com/danrusei/market-data) and the [Examples folder](https://github.com/danrusei/market-data/tree/main/examples) for more information.
 
 mod client;
-pub use client::{Interval, MarketClient, MarketSeries, Series};
+pub use client::{
+    actions::{Adjustment, CorporateAction},
+    builder::MarketClientBuilder,
+    cache::FileCache,
+    cassette::{Cassette, CassetteMode, Interaction},
+    fallback::{FallbackClient, FallbackSeries, SeriesSource},
+    interval::IntervalUnit,
+    rate_limit::RateLimit,
+    retry::RetryPolicy,
+    Interval, MarketClient, MarketSeries, Series,
+};
 
 mod publishers;
 pub use publishers::{
@@ -20,10 +30,30 @@ pub use publishers::{
     massive::Massive,
     twelvedata::Twelvedata,
     yahoo_finance::{YahooFin, YahooRange},
+    Capabilities, IntoUtc, Publisher, SeriesRequest,
+};
+
+mod series;
+pub use series::{
+    binary::{BinaryHeader, BinaryOptions, BinaryReader, BinaryWriter},
+    calendar::TradingCalendar,
+    columns::SeriesColumns,
+    csv::{CsvColumn, CsvOptions},
+    gaps::Gap,
+    merge::ConflictPolicy,
+    quality::{Anomaly, CleanOptions, InvalidBars, ValidationReport},
+    slice::EnhancedSlice,
 };
 
+#[cfg(feature = "sqlite")]
+mod store;
+#[cfg(feature = "sqlite")]
+pub use store::{Coverage, SqliteStore};
+
 mod indicators;
 pub use indicators::{EnhancedMarketSeries, Indicators};
 
 pub mod errors;
 pub use errors::{MarketError, MarketResult};
+
+pub use chrono_tz::Tz;
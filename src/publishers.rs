@@ -4,12 +4,225 @@ pub(crate) mod massive;
 pub(crate) mod twelvedata;
 pub(crate) mod yahoo_finance;
 
-use crate::{client::MarketSeries, errors::MarketResult};
+use crate::{
+    client::{interval::IntervalUnit, rate_limit::RateLimit, Interval, MarketSeries},
+    errors::{MarketError, MarketResult},
+};
+use chrono::{DateTime, Duration, NaiveDate, NaiveDateTime, NaiveTime, Utc};
+use chrono_tz::Tz;
+use std::hash::{DefaultHasher, Hash, Hasher};
 use url::Url;
 
+/// The number of bars requested when neither a start date nor a bar count is set
+const DEFAULT_BARS: usize = 100;
+
+/// Provider-neutral request, mapped by every Publisher to its own request type
+///
+/// All the bounds are optional: `end` defaults to now, and without a `start` date
+/// the publisher is asked for enough history to cover the `bars` count (100 by default).
+#[derive(Debug, Clone)]
+pub struct SeriesRequest {
+    /// symbol like: "GOOGL"
+    pub symbol: String,
+    /// interval between two bars
+    pub interval: Interval,
+    /// the first bar to fetch
+    pub start: Option<DateTime<Utc>>,
+    /// the last bar to fetch
+    pub end: Option<DateTime<Utc>>,
+    /// the number of bars to keep, the latest ones
+    pub bars: Option<usize>,
+}
+
+/// Date-times accepted as bounds of a SeriesRequest, naive values are taken as UTC
+pub trait IntoUtc {
+    fn into_utc(self) -> DateTime<Utc>;
+}
+
+impl IntoUtc for DateTime<Utc> {
+    fn into_utc(self) -> DateTime<Utc> {
+        self
+    }
+}
+
+impl IntoUtc for NaiveDateTime {
+    fn into_utc(self) -> DateTime<Utc> {
+        self.and_utc()
+    }
+}
+
+impl IntoUtc for NaiveDate {
+    fn into_utc(self) -> DateTime<Utc> {
+        self.and_time(NaiveTime::MIN).and_utc()
+    }
+}
+
+impl SeriesRequest {
+    pub fn new(symbol: impl Into<String>, interval: Interval) -> Self {
+        SeriesRequest {
+            symbol: symbol.into(),
+            interval,
+            start: None,
+            end: None,
+            bars: None,
+        }
+    }
+
+    /// Fetch the bars starting at this date
+    pub fn with_start(mut self, start: impl IntoUtc) -> Self {
+        self.start = Some(start.into_utc());
+        self
+    }
+
+    /// Fetch the bars up to this date
+    pub fn with_end(mut self, end: impl IntoUtc) -> Self {
+        self.end = Some(end.into_utc());
+        self
+    }
+
+    /// Keep only the latest `bars` bars
+    pub fn with_bars(mut self, bars: usize) -> Self {
+        self.bars = Some(bars);
+        self
+    }
+
+    /// The date range sent to the publishers, with the defaults applied
+    pub fn range(&self) -> (DateTime<Utc>, DateTime<Utc>) {
+        let end = self.end.unwrap_or_else(Utc::now);
+        let start = self.start.unwrap_or_else(|| {
+            let lookback = lookback(&self.interval, self.bars.unwrap_or(DEFAULT_BARS));
+            end.checked_sub_signed(lookback)
+                .unwrap_or(DateTime::<Utc>::MIN_UTC)
+        });
+        (start, end)
+    }
+}
+
+/// Calendar time covering the given number of bars, with slack for nights, weekends and holidays
+pub(crate) fn lookback(interval: &Interval, bars: usize) -> Duration {
+    // saturated, a huge count or multiplier covers the whole history
+    let bars = i64::try_from(bars).unwrap_or(i64::MAX);
+    let multiplier = interval.multiplier() as i64;
+    let days = match interval.unit() {
+        IntervalUnit::Minute | IntervalUnit::Hour => {
+            // a regular US session lasts 390 minutes
+            let minutes = interval.nominal_duration().num_minutes();
+            let sessions = bars.saturating_mul(minutes).saturating_add(389) / 390;
+            sessions.saturating_mul(7) / 5 + 4
+        }
+        IntervalUnit::Day => bars.saturating_mul(multiplier).saturating_mul(7) / 5 + 4,
+        IntervalUnit::Week => bars
+            .saturating_mul(multiplier)
+            .saturating_add(1)
+            .saturating_mul(7),
+        _ => bars.saturating_mul(interval.nominal_duration().num_days()),
+    };
+    Duration::try_days(days).unwrap_or(Duration::MAX)
+}
+
+/// What a Publisher provides, so generic code can pick one without hardcoding it
+#[derive(Debug, Clone, Default)]
+pub struct Capabilities {
+    /// the supported intervals
+    pub intervals: Vec<Interval>,
+    /// the units of which any multiple is supported, like 3 days or 10 minutes
+    pub any_multiple_of: Vec<IntervalUnit>,
+    /// how far back the free tier history goes, `None` if it is not documented as limited
+    pub max_history: Option<Duration>,
+    /// the maximum number of bars returned by a single request, `None` if unlimited
+    pub max_bars: Option<usize>,
+    /// whether an API key is required
+    pub requires_api_key: bool,
+}
+
+impl Capabilities {
+    /// Whether the interval is listed, or is a multiple of a unit listed in `any_multiple_of`
+    pub fn supports(&self, interval: &Interval) -> bool {
+        // the whole hours are normalized out of the minutes, 120 minutes are 2 hours
+        let minutes = interval.is_intraday().then_some(IntervalUnit::Minute);
+        self.intervals.contains(interval)
+            || self
+                .any_multiple_of
+                .iter()
+                .any(|unit| *unit == interval.unit() || Some(*unit) == minutes)
+    }
+}
+
+/// Parse an IANA timezone name, like "America/New_York"
+pub(crate) fn parse_timezone(name: &str) -> MarketResult<Tz> {
+    name.parse()
+        .map_err(|_| MarketError::ParsingError(format!("Unknown timezone: {}", name)))
+}
+
+/// Make sure the base url ends with a slash, so the endpoint paths are joined to it instead of replacing its last segment
+pub(crate) fn normalize_base_url(base_url: String) -> String {
+    if base_url.ends_with('/') {
+        base_url
+    } else {
+        format!("{}/", base_url)
+    }
+}
+
 /// This trait has to be implemented by all the added sites
+///
+/// Only `create_endpoint` and `transform_data` are required, the other methods have defaults.
 pub trait Publisher {
     type Request;
+
     fn create_endpoint(&self, request: &Self::Request) -> MarketResult<Url>;
     fn transform_data(&self, data: String, request: &Self::Request) -> MarketResult<MarketSeries>;
+
+    /// Short name of the publisher, like "finnhub", by default the name of the implementing type
+    fn name(&self) -> &'static str {
+        let name = std::any::type_name::<Self>();
+        name.rsplit("::").next().unwrap_or(name)
+    }
+
+    /// Build the publisher's own request from a provider-neutral one,
+    /// fails with `MarketError::UnsuportedInterval` if the publisher doesn't provide the interval
+    fn series_request(&self, request: &SeriesRequest) -> MarketResult<Self::Request> {
+        Err(MarketError::UnsuportedInterval(
+            request.interval.to_string(),
+        ))
+    }
+
+    /// What the publisher provides: intervals, history depth, API key requirement.
+    /// Nothing by default, so a FallbackClient skips the publisher.
+    fn capabilities(&self) -> Capabilities {
+        Capabilities::default()
+    }
+
+    /// The symbol targeted by the request, used to key batch results, empty by default
+    fn request_symbol<'a>(&self, _request: &'a Self::Request) -> &'a str {
+        ""
+    }
+
+    /// Identifies the request within the cache: symbol, interval and range, never the API key.
+    /// By default the symbol followed by a hash of the endpoint url.
+    fn cache_key(&self, request: &Self::Request) -> String {
+        let mut hasher = DefaultHasher::new();
+        if let Ok(url) = self.create_endpoint(request) {
+            url.as_str().hash(&mut hasher);
+        }
+        format!("{}_{:016x}", self.request_symbol(request), hasher.finish())
+    }
+
+    /// A request for the bars starting at `since`, used to refresh a cached series.
+    /// `None` if the publisher can't narrow the request, the original one is sent instead.
+    ///
+    /// The new bars are merged into the cached ones, so only the requests whose series grows over time,
+    /// like a fixed date range or the full history, can be narrowed. A rolling window, like the last
+    /// 100 bars or the last 5 days, must be fetched again to drop the bars that went out of it.
+    fn refresh_request(
+        &self,
+        _request: &Self::Request,
+        _since: NaiveDateTime,
+    ) -> Option<Self::Request> {
+        None
+    }
+
+    /// The documented free-tier quota, enforced by MarketClient unless overridden
+    fn rate_limit(&self) -> Option<RateLimit> {
+        None
+    }
 }
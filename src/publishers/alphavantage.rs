@@ -1,25 +1,33 @@
 //! Fetch time series stock data from [AlphaVantage](https://www.alphavantage.co/documentation/#time-series-data)
 
-use chrono::NaiveDateTime;
+use chrono::{Duration, NaiveDateTime, Utc};
+use chrono_tz::Tz;
 use url::Url;
 
 use crate::{
-    client::{Interval, MarketSeries, Series},
+    client::{
+        actions::{Adjustment, CorporateAction},
+        rate_limit::RateLimit,
+        Interval, MarketSeries, Series,
+    },
     errors::MarketResult,
-    publishers::Publisher,
+    publishers::{normalize_base_url, parse_timezone, Capabilities, Publisher, SeriesRequest},
     MarketError,
 };
 
 const BASE_URL: &str = "https://www.alphavantage.co/";
+/// The number of requests per day of the free tier
+const DAILY_QUOTA: u32 = 25;
 
 /// Fetch time series stock data from [AlphaVantage](https://www.alphavantage.co/documentation/#time-series-data),
 /// implements Publisher trait
 #[derive(Debug)]
 pub struct AlphaVantage {
     token: String,
+    base_url: String,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct AVRequest {
     symbol: String,
     function: Function,
@@ -32,6 +40,7 @@ pub enum Function {
     Intraday,
     #[default]
     Daily,
+    DailyAdjusted,
     Weekly,
     Monthly,
 }
@@ -48,9 +57,16 @@ impl AlphaVantage {
     pub fn new(token: impl Into<String>) -> Self {
         AlphaVantage {
             token: token.into(),
+            base_url: BASE_URL.to_string(),
         }
     }
 
+    /// Override the base url, e.g. to route through a proxy or to use a local mock server
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = normalize_base_url(base_url.into());
+        self
+    }
+
     /// Request for intraday series
     pub fn intraday_series(
         &self,
@@ -89,6 +105,20 @@ impl AlphaVantage {
         }
     }
 
+    /// Request for daily series with the adjusted close, dividends and split coefficients
+    pub fn daily_adjusted_series(
+        &self,
+        symbol: impl Into<String>,
+        output_size: OutputSize,
+    ) -> AVRequest {
+        AVRequest {
+            symbol: symbol.into(),
+            function: Function::DailyAdjusted,
+            interval: None,
+            output_size,
+        }
+    }
+
     /// Request for weekly series
     pub fn weekly_series(&self, symbol: impl Into<String>, output_size: OutputSize) -> AVRequest {
         AVRequest {
@@ -113,8 +143,12 @@ impl AlphaVantage {
 impl Publisher for AlphaVantage {
     type Request = AVRequest;
 
+    fn name(&self) -> &'static str {
+        "alphavantage"
+    }
+
     fn create_endpoint(&self, request: &Self::Request) -> MarketResult<Url> {
-        let base_url = Url::parse(BASE_URL)?;
+        let base_url = Url::parse(&self.base_url)?;
         let mut url = base_url.join("query")?;
         {
             let mut pairs = url.query_pairs_mut();
@@ -137,6 +171,70 @@ impl Publisher for AlphaVantage {
         let mut data_series: Vec<Series> = Vec::new();
         let v: serde_json::Value = serde_json::from_str(&data)?;
 
+        // The request limits are reported under "Note" or "Information", the latter is also used for premium endpoints
+        if let Some(message) = v
+            .get("Note")
+            .or_else(|| v.get("Information"))
+            .and_then(|m| m.as_str())
+        {
+            let message = message.to_string();
+            if message.contains("apikey") {
+                return Err(MarketError::Unauthorized {
+                    publisher: self.name(),
+                    message,
+                });
+            }
+            // the daily quota is only reset the next day, so retrying doesn't help
+            if message.contains("per day") && !message.contains("per minute") {
+                let limit = message
+                    .split("per day")
+                    .next()
+                    .and_then(|before| {
+                        before
+                            .split_whitespace()
+                            .rev()
+                            .find_map(|word| word.parse().ok())
+                    })
+                    .unwrap_or(DAILY_QUOTA);
+                return Err(MarketError::QuotaExhausted { limit });
+            }
+            if message.contains("rate limit") || message.contains("call frequency") {
+                return Err(MarketError::RateLimited {
+                    publisher: self.name(),
+                    retry_after: None,
+                });
+            }
+            return Err(MarketError::ProviderMessage {
+                publisher: self.name(),
+                message,
+            });
+        }
+
+        // The function and interval are built by the library, so an invalid call points to the symbol
+        if let Some(message) = v.get("Error Message").and_then(|m| m.as_str()) {
+            if message.contains("apikey") {
+                return Err(MarketError::Unauthorized {
+                    publisher: self.name(),
+                    message: message.to_string(),
+                });
+            }
+            return Err(MarketError::SymbolNotFound {
+                publisher: self.name(),
+                symbol: request.symbol.clone(),
+            });
+        }
+
+        // The series are in US/Eastern, as reported by the "Time Zone" of the meta data
+        let timezone = match v
+            .get("Meta Data")
+            .and_then(|m| m.as_object())
+            .and_then(|m| m.iter().find(|(k, _)| k.contains("Time Zone")))
+            .and_then(|(_, tz)| tz.as_str())
+        {
+            Some(name) => parse_timezone(name)?,
+            None => Tz::America__New_York,
+        };
+
         // Find the key that contains "Time Series"
         let series_key = v
             .as_object()
@@ -152,29 +250,38 @@ impl Publisher for AlphaVantage {
                 MarketError::DownloadedData("Invalid Time Series data format".to_string())
             })?;
 
+        if series_data.is_empty() {
+            return Err(MarketError::NoData {
+                publisher: self.name(),
+            });
+        }
+
+        let mut actions = Vec::new();
         for (date_str, values) in series_data {
-            let open: f32 = values
+            let open: f64 = values
                 .get("1. open")
                 .and_then(|v| v.as_str())
                 .and_then(|s| s.parse().ok())
                 .ok_or_else(|| MarketError::ParsingError("Unable to parse Open".to_string()))?;
-            let high: f32 = values
+            let high: f64 = values
                 .get("2. high")
                 .and_then(|v| v.as_str())
                 .and_then(|s| s.parse().ok())
                 .ok_or_else(|| MarketError::ParsingError("Unable to parse High".to_string()))?;
-            let low: f32 = values
+            let low: f64 = values
                 .get("3. low")
                 .and_then(|v| v.as_str())
                 .and_then(|s| s.parse().ok())
                 .ok_or_else(|| MarketError::ParsingError("Unable to parse Low".to_string()))?;
-            let close: f32 = values
+            let close: f64 = values
                 .get("4. close")
                 .and_then(|v| v.as_str())
                 .and_then(|s| s.parse().ok())
                 .ok_or_else(|| MarketError::ParsingError("Unable to parse Close".to_string()))?;
+            // the adjusted series moves the volume after the adjusted close
             let volume: f64 = values
                 .get("5. volume")
+                .or_else(|| values.get("6. volume"))
                 .and_then(|v| v.as_str())
                 .and_then(|s| s.parse().ok())
                 .ok_or_else(|| MarketError::ParsingError("Unable to parse Volume".to_string()))?;
@@ -190,6 +297,25 @@ impl Publisher for AlphaVantage {
                     .map_err(|e| MarketError::ParsingError(e.to_string()))?
             };
 
+            let field = |key: &str| -> Option<f64> {
+                values
+                    .get(key)
+                    .and_then(|v| v.as_str())
+                    .and_then(|s| s.parse().ok())
+            };
+            if let Some(amount) = field("7. dividend amount").filter(|amount| *amount > 0.0) {
+                actions.push(CorporateAction::Dividend {
+                    date: datetime.date(),
+                    amount,
+                });
+            }
+            if let Some(ratio) = field("8. split coefficient").filter(|ratio| *ratio != 1.0) {
+                actions.push(CorporateAction::Split {
+                    date: datetime.date(),
+                    ratio,
+                });
+            }
+
             data_series.push(Series {
                 datetime,
                 open,
@@ -197,26 +323,116 @@ impl Publisher for AlphaVantage {
                 high,
                 low,
                 volume,
+                adj_close: field("5. adjusted close"),
             });
         }
 
         data_series.sort_by_key(|item| item.datetime);
+        actions.sort_by_key(|action| action.date());
+
+        let interval = match request.function {
+            Function::Intraday => request.interval.as_deref().unwrap_or_default().parse()?,
+            Function::Daily | Function::DailyAdjusted => Interval::Daily,
+            Function::Weekly => Interval::Weekly,
+            Function::Monthly => Interval::Monthly,
+        };
 
         Ok(MarketSeries {
             symbol: request.symbol.clone(),
-            interval: match request.function {
-                Function::Intraday => request
-                    .interval
-                    .as_ref()
-                    .map(|i| i.clone().into())
-                    .unwrap_or(Interval::Daily),
-                Function::Daily => Interval::Daily,
-                Function::Weekly => Interval::Weekly,
-                Function::Monthly => Interval::Monthly,
-            },
+            interval,
+            timezone,
             data: data_series,
+            // the intraday series are adjusted by default, the other ones are as traded
+            adjustment: match request.function {
+                Function::Intraday => Adjustment::SplitsAndDividends,
+                _ => Adjustment::Raw,
+            },
+            actions,
         })
     }
+
+    /// The compact output size is used when the latest 100 bars cover the request
+    fn series_request(&self, request: &SeriesRequest) -> MarketResult<Self::Request> {
+        let symbol = request.symbol.as_str();
+        let covered = match request.interval {
+            Interval::Min1 => Duration::minutes(100),
+            Interval::Min5 => Duration::minutes(500),
+            Interval::Min15 => Duration::minutes(1500),
+            Interval::Min30 => Duration::minutes(3000),
+            Interval::Hour1 => Duration::hours(100),
+            Interval::Daily => Duration::days(140),
+            Interval::Weekly => Duration::weeks(100),
+            _ => Duration::days(3000),
+        };
+        let compact = match request.start {
+            Some(start) => Utc::now() - start < covered,
+            None => request.end.is_none() && request.bars.is_none_or(|bars| bars <= 100),
+        };
+        let output_size = if compact {
+            OutputSize::Compact
+        } else {
+            OutputSize::Full
+        };
+        match request.interval {
+            Interval::Daily => Ok(self.daily_series(symbol, output_size)),
+            Interval::Weekly => Ok(self.weekly_series(symbol, output_size)),
+            Interval::Monthly => Ok(self.monthly_series(symbol, output_size)),
+            _ => self.intraday_series(symbol, output_size, request.interval),
+        }
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        Capabilities {
+            intervals: vec![
+                Interval::Min1,
+                Interval::Min5,
+                Interval::Min15,
+                Interval::Min30,
+                Interval::Hour1,
+                Interval::Daily,
+                Interval::Weekly,
+                Interval::Monthly,
+            ],
+            any_multiple_of: Vec::new(),
+            max_history: Some(Duration::days(20 * 365)),
+            max_bars: None,
+            requires_api_key: true,
+        }
+    }
+
+    fn request_symbol<'a>(&self, request: &'a Self::Request) -> &'a str {
+        &request.symbol
+    }
+
+    fn cache_key(&self, request: &Self::Request) -> String {
+        format!(
+            "{}_{}_{}_{}",
+            request.symbol,
+            request.function,
+            request.interval.as_deref().unwrap_or_default(),
+            request.output_size
+        )
+    }
+
+    /// The compact output (latest 100 bars) is enough to refresh a cached series
+    fn refresh_request(
+        &self,
+        request: &Self::Request,
+        _since: NaiveDateTime,
+    ) -> Option<Self::Request> {
+        // the compact output size is the rolling window of the last 100 bars
+        match request.output_size {
+            OutputSize::Full => Some(AVRequest {
+                output_size: OutputSize::Compact,
+                ..request.clone()
+            }),
+            OutputSize::Compact => None,
+        }
+    }
+
+    fn rate_limit(&self) -> Option<RateLimit> {
+        Some(RateLimit::daily(DAILY_QUOTA))
+    }
 }
 
 impl std::fmt::Display for Function {
@@ -224,6 +440,7 @@ impl std::fmt::Display for Function {
         let s = match self {
             Function::Intraday => "TIME_SERIES_INTRADAY",
             Function::Daily => "TIME_SERIES_DAILY",
+            Function::DailyAdjusted => "TIME_SERIES_DAILY_ADJUSTED",
             Function::Weekly => "TIME_SERIES_WEEKLY",
             Function::Monthly => "TIME_SERIES_MONTHLY",
         };
@@ -1,13 +1,14 @@
 //! Fetch time series stock data from [Finnhub](https://finnhub.io/docs/api), implements Publisher trait
 
-use chrono::DateTime;
+use chrono::{DateTime, Duration, NaiveDateTime};
+use chrono_tz::Tz;
 use serde::{Deserialize, Serialize};
 use url::Url;
 
 use crate::{
-    client::{Interval, MarketSeries, Series},
+    client::{actions::Adjustment, rate_limit::RateLimit, Interval, MarketSeries, Series},
     errors::{MarketError, MarketResult},
-    publishers::Publisher,
+    publishers::{normalize_base_url, Capabilities, Publisher, SeriesRequest},
 };
 
 const BASE_URL: &str = "https://finnhub.io/api/v1/";
@@ -16,6 +17,7 @@ const BASE_URL: &str = "https://finnhub.io/api/v1/";
 #[derive(Debug)]
 pub struct Finnhub {
     token: String,
+    base_url: String,
 }
 
 #[derive(Debug, Clone)]
@@ -35,9 +37,16 @@ impl Finnhub {
     pub fn new(token: impl Into<String>) -> Self {
         Finnhub {
             token: token.into(),
+            base_url: BASE_URL.to_string(),
         }
     }
 
+    /// Override the base url, e.g. to route through a proxy or to use a local mock server
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = normalize_base_url(base_url.into());
+        self
+    }
+
     /// Request for daily series
     pub fn daily_series(&self, symbol: impl Into<String>, from: i64, to: i64) -> FinnhubRequest {
         FinnhubRequest::Candle {
@@ -103,13 +112,43 @@ impl Finnhub {
             symbol: symbol.into(),
         }
     }
+
+    /// Maps the message of an error payload like `{"error": "Invalid API key"}`
+    fn error(&self, message: String, symbol: &str) -> MarketError {
+        let lowercase = message.to_lowercase();
+        if lowercase.contains("limit") {
+            MarketError::RateLimited {
+                publisher: self.name(),
+                retry_after: None,
+            }
+        } else if lowercase.contains("api key") || lowercase.contains("access") {
+            MarketError::Unauthorized {
+                publisher: self.name(),
+                message,
+            }
+        } else if lowercase.contains("symbol") {
+            MarketError::SymbolNotFound {
+                publisher: self.name(),
+                symbol: symbol.to_string(),
+            }
+        } else {
+            MarketError::ProviderMessage {
+                publisher: self.name(),
+                message,
+            }
+        }
+    }
 }
 
 impl Publisher for Finnhub {
     type Request = FinnhubRequest;
 
+    fn name(&self) -> &'static str {
+        "finnhub"
+    }
+
     fn create_endpoint(&self, request: &Self::Request) -> MarketResult<Url> {
-        let base_url = Url::parse(BASE_URL)?;
+        let base_url = Url::parse(&self.base_url)?;
         match request {
             FinnhubRequest::Candle {
                 symbol,
@@ -143,27 +182,29 @@ impl Publisher for Finnhub {
             } => {
                 let candles: FinnhubCandles = serde_json::from_str(&data)?;
 
-                let status = match candles.status {
-                    Some(ref s) => s.as_str(),
+                if let Some(error) = candles.error {
+                    return Err(self.error(error, symbol));
+                }
+
+                match candles.status.as_deref() {
+                    Some("ok") => {}
+                    Some("no_data") => {
+                        return Err(MarketError::NoData {
+                            publisher: self.name(),
+                        })
+                    }
+                    Some(status) => {
+                        return Err(MarketError::ProviderMessage {
+                            publisher: self.name(),
+                            message: format!("status: {}", status),
+                        })
+                    }
                     None => {
-                        if let Some(ref err) = candles.error {
-                            return Err(MarketError::DownloadedData(format!(
-                                "Finnhub error: {}",
-                                err
-                            )));
-                        }
                         return Err(MarketError::DownloadedData(format!(
                             "Finnhub response missing status. Response: {}",
                             data
-                        )));
+                        )))
                     }
-                };
-
-                if status != "ok" {
-                    return Err(MarketError::DownloadedData(format!(
-                        "Error returned from Finnhub: {}",
-                        candles.error.unwrap_or_else(|| status.to_string())
-                    )));
                 }
 
                 let t = candles
@@ -198,6 +239,7 @@ impl Publisher for Finnhub {
                         high: h[i],
                         low: l[i],
                         volume: v[i] as f64,
+                        adj_close: None,
                     });
                 }
 
@@ -205,24 +247,29 @@ impl Publisher for Finnhub {
 
                 Ok(MarketSeries {
                     symbol: symbol.clone(),
-                    interval: match resolution.as_str() {
-                        "D" => Interval::Daily,
-                        "W" => Interval::Weekly,
-                        "M" => Interval::Monthly,
-                        _ => Interval::Daily,
-                    },
+                    interval: resolution.parse()?,
+                    timezone: Tz::UTC,
                     data: data_series,
+                    // the daily and longer candles are adjusted for splits, the intraday ones are not
+                    adjustment: match resolution.as_str() {
+                        "D" | "W" | "M" => Adjustment::Splits,
+                        _ => Adjustment::Raw,
+                    },
+                    actions: Vec::new(),
                 })
             }
             FinnhubRequest::Quote { symbol } => {
+                if let Ok(FinnhubError { error }) = serde_json::from_str(&data) {
+                    return Err(self.error(error, symbol));
+                }
                 let quote: FinnhubQuote = serde_json::from_str(&data)?;
 
                 // If 't' is 0, it often means the symbol was not found
                 if quote.t == 0 {
-                    return Err(MarketError::DownloadedData(format!(
-                        "Finnhub quote returned no data for symbol: {}",
-                        symbol
-                    )));
+                    return Err(MarketError::SymbolNotFound {
+                        publisher: self.name(),
+                        symbol: symbol.clone(),
+                    });
                 }
 
                 let datetime = DateTime::from_timestamp(quote.t, 0).ok_or_else(|| {
@@ -236,28 +283,106 @@ impl Publisher for Finnhub {
                     high: quote.h,
                     low: quote.l,
                     volume: 0.0, // Quote doesn't return volume
+                    adj_close: None,
                 };
 
                 Ok(MarketSeries {
                     symbol: symbol.clone(),
                     interval: Interval::Daily,
+                    timezone: Tz::UTC,
                     data: vec![series],
+                    adjustment: Adjustment::Raw,
+                    actions: Vec::new(),
                 })
             }
         }
     }
+
+    fn series_request(&self, request: &SeriesRequest) -> MarketResult<Self::Request> {
+        let symbol = request.symbol.as_str();
+        let (start, end) = request.range();
+        let (from, to) = (start.timestamp(), end.timestamp());
+        match request.interval {
+            Interval::Daily => Ok(self.daily_series(symbol, from, to)),
+            Interval::Weekly => Ok(self.weekly_series(symbol, from, to)),
+            Interval::Monthly => Ok(self.monthly_series(symbol, from, to)),
+            _ => self.intraday_series(symbol, from, to, request.interval),
+        }
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        Capabilities {
+            intervals: vec![
+                Interval::Min1,
+                Interval::Min5,
+                Interval::Min15,
+                Interval::Min30,
+                Interval::Hour1,
+                Interval::Daily,
+                Interval::Weekly,
+                Interval::Monthly,
+            ],
+            any_multiple_of: Vec::new(),
+            max_history: Some(Duration::days(365)),
+            max_bars: None,
+            requires_api_key: true,
+        }
+    }
+
+    fn request_symbol<'a>(&self, request: &'a Self::Request) -> &'a str {
+        match request {
+            FinnhubRequest::Candle { symbol, .. } | FinnhubRequest::Quote { symbol } => symbol,
+        }
+    }
+
+    fn cache_key(&self, request: &Self::Request) -> String {
+        match request {
+            FinnhubRequest::Candle {
+                symbol,
+                resolution,
+                from,
+                to,
+            } => format!("{}_{}_{}_{}", symbol, resolution, from, to),
+            FinnhubRequest::Quote { symbol } => format!("{}_quote", symbol),
+        }
+    }
+
+    fn refresh_request(
+        &self,
+        request: &Self::Request,
+        since: NaiveDateTime,
+    ) -> Option<Self::Request> {
+        match request {
+            FinnhubRequest::Candle {
+                symbol,
+                resolution,
+                to,
+                ..
+            } => Some(FinnhubRequest::Candle {
+                symbol: symbol.clone(),
+                resolution: resolution.clone(),
+                from: since.and_utc().timestamp(),
+                to: *to,
+            }),
+            FinnhubRequest::Quote { .. } => None,
+        }
+    }
+
+    fn rate_limit(&self) -> Option<RateLimit> {
+        Some(RateLimit::per_minute(60))
+    }
 }
 
 #[derive(Debug, Deserialize, Serialize)]
 struct FinnhubCandles {
     #[serde(rename = "c")]
-    c: Option<Vec<f32>>,
+    c: Option<Vec<f64>>,
     #[serde(rename = "h")]
-    h: Option<Vec<f32>>,
+    h: Option<Vec<f64>>,
     #[serde(rename = "l")]
-    l: Option<Vec<f32>>,
+    l: Option<Vec<f64>>,
     #[serde(rename = "o")]
-    o: Option<Vec<f32>>,
+    o: Option<Vec<f64>>,
     #[serde(rename = "s")]
     status: Option<String>,
     #[serde(rename = "t")]
@@ -268,18 +393,23 @@ struct FinnhubCandles {
     error: Option<String>,
 }
 
+#[derive(Debug, Deserialize, Serialize)]
+struct FinnhubError {
+    error: String,
+}
+
 #[derive(Debug, Deserialize, Serialize)]
 struct FinnhubQuote {
     #[serde(rename = "c")]
-    c: f32,
+    c: f64,
     #[serde(rename = "h")]
-    h: f32,
+    h: f64,
     #[serde(rename = "l")]
-    l: f32,
+    l: f64,
     #[serde(rename = "o")]
-    o: f32,
+    o: f64,
     #[serde(rename = "pc")]
-    pc: f32,
+    pc: f64,
     #[serde(rename = "t")]
     t: i64,
 }
@@ -1,21 +1,29 @@
 //! Fetch time series stock data from [Massive](https://massive.com/docs/rest/stocks/aggregates/custom-bars)
 
-use chrono::DateTime;
+use chrono::{DateTime, Duration, NaiveDateTime};
+use chrono_tz::Tz;
 use serde::{Deserialize, Serialize};
 use url::Url;
 
 use crate::{
-    client::{Interval, MarketSeries, Series},
+    client::{
+        actions::Adjustment, interval::IntervalUnit, rate_limit::RateLimit, Interval, MarketSeries,
+        Series,
+    },
     errors::{MarketError, MarketResult},
-    publishers::Publisher,
+    publishers::{normalize_base_url, Capabilities, Publisher, SeriesRequest},
 };
 
 const BASE_URL: &str = "https://api.massive.com/v2/aggs/ticker/";
+/// The maximum number of base aggregates accepted by the API
+const MAX_LIMIT: i32 = 50000;
 
 /// Fetch time series stock data from [Massive](https://massive.com/), implements Publisher trait
 #[derive(Debug)]
 pub struct Massive {
     token: String,
+    base_url: String,
+    adjusted: bool,
 }
 
 #[derive(Debug, Clone)]
@@ -34,9 +42,23 @@ impl Massive {
     pub fn new(token: impl Into<String>) -> Self {
         Massive {
             token: token.into(),
+            base_url: BASE_URL.to_string(),
+            adjusted: true,
         }
     }
 
+    /// Override the base url, e.g. to route through a proxy or to use a local mock server
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = normalize_base_url(base_url.into());
+        self
+    }
+
+    /// Request the prices adjusted for splits (the default) or as traded
+    pub fn with_adjusted(mut self, adjusted: bool) -> Self {
+        self.adjusted = adjusted;
+        self
+    }
+
     /// Request for intraday series
     pub fn intraday_series(
         &self,
@@ -46,30 +68,60 @@ impl Massive {
         interval: Interval,
         limit: i32,
     ) -> MarketResult<MassiveRequest> {
-        let (timespan, multiplier) = match interval {
-            Interval::Min1 => ("minute", 1),
-            Interval::Min5 => ("minute", 5),
-            Interval::Min15 => ("minute", 15),
-            Interval::Min30 => ("minute", 30),
-            Interval::Hour1 => ("hour", 1),
-            Interval::Hour2 => ("hour", 2),
-            Interval::Hour4 => ("hour", 4),
-            _ => {
-                return Err(MarketError::UnsuportedInterval(format!(
-                    "{} interval is not supported by Massive",
-                    interval
-                )))
-            }
+        if !interval.is_intraday() {
+            return Err(MarketError::UnsuportedInterval(format!(
+                "{} interval is not an intraday interval",
+                interval
+            )));
+        }
+        self.aggregates(symbol, from_date, to_date, interval, limit)
+    }
+
+    /// Request for the bars of any interval, like 3 days or 1 quarter
+    pub fn aggregates(
+        &self,
+        symbol: impl Into<String>,
+        from_date: impl Into<String>,
+        to_date: impl Into<String>,
+        interval: Interval,
+        limit: i32,
+    ) -> MarketResult<MassiveRequest> {
+        let multiplier = i32::try_from(interval.multiplier()).map_err(|_| {
+            MarketError::UnsuportedInterval(format!(
+                "{} interval, the multiplier is too large",
+                interval
+            ))
+        })?;
+        Ok(self.request(symbol, from_date, to_date, interval, multiplier, limit))
+    }
+
+    fn request(
+        &self,
+        symbol: impl Into<String>,
+        from_date: impl Into<String>,
+        to_date: impl Into<String>,
+        interval: Interval,
+        multiplier: i32,
+        limit: i32,
+    ) -> MassiveRequest {
+        let timespan = match interval.unit() {
+            IntervalUnit::Minute => "minute",
+            IntervalUnit::Hour => "hour",
+            IntervalUnit::Day => "day",
+            IntervalUnit::Week => "week",
+            IntervalUnit::Month => "month",
+            IntervalUnit::Quarter => "quarter",
+            IntervalUnit::Year => "year",
         };
-        Ok(MassiveRequest {
+        MassiveRequest {
             symbol: symbol.into(),
-            timespan: timespan.into(),
+            timespan: timespan.to_string(),
             multiplier,
             from_date: from_date.into(),
             to_date: to_date.into(),
             limit,
             interval,
-        })
+        }
     }
 
     /// Request for daily series
@@ -80,15 +132,7 @@ impl Massive {
         to_date: impl Into<String>,
         limit: i32,
     ) -> MassiveRequest {
-        MassiveRequest {
-            symbol: symbol.into(),
-            timespan: "day".to_string(),
-            multiplier: 1,
-            from_date: from_date.into(),
-            to_date: to_date.into(),
-            limit,
-            interval: Interval::Daily,
-        }
+        self.request(symbol, from_date, to_date, Interval::Daily, 1, limit)
     }
 
     /// Request for weekly series
@@ -99,15 +143,7 @@ impl Massive {
         to_date: impl Into<String>,
         limit: i32,
     ) -> MassiveRequest {
-        MassiveRequest {
-            symbol: symbol.into(),
-            timespan: "week".to_string(),
-            multiplier: 1,
-            from_date: from_date.into(),
-            to_date: to_date.into(),
-            limit,
-            interval: Interval::Weekly,
-        }
+        self.request(symbol, from_date, to_date, Interval::Weekly, 1, limit)
     }
 
     /// Request for monthly series
@@ -118,23 +154,19 @@ impl Massive {
         to_date: impl Into<String>,
         limit: i32,
     ) -> MassiveRequest {
-        MassiveRequest {
-            symbol: symbol.into(),
-            timespan: "month".to_string(),
-            multiplier: 1,
-            from_date: from_date.into(),
-            to_date: to_date.into(),
-            limit,
-            interval: Interval::Monthly,
-        }
+        self.request(symbol, from_date, to_date, Interval::Monthly, 1, limit)
     }
 }
 
 impl Publisher for Massive {
     type Request = MassiveRequest;
 
+    fn name(&self) -> &'static str {
+        "massive"
+    }
+
     fn create_endpoint(&self, request: &Self::Request) -> MarketResult<Url> {
-        let base_url = Url::parse(BASE_URL)?;
+        let base_url = Url::parse(&self.base_url)?;
         let mut url = base_url.join(&format!(
             "{}/range/{}/{}/{}/{}",
             request.symbol,
@@ -147,6 +179,7 @@ impl Publisher for Massive {
             let mut pairs = url.query_pairs_mut();
             pairs.append_pair("sort", "asc");
             pairs.append_pair("limit", &request.limit.to_string());
+            pairs.append_pair("adjusted", &self.adjusted.to_string());
             pairs.append_pair("apiKey", &self.token);
         }
         Ok(url)
@@ -155,11 +188,40 @@ impl Publisher for Massive {
     fn transform_data(&self, data: String, request: &Self::Request) -> MarketResult<MarketSeries> {
         let prices: MassivePrices = serde_json::from_str(&data)?;
 
-        if prices.status != "OK" {
-            return Err(MarketError::DownloadedData(format!(
-                "Downloaded data status is: {}",
-                prices.status
-            )));
+        // the free plans return "DELAYED" data
+        match prices.status.as_str() {
+            "OK" | "DELAYED" => {}
+            "NOT_AUTHORIZED" => {
+                return Err(MarketError::Unauthorized {
+                    publisher: self.name(),
+                    message: prices.message().to_string(),
+                })
+            }
+            _ => {
+                let message = prices.message().to_string();
+                if message.to_lowercase().contains("api key") {
+                    return Err(MarketError::Unauthorized {
+                        publisher: self.name(),
+                        message,
+                    });
+                }
+                if message.to_lowercase().contains("exceeded") {
+                    return Err(MarketError::RateLimited {
+                        publisher: self.name(),
+                        retry_after: None,
+                    });
+                }
+                return Err(MarketError::ProviderMessage {
+                    publisher: self.name(),
+                    message,
+                });
+            }
+        }
+
+        if prices.time_series.is_empty() {
+            return Err(MarketError::NoData {
+                publisher: self.name(),
+            });
         }
 
         let mut data_series: Vec<Series> = Vec::with_capacity(prices.time_series.len());
@@ -176,31 +238,122 @@ impl Publisher for Massive {
                 high: series.h,
                 low: series.l,
                 volume: series.v,
+                adj_close: None,
             })
         }
 
         Ok(MarketSeries {
-            symbol: prices.ticker.clone(),
-            interval: request.interval.clone(),
+            symbol: request.symbol.clone(),
+            interval: request.interval,
+            timezone: Tz::UTC,
             data: data_series,
+            // the adjusted aggregates account for splits only
+            adjustment: if self.adjusted {
+                Adjustment::Splits
+            } else {
+                Adjustment::Raw
+            },
+            actions: Vec::new(),
         })
     }
+
+    fn series_request(&self, request: &SeriesRequest) -> MarketResult<Self::Request> {
+        let symbol = request.symbol.as_str();
+        let (start, end) = request.range();
+        let from = start.format("%Y-%m-%d").to_string();
+        let to = end.format("%Y-%m-%d").to_string();
+        self.aggregates(symbol, from, to, request.interval, MAX_LIMIT)
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        Capabilities {
+            intervals: vec![
+                Interval::Min1,
+                Interval::Min5,
+                Interval::Min15,
+                Interval::Min30,
+                Interval::Hour1,
+                Interval::Hour2,
+                Interval::Hour4,
+                Interval::Daily,
+                Interval::Weekly,
+                Interval::Monthly,
+                Interval::Quarterly,
+                Interval::Yearly,
+            ],
+            any_multiple_of: vec![
+                IntervalUnit::Minute,
+                IntervalUnit::Hour,
+                IntervalUnit::Day,
+                IntervalUnit::Week,
+                IntervalUnit::Month,
+                IntervalUnit::Quarter,
+                IntervalUnit::Year,
+            ],
+            max_history: Some(Duration::days(2 * 365)),
+            max_bars: Some(MAX_LIMIT as usize),
+            requires_api_key: true,
+        }
+    }
+
+    fn request_symbol<'a>(&self, request: &'a Self::Request) -> &'a str {
+        &request.symbol
+    }
+
+    fn cache_key(&self, request: &Self::Request) -> String {
+        format!(
+            "{}_{}{}_{}_{}_{}{}",
+            request.symbol,
+            request.multiplier,
+            request.timespan,
+            request.from_date,
+            request.to_date,
+            request.limit,
+            if self.adjusted { "" } else { "_unadjusted" }
+        )
+    }
+
+    fn refresh_request(
+        &self,
+        request: &Self::Request,
+        since: NaiveDateTime,
+    ) -> Option<Self::Request> {
+        Some(MassiveRequest {
+            from_date: since.format("%Y-%m-%d").to_string(),
+            ..request.clone()
+        })
+    }
+
+    fn rate_limit(&self) -> Option<RateLimit> {
+        Some(RateLimit::per_minute(5))
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 struct MassivePrices {
-    #[serde(rename = "results")]
+    #[serde(rename = "results", default)]
     time_series: Vec<TimeSeriesData>,
     status: String,
-    ticker: String,
+    error: Option<String>,
+    message: Option<String>,
+}
+
+impl MassivePrices {
+    /// The error description, sent either as "error" or as "message"
+    fn message(&self) -> &str {
+        self.error
+            .as_deref()
+            .or(self.message.as_deref())
+            .unwrap_or(&self.status)
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 struct TimeSeriesData {
-    c: f32,
-    h: f32,
-    l: f32,
-    o: f32,
+    c: f64,
+    h: f64,
+    l: f64,
+    o: f64,
     t: i64,
     v: f64,
 }
@@ -1,29 +1,40 @@
 //! Fetch time series stock data from [Twelvedata](https://twelvedata.com/docs#time-series)
 
-use chrono::NaiveDateTime;
+use chrono::{DateTime, NaiveDateTime, Utc};
+use chrono_tz::Tz;
 use serde::{Deserialize, Serialize};
 use url::Url;
 
 use crate::{
-    client::{Interval, MarketSeries, Series},
+    client::{
+        actions::Adjustment, interval::IntervalUnit, rate_limit::RateLimit, Interval, MarketSeries,
+        Series,
+    },
     errors::MarketResult,
-    publishers::Publisher,
+    publishers::{normalize_base_url, parse_timezone, Capabilities, Publisher, SeriesRequest},
     MarketError,
 };
 
-const BASE_URL: &str = "https://api.twelvedata.com/time_series";
+const BASE_URL: &str = "https://api.twelvedata.com/";
+/// The maximum number of data points returned by the API
+const MAX_OUTPUT_SIZE: u32 = 5000;
 
 /// Fetch time series stock data from [Twelvedata](https://twelvedata.com/docs#time-series), implements Publisher trait
 #[derive(Debug)]
 pub struct Twelvedata {
     token: String,
+    base_url: String,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct TDRequest {
     symbol: String,
     interval: String,
     output_size: u32,
+    start_date: Option<String>,
+    end_date: Option<String>,
+    /// the timezone of the dates sent and of the datetimes returned, the exchange one by default
+    timezone: Option<Tz>,
 }
 
 impl Twelvedata {
@@ -31,9 +42,16 @@ impl Twelvedata {
     pub fn new(token: impl Into<String>) -> Self {
         Twelvedata {
             token: token.into(),
+            base_url: BASE_URL.to_string(),
         }
     }
 
+    /// Override the base url, e.g. to route through a proxy or to use a local mock server
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = normalize_base_url(base_url.into());
+        self
+    }
+
     /// Request for intraday series
     pub fn intraday_series(
         &self,
@@ -41,14 +59,9 @@ impl Twelvedata {
         output_size: u32,
         interval: Interval,
     ) -> MarketResult<TDRequest> {
-        let interval_str = match interval {
-            Interval::Min1 => "1min".to_string(),
-            Interval::Min5 => "5min".to_string(),
-            Interval::Min15 => "15min".to_string(),
-            Interval::Min30 => "30min".to_string(),
-            Interval::Hour1 => "1h".to_string(),
-            Interval::Hour2 => "2h".to_string(),
-            Interval::Hour4 => "4h".to_string(),
+        let interval_str = match (interval.unit(), interval.multiplier()) {
+            (IntervalUnit::Minute, m @ (1 | 5 | 15 | 30 | 45)) => format!("{}min", m),
+            (IntervalUnit::Hour, h @ (1 | 2 | 4 | 8)) => format!("{}h", h),
             _ => {
                 return Err(MarketError::UnsuportedInterval(format!(
                     "{} interval is not supported by Twelvedata",
@@ -60,6 +73,9 @@ impl Twelvedata {
             symbol: symbol.into(),
             interval: interval_str,
             output_size,
+            start_date: None,
+            end_date: None,
+            timezone: None,
         })
     }
 
@@ -69,6 +85,9 @@ impl Twelvedata {
             symbol: symbol.into(),
             interval: "1day".to_string(),
             output_size,
+            start_date: None,
+            end_date: None,
+            timezone: None,
         }
     }
 
@@ -78,6 +97,9 @@ impl Twelvedata {
             symbol: symbol.into(),
             interval: "1week".to_string(),
             output_size,
+            start_date: None,
+            end_date: None,
+            timezone: None,
         }
     }
 
@@ -87,6 +109,9 @@ impl Twelvedata {
             symbol: symbol.into(),
             interval: "1month".to_string(),
             output_size,
+            start_date: None,
+            end_date: None,
+            timezone: None,
         }
     }
 }
@@ -94,19 +119,60 @@ impl Twelvedata {
 impl Publisher for Twelvedata {
     type Request = TDRequest;
 
+    fn name(&self) -> &'static str {
+        "twelvedata"
+    }
+
     fn create_endpoint(&self, request: &Self::Request) -> MarketResult<Url> {
-        let base_url = Url::parse(BASE_URL)?;
-        let mut url = base_url;
+        let base_url = Url::parse(&self.base_url)?;
+        let mut url = base_url.join("time_series")?;
         url.query_pairs_mut()
             .append_pair("symbol", &request.symbol)
             .append_pair("interval", &request.interval)
             .append_pair("outputsize", &request.output_size.to_string())
             .append_pair("format", "json")
             .append_pair("apikey", &self.token);
+        if let Some(start_date) = &request.start_date {
+            url.query_pairs_mut().append_pair("start_date", start_date);
+        }
+        if let Some(end_date) = &request.end_date {
+            url.query_pairs_mut().append_pair("end_date", end_date);
+        }
+        if let Some(timezone) = &request.timezone {
+            url.query_pairs_mut()
+                .append_pair("timezone", timezone.name());
+        }
         Ok(url)
     }
 
-    fn transform_data(&self, data: String, _request: &Self::Request) -> MarketResult<MarketSeries> {
+    fn transform_data(&self, data: String, request: &Self::Request) -> MarketResult<MarketSeries> {
+        // the errors are returned with a 200 status code, the actual one is in the payload
+        if let Ok(error) = serde_json::from_str::<TwelvedataError>(&data) {
+            if error.status == "error" {
+                return Err(match error.code {
+                    401 | 403 => MarketError::Unauthorized {
+                        publisher: self.name(),
+                        message: error.message,
+                    },
+                    404 => MarketError::SymbolNotFound {
+                        publisher: self.name(),
+                        symbol: request.symbol.clone(),
+                    },
+                    429 => MarketError::RateLimited {
+                        publisher: self.name(),
+                        retry_after: None,
+                    },
+                    _ if error.message.contains("No data is available") => MarketError::NoData {
+                        publisher: self.name(),
+                    },
+                    _ => MarketError::ProviderMessage {
+                        publisher: self.name(),
+                        message: error.message,
+                    },
+                });
+            }
+        }
+
         let prices: TwelvedataPrices = serde_json::from_str(&data)?;
 
         if prices.status != "ok" {
@@ -119,16 +185,16 @@ impl Publisher for Twelvedata {
         let mut data_series: Vec<Series> = Vec::with_capacity(prices.time_series.len());
 
         for series in prices.time_series.iter() {
-            let open: f32 = series.open.trim().parse().map_err(|e| {
+            let open: f64 = series.open.trim().parse().map_err(|e| {
                 MarketError::ParsingError(format!("Unable to parse Open field: {}", e))
             })?;
-            let close: f32 = series.close.trim().parse().map_err(|e| {
+            let close: f64 = series.close.trim().parse().map_err(|e| {
                 MarketError::ParsingError(format!("Unable to parse Close field: {}", e))
             })?;
-            let high: f32 = series.high.trim().parse().map_err(|e| {
+            let high: f64 = series.high.trim().parse().map_err(|e| {
                 MarketError::ParsingError(format!("Unable to parse High field: {}", e))
             })?;
-            let low: f32 = series.low.trim().parse().map_err(|e| {
+            let low: f64 = series.low.trim().parse().map_err(|e| {
                 MarketError::ParsingError(format!("Unable to parse Low field: {}", e))
             })?;
             let volume: f64 = series.volume.trim().parse().map_err(|e| {
@@ -154,6 +220,7 @@ impl Publisher for Twelvedata {
                 high,
                 low,
                 volume,
+                adj_close: None,
             })
         }
 
@@ -162,10 +229,105 @@ impl Publisher for Twelvedata {
 
         Ok(MarketSeries {
             symbol: prices.meta.symbol.clone(),
-            interval: prices.meta.interval.clone().into(),
+            interval: prices.meta.interval.parse()?,
+            timezone: match (request.timezone, &prices.meta.exchange_timezone) {
+                (Some(timezone), _) => timezone,
+                (None, Some(name)) => parse_timezone(name)?,
+                (None, None) => Tz::UTC,
+            },
             data: data_series,
+            // the prices are adjusted for splits by default
+            adjustment: Adjustment::Splits,
+            actions: Vec::new(),
         })
     }
+
+    /// Without a start date, the bar count is sent as output size.
+    /// The bounds are sent in UTC, so the datetimes are returned in UTC as well.
+    fn series_request(&self, request: &SeriesRequest) -> MarketResult<Self::Request> {
+        let symbol = request.symbol.as_str();
+        let output_size = match (request.start, request.bars) {
+            // at least one bar is requested, fetch_series trims the series to the count
+            (None, Some(bars)) => bars.clamp(1, MAX_OUTPUT_SIZE as usize) as u32,
+            _ => MAX_OUTPUT_SIZE,
+        };
+        let td_request = match request.interval {
+            Interval::Daily => self.daily_series(symbol, output_size),
+            Interval::Weekly => self.weekly_series(symbol, output_size),
+            Interval::Monthly => self.monthly_series(symbol, output_size),
+            _ => self.intraday_series(symbol, output_size, request.interval)?,
+        };
+        let format = |date: DateTime<Utc>| date.format("%Y-%m-%d %H:%M:%S").to_string();
+        let start_date = match (request.start, request.bars) {
+            (None, Some(_)) => None,
+            _ => Some(format(request.range().0)),
+        };
+        let end_date = request.end.map(format);
+        let timezone = match (&start_date, &end_date) {
+            (None, None) => None,
+            _ => Some(Tz::UTC),
+        };
+        Ok(TDRequest {
+            start_date,
+            end_date,
+            timezone,
+            ..td_request
+        })
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        Capabilities {
+            intervals: vec![
+                Interval::Min1,
+                Interval::Min5,
+                Interval::Min15,
+                Interval::Min30,
+                Interval::of(45, IntervalUnit::Minute),
+                Interval::Hour1,
+                Interval::Hour2,
+                Interval::Hour4,
+                Interval::of(8, IntervalUnit::Hour),
+                Interval::Daily,
+                Interval::Weekly,
+                Interval::Monthly,
+            ],
+            any_multiple_of: Vec::new(),
+            max_history: None,
+            max_bars: Some(MAX_OUTPUT_SIZE as usize),
+            requires_api_key: true,
+        }
+    }
+
+    fn request_symbol<'a>(&self, request: &'a Self::Request) -> &'a str {
+        &request.symbol
+    }
+
+    fn cache_key(&self, request: &Self::Request) -> String {
+        let mut key = format!(
+            "{}_{}_{}",
+            request.symbol, request.interval, request.output_size
+        );
+        // a request narrowed by dates is another series
+        for part in [&request.start_date, &request.end_date]
+            .into_iter()
+            .flatten()
+        {
+            key.push('_');
+            key.push_str(&part.replace([' ', ':'], ""));
+        }
+        key
+    }
+
+    fn rate_limit(&self) -> Option<RateLimit> {
+        Some(RateLimit::per_minute(8).with_daily_quota(800))
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+struct TwelvedataError {
+    code: u16,
+    message: String,
+    status: String,
 }
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -180,6 +342,7 @@ struct TwelvedataPrices {
 struct MetaData {
     symbol: String,
     interval: String,
+    exchange_timezone: Option<String>,
 }
 
 #[derive(Debug, Deserialize, Serialize)]
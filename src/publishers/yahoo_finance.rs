@@ -1,21 +1,29 @@
 //! Fetch time series stock data from [Yahoo Finance](https://finance.yahoo.com/)
 
-use chrono::DateTime;
+use chrono::{DateTime, NaiveDateTime, Utc};
+use chrono_tz::Tz;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::fmt;
 use url::Url;
 
 use crate::{
-    client::{Interval, MarketSeries, Series},
+    client::{
+        actions::{Adjustment, CorporateAction},
+        interval::IntervalUnit,
+        Interval, MarketSeries, Series,
+    },
     errors::{MarketError, MarketResult},
-    publishers::Publisher,
+    publishers::{normalize_base_url, parse_timezone, Capabilities, Publisher, SeriesRequest},
 };
 
 const BASE_URL: &str = "https://query1.finance.yahoo.com/v8/finance/chart/";
 
 /// Fetch time series stock data from [Yahoo Finance](https://finance.yahoo.com/), implements Publisher trait
-#[derive(Debug, Default)]
-pub struct YahooFin {}
+#[derive(Debug)]
+pub struct YahooFin {
+    base_url: String,
+}
 
 #[derive(Debug, Clone)]
 pub struct YahooRequest {
@@ -44,7 +52,15 @@ pub enum YahooRange {
 impl YahooFin {
     /// create new instance of YahooFin
     pub fn new() -> Self {
-        YahooFin {}
+        YahooFin {
+            base_url: BASE_URL.to_string(),
+        }
+    }
+
+    /// Override the base url, e.g. to route through a proxy or to use a local mock server
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = normalize_base_url(base_url.into());
+        self
     }
 
     /// Request for intraday series
@@ -54,12 +70,9 @@ impl YahooFin {
         interval: Interval,
         range: YahooRange,
     ) -> MarketResult<YahooRequest> {
-        let interval_str = match interval {
-            Interval::Min1 => "1m".to_string(),
-            Interval::Min5 => "5m".to_string(),
-            Interval::Min15 => "15m".to_string(),
-            Interval::Min30 => "30m".to_string(),
-            Interval::Hour1 => "1h".to_string(),
+        let interval_str = match (interval.unit(), interval.multiplier()) {
+            (IntervalUnit::Minute, m @ (1 | 2 | 5 | 15 | 30 | 90)) => format!("{}m", m),
+            (IntervalUnit::Hour, 1) => "1h".to_string(),
             _ => {
                 return Err(MarketError::UnsuportedInterval(format!(
                     "{} interval is not supported by Yahoo Finance intraday",
@@ -99,7 +112,7 @@ impl YahooFin {
     pub fn monthly_series(&self, symbol: impl Into<String>, range: YahooRange) -> YahooRequest {
         YahooRequest {
             symbol: symbol.into(),
-            interval: "1m".to_string(),
+            interval: "1mo".to_string(),
             range,
             interval_enum: Interval::Monthly,
         }
@@ -109,14 +122,19 @@ impl YahooFin {
 impl Publisher for YahooFin {
     type Request = YahooRequest;
 
+    fn name(&self) -> &'static str {
+        "yahoo_finance"
+    }
+
     fn create_endpoint(&self, request: &Self::Request) -> MarketResult<Url> {
-        let base_url = Url::parse(BASE_URL)?;
+        let base_url = Url::parse(&self.base_url)?;
         let mut url = base_url.join(&request.symbol)?;
         {
             let mut pairs = url.query_pairs_mut();
             pairs.append_pair("metrics", "high");
             pairs.append_pair("interval", &request.interval);
             pairs.append_pair("range", &request.range.to_string());
+            pairs.append_pair("events", "div,splits");
         }
         Ok(url)
     }
@@ -124,20 +142,42 @@ impl Publisher for YahooFin {
     fn transform_data(&self, data: String, request: &Self::Request) -> MarketResult<MarketSeries> {
         let yahoo_prices: YahooPrices = serde_json::from_str(&data)?;
 
-        if let Some(error) = &yahoo_prices.chart.error {
-            return Err(MarketError::DownloadedData(format!(
-                "Yahoo Finance error: {}",
-                error
-            )));
+        if let Some(error) = yahoo_prices.chart.error {
+            return Err(match error.code.as_str() {
+                "Not Found" => MarketError::SymbolNotFound {
+                    publisher: self.name(),
+                    symbol: request.symbol.clone(),
+                },
+                "Unauthorized" | "Forbidden" => MarketError::Unauthorized {
+                    publisher: self.name(),
+                    message: error.description,
+                },
+                "Too Many Requests" => MarketError::RateLimited {
+                    publisher: self.name(),
+                    retry_after: None,
+                },
+                _ => MarketError::ProviderMessage {
+                    publisher: self.name(),
+                    message: format!("{}: {}", error.code, error.description),
+                },
+            });
         }
 
-        if yahoo_prices.chart.result.is_empty() {
-            return Err(MarketError::DownloadedData(
-                "Yahoo Finance returned empty result".to_string(),
-            ));
+        let Some(result) = yahoo_prices.chart.result.as_ref().and_then(|r| r.first()) else {
+            return Err(MarketError::NoData {
+                publisher: self.name(),
+            });
+        };
+        if result.timestamp.is_empty() {
+            return Err(MarketError::NoData {
+                publisher: self.name(),
+            });
         }
-
-        let result = &yahoo_prices.chart.result[0];
+        // the timestamps are converted to the exchange-local time
+        let timezone = match &result.meta.exchange_timezone_name {
+            Some(name) => parse_timezone(name)?,
+            None => Tz::UTC,
+        };
         let mut data_series: Vec<Series> = Vec::new();
 
         for (i, timestamp) in result.timestamp.iter().enumerate() {
@@ -157,26 +197,139 @@ impl Publisher for YahooFin {
             let low = quote.low.get(i).and_then(|v| *v);
             let close = quote.close.get(i).and_then(|v| *v);
             let volume = quote.volume.get(i).and_then(|v| *v);
+            let adj_close = result
+                .indicators
+                .adjclose
+                .first()
+                .and_then(|a| a.adjclose.get(i).copied().flatten());
 
             if let (Some(o), Some(h), Some(l), Some(c), Some(v)) = (open, high, low, close, volume)
             {
                 data_series.push(Series {
-                    datetime: datetime.naive_utc(),
-                    open: o as f32,
-                    high: h as f32,
-                    low: l as f32,
-                    close: c as f32,
+                    datetime: datetime.with_timezone(&timezone).naive_local(),
+                    open: o,
+                    high: h,
+                    low: l,
+                    close: c,
                     volume: v as f64,
+                    adj_close,
                 });
             }
         }
 
+        let local_date = |timestamp: i64| {
+            DateTime::from_timestamp(timestamp, 0)
+                .map(|datetime| datetime.with_timezone(&timezone).date_naive())
+                .ok_or_else(|| MarketError::ParsingError("Unable to parse event date".to_string()))
+        };
+        let mut actions = Vec::new();
+        for dividend in result.events.dividends.values() {
+            actions.push(CorporateAction::Dividend {
+                date: local_date(dividend.date)?,
+                amount: dividend.amount,
+            });
+        }
+        for split in result.events.splits.values() {
+            if split.denominator > 0.0 {
+                actions.push(CorporateAction::Split {
+                    date: local_date(split.date)?,
+                    ratio: split.numerator / split.denominator,
+                });
+            }
+        }
+        actions.sort_by_key(|action| action.date());
+
         Ok(MarketSeries {
             symbol: result.meta.symbol.clone(),
-            interval: request.interval_enum.clone(),
+            interval: request.interval_enum,
+            timezone,
             data: data_series,
+            // the prices are adjusted for splits, the dividends are applied to the adjusted close only
+            adjustment: Adjustment::Splits,
+            actions,
         })
     }
+
+    /// Yahoo Finance accepts only predefined ranges, the smallest one covering the start date is used
+    fn series_request(&self, request: &SeriesRequest) -> MarketResult<Self::Request> {
+        let symbol = request.symbol.as_str();
+        let range = YahooRange::since(request.range().0.naive_utc());
+        match request.interval {
+            Interval::Daily => Ok(self.daily_series(symbol, range)),
+            Interval::Weekly => Ok(self.weekly_series(symbol, range)),
+            Interval::Monthly => Ok(self.monthly_series(symbol, range)),
+            _ => self.intraday_series(symbol, request.interval, range),
+        }
+    }
+
+    /// The intraday history is limited by Yahoo Finance to the last 7 days for 1 minute bars, 60 days otherwise
+    fn capabilities(&self) -> Capabilities {
+        Capabilities {
+            intervals: vec![
+                Interval::Min1,
+                Interval::of(2, IntervalUnit::Minute),
+                Interval::Min5,
+                Interval::Min15,
+                Interval::Min30,
+                Interval::Hour1,
+                Interval::of(90, IntervalUnit::Minute),
+                Interval::Daily,
+                Interval::Weekly,
+                Interval::Monthly,
+            ],
+            any_multiple_of: Vec::new(),
+            max_history: None,
+            max_bars: None,
+            requires_api_key: false,
+        }
+    }
+
+    fn request_symbol<'a>(&self, request: &'a Self::Request) -> &'a str {
+        &request.symbol
+    }
+
+    fn cache_key(&self, request: &Self::Request) -> String {
+        format!("{}_{}_{}", request.symbol, request.interval, request.range)
+    }
+
+    fn refresh_request(
+        &self,
+        request: &Self::Request,
+        since: NaiveDateTime,
+    ) -> Option<Self::Request> {
+        // the other ranges are rolling windows ending now
+        match request.range {
+            YahooRange::Max => Some(YahooRequest {
+                range: YahooRange::since(since),
+                ..request.clone()
+            }),
+            _ => None,
+        }
+    }
+}
+
+impl YahooRange {
+    /// The smallest range covering the bars since the given date
+    fn since(since: NaiveDateTime) -> Self {
+        match (Utc::now().naive_utc() - since).num_days() {
+            ..=0 => YahooRange::Day1,
+            1..=4 => YahooRange::Day5,
+            5..=30 => YahooRange::Month1,
+            31..=91 => YahooRange::Month3,
+            92..=182 => YahooRange::Month6,
+            183..=364 => YahooRange::Year1,
+            365..=729 => YahooRange::Year2,
+            730..=1824 => YahooRange::Year5,
+            1825..=3649 => YahooRange::Year10,
+            _ => YahooRange::Max,
+        }
+    }
+}
+
+impl Default for YahooFin {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -186,25 +339,65 @@ struct YahooPrices {
 
 #[derive(Debug, Deserialize, Serialize)]
 struct Chart {
-    result: Vec<YahooResult>,
-    error: Option<serde_json::Value>,
+    result: Option<Vec<YahooResult>>,
+    error: Option<ChartError>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+struct ChartError {
+    code: String,
+    description: String,
 }
 
 #[derive(Debug, Deserialize, Serialize)]
 struct YahooResult {
     meta: Meta,
+    #[serde(default)]
     timestamp: Vec<i64>,
     indicators: Indicators,
+    #[serde(default)]
+    events: Events,
 }
 
 #[derive(Debug, Deserialize, Serialize)]
 struct Meta {
     symbol: String,
+    #[serde(rename = "exchangeTimezoneName")]
+    exchange_timezone_name: Option<String>,
 }
 
 #[derive(Debug, Deserialize, Serialize)]
 struct Indicators {
     quote: Vec<Quote>,
+    #[serde(default)]
+    adjclose: Vec<AdjClose>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+struct AdjClose {
+    adjclose: Vec<Option<f64>>,
+}
+
+/// The dividends and splits, keyed by their timestamp
+#[derive(Debug, Default, Deserialize, Serialize)]
+struct Events {
+    #[serde(default)]
+    dividends: HashMap<String, Dividend>,
+    #[serde(default)]
+    splits: HashMap<String, Split>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+struct Dividend {
+    amount: f64,
+    date: i64,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+struct Split {
+    date: i64,
+    numerator: f64,
+    denominator: f64,
 }
 
 #[derive(Debug, Deserialize, Serialize)]
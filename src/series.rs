@@ -0,0 +1,17 @@
+//! Transformations of a MarketSeries, applied to the downloaded bars without any API call
+
+#[cfg(feature = "arrow")]
+pub(crate) mod arrow;
+pub(crate) mod binary;
+pub(crate) mod calendar;
+pub(crate) mod columns;
+pub(crate) mod csv;
+pub(crate) mod gaps;
+pub(crate) mod merge;
+#[cfg(feature = "parquet")]
+pub(crate) mod parquet;
+#[cfg(feature = "polars")]
+pub(crate) mod polars;
+pub(crate) mod quality;
+pub(crate) mod resample;
+pub(crate) mod slice;
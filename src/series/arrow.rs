@@ -0,0 +1,183 @@
+//! Convert MarketSeries and EnhancedMarketSeries to Arrow record batches, behind the `arrow` feature
+//!
+//! The datetimes are stored as UTC milliseconds, tagged with the timezone of the series.
+//! The symbol, the interval and the adjustment are stored as schema metadata.
+
+use arrow::{
+    array::{Array, ArrayRef, Float64Array, RecordBatch, TimestampMillisecondArray},
+    datatypes::{DataType, Field, Schema},
+};
+use chrono::{DateTime, Utc};
+use chrono_tz::Tz;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::{
+    client::{actions::Adjustment, Interval, MarketSeries, Series},
+    errors::{MarketError, MarketResult},
+    indicators::EnhancedMarketSeries,
+    publishers::parse_timezone,
+};
+
+const DATETIME: &str = "datetime";
+const PRICES: [&str; 5] = ["open", "high", "low", "close", "volume"];
+const ADJ_CLOSE: &str = "adj_close";
+
+impl MarketSeries {
+    /// One row per bar, with the datetime, OHLCV and adjusted close columns
+    pub fn to_record_batch(&self) -> MarketResult<RecordBatch> {
+        let mut columns = ohlcv_columns(&self.data, self.timezone);
+        columns.push((
+            Field::new(ADJ_CLOSE, DataType::Float64, true),
+            Arc::new(
+                self.data
+                    .iter()
+                    .map(|s| s.adj_close)
+                    .collect::<Float64Array>(),
+            ),
+        ));
+        record_batch(
+            columns,
+            metadata(&self.symbol, self.interval, Some(self.adjustment)),
+        )
+    }
+
+    /// Reads the bars of a record batch written by `to_record_batch`
+    ///
+    /// The symbol and the interval are read from the schema metadata, the adjusted close column is optional.
+    pub fn from_record_batch(batch: &RecordBatch) -> MarketResult<MarketSeries> {
+        let schema = batch.schema();
+        let metadata = schema.metadata();
+        let read = |key: &str| {
+            metadata.get(key).ok_or_else(|| {
+                MarketError::ParsingError(format!("missing schema metadata: {}", key))
+            })
+        };
+        let symbol = read("symbol")?.clone();
+        let interval = read("interval")?.parse()?;
+        // a batch written elsewhere may not record it
+        let adjustment = match metadata.get("adjustment") {
+            Some(adjustment) => adjustment.parse()?,
+            None => Adjustment::Raw,
+        };
+
+        let datetimes = batch
+            .column_by_name(DATETIME)
+            .and_then(|column| column.as_any().downcast_ref::<TimestampMillisecondArray>())
+            .ok_or_else(|| missing_column(DATETIME))?;
+        let timezone = match datetimes.timezone() {
+            Some(name) => parse_timezone(name)?,
+            None => Tz::UTC,
+        };
+        let [open, high, low, close, volume] = PRICES.map(|name| float_column(batch, name));
+        let (open, high, low, close, volume) = (open?, high?, low?, close?, volume?);
+        let adj_close = float_column(batch, ADJ_CLOSE).ok();
+
+        let mut data = Vec::with_capacity(batch.num_rows());
+        for row in 0..batch.num_rows() {
+            let datetime = DateTime::<Utc>::from_timestamp_millis(datetimes.value(row))
+                .ok_or_else(|| {
+                    MarketError::ParsingError(format!(
+                        "invalid timestamp: {}",
+                        datetimes.value(row)
+                    ))
+                })?;
+            data.push(Series {
+                datetime: datetime.with_timezone(&timezone).naive_local(),
+                open: open.value(row),
+                close: close.value(row),
+                high: high.value(row),
+                low: low.value(row),
+                volume: volume.value(row),
+                adj_close: adj_close
+                    .filter(|adj_close| adj_close.is_valid(row))
+                    .map(|adj_close| adj_close.value(row)),
+            });
+        }
+
+        Ok(MarketSeries {
+            symbol,
+            interval,
+            timezone,
+            data,
+            adjustment,
+            actions: Vec::new(),
+        })
+    }
+}
+
+impl EnhancedMarketSeries {
+    /// One row per bar, with the datetime and OHLCV columns followed by one column per computed indicator
+    ///
+    /// The indicator columns are named like "SMA 10" or "MACD (12, 26, 9)/signal", the warm-up rows are null.
+    pub fn to_record_batch(&self) -> MarketResult<RecordBatch> {
+        let mut columns = ohlcv_columns(&self.series, self.timezone);
+        for column in self.columns() {
+            let values: Float64Array = (0..self.series.len())
+                .map(|row| column.value(row))
+                .collect();
+            columns.push((
+                Field::new(column.name.clone(), DataType::Float64, true),
+                Arc::new(values),
+            ));
+        }
+        record_batch(columns, metadata(&self.symbol, self.interval, None))
+    }
+}
+
+/// The symbol, the interval and the adjustment, if known, as schema metadata
+fn metadata(
+    symbol: &str,
+    interval: Interval,
+    adjustment: Option<Adjustment>,
+) -> HashMap<String, String> {
+    let mut metadata = HashMap::from([
+        ("symbol".to_string(), symbol.to_string()),
+        ("interval".to_string(), interval.to_string()),
+    ]);
+    if let Some(adjustment) = adjustment {
+        metadata.insert("adjustment".to_string(), adjustment.to_string());
+    }
+    metadata
+}
+
+fn ohlcv_columns(data: &[Series], timezone: Tz) -> Vec<(Field, ArrayRef)> {
+    let datetimes = TimestampMillisecondArray::from_iter_values(
+        data.iter()
+            .map(|s| s.datetime_in(timezone).timestamp_millis()),
+    )
+    .with_timezone(timezone.name());
+    let mut columns: Vec<(Field, ArrayRef)> = vec![(
+        Field::new(DATETIME, datetimes.data_type().clone(), false),
+        Arc::new(datetimes),
+    )];
+    let values: [fn(&Series) -> f64; 5] =
+        [|s| s.open, |s| s.high, |s| s.low, |s| s.close, |s| s.volume];
+    for (name, value) in PRICES.into_iter().zip(values) {
+        columns.push((
+            Field::new(name, DataType::Float64, false),
+            Arc::new(data.iter().map(value).collect::<Float64Array>()),
+        ));
+    }
+    columns
+}
+
+fn record_batch(
+    columns: Vec<(Field, ArrayRef)>,
+    metadata: HashMap<String, String>,
+) -> MarketResult<RecordBatch> {
+    let (fields, arrays): (Vec<Field>, Vec<ArrayRef>) = columns.into_iter().unzip();
+    let schema = Schema::new(fields).with_metadata(metadata);
+    RecordBatch::try_new(Arc::new(schema), arrays).map_err(|e| MarketError::ToWriter(e.to_string()))
+}
+
+fn float_column<'a>(batch: &'a RecordBatch, name: &str) -> MarketResult<&'a Float64Array> {
+    batch
+        .column_by_name(name)
+        .and_then(|column| column.as_any().downcast_ref::<Float64Array>())
+        .ok_or_else(|| missing_column(name))
+}
+
+fn missing_column(name: &str) -> MarketError {
+    MarketError::ParsingError(format!("missing or mistyped column: {}", name))
+}
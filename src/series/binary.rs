@@ -0,0 +1,520 @@
+//! A compact binary encoding of MarketSeries, for bulk archives of long series
+//!
+//! The layout is a header followed by one record per bar, until the end of the input:
+//!
+//! - header: the "MKTD" magic, the format version, the quantization flags and decimals, the symbol,
+//!   the interval, the timezone, the adjustment and the corporate actions
+//! - bar: a flags byte, the datetime as the zigzag varint of the seconds since the previous bar,
+//!   the open, high, low, close and optional adjusted close prices, and the volume
+//!
+//! The prices and the volumes are stored as little endian f64, losslessly, unless they are quantized
+//! to a number of decimals. The quantized prices are stored as zigzag varints of their difference
+//! with the previous close, which takes two or three bytes for most bars.
+
+use chrono::{DateTime, Datelike, NaiveDate, Timelike};
+use std::io::{self, Read, Write};
+
+use crate::{
+    client::{
+        actions::{Adjustment, CorporateAction},
+        Interval, MarketSeries, Series,
+    },
+    errors::{MarketError, MarketResult},
+    publishers::parse_timezone,
+    Tz,
+};
+
+const MAGIC: &[u8; 4] = b"MKTD";
+const VERSION: u8 = 1;
+
+const QUANTIZED_PRICES: u8 = 1;
+const QUANTIZED_VOLUMES: u8 = 1 << 1;
+const HAS_ADJ_CLOSE: u8 = 1;
+
+/// Whether the prices and the volumes are stored losslessly, by default, or rounded to a number of decimals
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BinaryOptions {
+    price_decimals: Option<u8>,
+    volume_decimals: Option<u8>,
+}
+
+impl BinaryOptions {
+    /// Round the open, high, low, close and adjusted close prices, like 4 decimals for equities
+    ///
+    /// The prices that have at most this many decimals are read back unchanged.
+    pub fn with_price_decimals(mut self, decimals: u8) -> Self {
+        self.price_decimals = Some(decimals);
+        self
+    }
+
+    /// Round the volumes, like 0 decimals for the whole numbers of shares
+    pub fn with_volume_decimals(mut self, decimals: u8) -> Self {
+        self.volume_decimals = Some(decimals);
+        self
+    }
+}
+
+/// The metadata of a series, written once before its bars
+#[derive(Debug, Clone, PartialEq)]
+pub struct BinaryHeader {
+    pub symbol: String,
+    pub interval: Interval,
+    pub timezone: Tz,
+    pub adjustment: Adjustment,
+    pub actions: Vec<CorporateAction>,
+}
+
+impl From<&MarketSeries> for BinaryHeader {
+    fn from(series: &MarketSeries) -> Self {
+        BinaryHeader {
+            symbol: series.symbol.clone(),
+            interval: series.interval,
+            timezone: series.timezone,
+            adjustment: series.adjustment,
+            actions: series.actions.clone(),
+        }
+    }
+}
+
+impl MarketSeries {
+    /// Writes the series losslessly in the binary format, see `BinaryOptions` to quantize the values
+    pub fn to_binary(&self, writer: impl Write) -> MarketResult<()> {
+        self.to_binary_with(writer, &BinaryOptions::default())
+    }
+
+    pub fn to_binary_with(&self, writer: impl Write, options: &BinaryOptions) -> MarketResult<()> {
+        let mut writer = BinaryWriter::new(writer, &BinaryHeader::from(self), options)?;
+        for bar in self.data.iter() {
+            writer.write(bar)?;
+        }
+        writer.finish()?;
+        Ok(())
+    }
+
+    /// The series in the binary format, as bytes
+    pub fn to_bytes(&self) -> MarketResult<Vec<u8>> {
+        let mut bytes = Vec::new();
+        self.to_binary(&mut bytes)?;
+        Ok(bytes)
+    }
+
+    /// Reads a series written in the binary format, wrap a file in a `std::io::BufReader`
+    pub fn from_binary(reader: impl Read) -> MarketResult<MarketSeries> {
+        let mut reader = BinaryReader::new(reader)?;
+        let data = reader.by_ref().collect::<MarketResult<Vec<Series>>>()?;
+        let header = reader.header;
+        Ok(MarketSeries {
+            symbol: header.symbol,
+            interval: header.interval,
+            timezone: header.timezone,
+            data,
+            adjustment: header.adjustment,
+            actions: header.actions,
+        })
+    }
+
+    /// Reads a whole series from bytes in the binary format, iterate a `BinaryReader` over the bytes
+    /// to read the bars one at a time instead
+    pub fn from_bytes(bytes: &[u8]) -> MarketResult<MarketSeries> {
+        MarketSeries::from_binary(bytes)
+    }
+}
+
+/// Writes the bars one by one, for series too long to be held in memory
+///
+/// The datetimes are stored with a precision of one second.
+#[derive(Debug)]
+pub struct BinaryWriter<W: Write> {
+    writer: W,
+    options: BinaryOptions,
+    buffer: Vec<u8>,
+    previous_datetime: i64,
+    previous_close: i64,
+}
+
+impl<W: Write> BinaryWriter<W> {
+    /// Writes the header, the bars are written next with `write`
+    pub fn new(writer: W, header: &BinaryHeader, options: &BinaryOptions) -> MarketResult<Self> {
+        let mut buffer = MAGIC.to_vec();
+        buffer.push(VERSION);
+        let mut flags = 0;
+        if options.price_decimals.is_some() {
+            flags |= QUANTIZED_PRICES;
+        }
+        if options.volume_decimals.is_some() {
+            flags |= QUANTIZED_VOLUMES;
+        }
+        buffer.push(flags);
+        buffer.extend(options.price_decimals);
+        buffer.extend(options.volume_decimals);
+
+        write_str(&mut buffer, &header.symbol);
+        write_str(&mut buffer, &header.interval.to_string());
+        write_str(&mut buffer, header.timezone.name());
+        buffer.push(match header.adjustment {
+            Adjustment::Raw => 0,
+            Adjustment::Splits => 1,
+            Adjustment::SplitsAndDividends => 2,
+        });
+        write_varint(&mut buffer, header.actions.len() as u64);
+        for action in header.actions.iter() {
+            let (kind, value) = match action {
+                CorporateAction::Dividend { amount, .. } => (0, amount),
+                CorporateAction::Split { ratio, .. } => (1, ratio),
+            };
+            buffer.push(kind);
+            write_varint(&mut buffer, zigzag(action.date().num_days_from_ce() as i64));
+            buffer.extend(value.to_le_bytes());
+        }
+
+        let mut writer = BinaryWriter {
+            writer,
+            options: *options,
+            buffer,
+            previous_datetime: 0,
+            previous_close: 0,
+        };
+        writer.flush_buffer()?;
+        Ok(writer)
+    }
+
+    /// Appends a bar
+    pub fn write(&mut self, bar: &Series) -> MarketResult<()> {
+        if bar.datetime.nanosecond() != 0 {
+            return Err(MarketError::ToWriter(format!(
+                "{} has a fraction of second",
+                bar.datetime
+            )));
+        }
+        // quantize everything first, a bar that fails is not written and doesn't move the deltas
+        let prices = [
+            bar.open,
+            bar.high,
+            bar.low,
+            bar.close,
+            bar.adj_close.unwrap_or_default(),
+        ];
+        let count = if bar.adj_close.is_some() { 5 } else { 4 };
+        let quantized = match self.options.price_decimals {
+            Some(decimals) => {
+                let mut quantized = [0i64; 5];
+                for (value, price) in quantized.iter_mut().zip(prices).take(count) {
+                    *value = quantize(price, decimals)?;
+                }
+                Some(quantized)
+            }
+            None => None,
+        };
+        let volume = match self.options.volume_decimals {
+            Some(decimals) => Some(quantize(bar.volume, decimals)?),
+            None => None,
+        };
+
+        self.buffer.clear();
+        self.buffer.push(if bar.adj_close.is_some() {
+            HAS_ADJ_CLOSE
+        } else {
+            0
+        });
+        let datetime = bar.datetime.and_utc().timestamp();
+        write_varint(
+            &mut self.buffer,
+            zigzag(datetime.wrapping_sub(self.previous_datetime)),
+        );
+        match quantized {
+            Some(quantized) => {
+                for price in &quantized[..count] {
+                    write_varint(
+                        &mut self.buffer,
+                        zigzag(price.wrapping_sub(self.previous_close)),
+                    );
+                }
+            }
+            None => {
+                for price in &prices[..count] {
+                    self.buffer.extend(price.to_le_bytes());
+                }
+            }
+        }
+        match volume {
+            Some(volume) => write_varint(&mut self.buffer, zigzag(volume)),
+            None => self.buffer.extend(bar.volume.to_le_bytes()),
+        }
+        self.flush_buffer()?;
+
+        self.previous_datetime = datetime;
+        if let Some(quantized) = quantized {
+            self.previous_close = quantized[3];
+        }
+        Ok(())
+    }
+
+    /// Flushes and returns the underlying writer
+    pub fn finish(mut self) -> MarketResult<W> {
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+
+    fn flush_buffer(&mut self) -> MarketResult<()> {
+        self.writer.write_all(&self.buffer)?;
+        self.buffer.clear();
+        Ok(())
+    }
+}
+
+/// Reads the bars one by one, as an iterator, after the header
+///
+/// Wrap a file in a `std::io::BufReader`, a `&[u8]` is read as is.
+#[derive(Debug)]
+pub struct BinaryReader<R: Read> {
+    reader: R,
+    header: BinaryHeader,
+    price_decimals: Option<u8>,
+    volume_decimals: Option<u8>,
+    previous_datetime: i64,
+    previous_close: i64,
+    done: bool,
+}
+
+impl<R: Read> BinaryReader<R> {
+    /// Reads and checks the header
+    pub fn new(mut reader: R) -> MarketResult<Self> {
+        let mut magic = [0u8; 4];
+        reader.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(MarketError::ParsingError(
+                "not a binary MarketSeries, the magic bytes differ".to_string(),
+            ));
+        }
+        let version = read_u8(&mut reader)?;
+        if version != VERSION {
+            return Err(MarketError::ParsingError(format!(
+                "unsupported binary format version: {}",
+                version
+            )));
+        }
+        let flags = read_u8(&mut reader)?;
+        if flags & !(QUANTIZED_PRICES | QUANTIZED_VOLUMES) != 0 {
+            return Err(MarketError::ParsingError(format!(
+                "unknown header flags: {:#04x}",
+                flags
+            )));
+        }
+        let price_decimals = match flags & QUANTIZED_PRICES {
+            0 => None,
+            _ => Some(read_u8(&mut reader)?),
+        };
+        let volume_decimals = match flags & QUANTIZED_VOLUMES {
+            0 => None,
+            _ => Some(read_u8(&mut reader)?),
+        };
+
+        let symbol = read_str(&mut reader)?;
+        let interval = read_str(&mut reader)?.parse()?;
+        let timezone = parse_timezone(&read_str(&mut reader)?)?;
+        let adjustment = match read_u8(&mut reader)? {
+            0 => Adjustment::Raw,
+            1 => Adjustment::Splits,
+            2 => Adjustment::SplitsAndDividends,
+            other => {
+                return Err(MarketError::ParsingError(format!(
+                    "unknown adjustment: {}",
+                    other
+                )))
+            }
+        };
+        let count = read_varint(&mut reader)?;
+        let mut actions = Vec::new();
+        for _ in 0..count {
+            let kind = read_u8(&mut reader)?;
+            let days = unzigzag(read_varint(&mut reader)?);
+            let date = i32::try_from(days)
+                .ok()
+                .and_then(NaiveDate::from_num_days_from_ce_opt)
+                .ok_or_else(|| MarketError::ParsingError(format!("invalid date: {}", days)))?;
+            let value = read_f64(&mut reader)?;
+            actions.push(match kind {
+                0 => CorporateAction::Dividend {
+                    date,
+                    amount: value,
+                },
+                1 => CorporateAction::Split { date, ratio: value },
+                other => {
+                    return Err(MarketError::ParsingError(format!(
+                        "unknown corporate action: {}",
+                        other
+                    )))
+                }
+            });
+        }
+
+        Ok(BinaryReader {
+            reader,
+            header: BinaryHeader {
+                symbol,
+                interval,
+                timezone,
+                adjustment,
+                actions,
+            },
+            price_decimals,
+            volume_decimals,
+            previous_datetime: 0,
+            previous_close: 0,
+            done: false,
+        })
+    }
+
+    pub fn header(&self) -> &BinaryHeader {
+        &self.header
+    }
+
+    /// The next bar, `None` at the end of the input
+    fn read_bar(&mut self) -> MarketResult<Option<Series>> {
+        let mut flags = [0u8; 1];
+        loop {
+            match self.reader.read(&mut flags) {
+                Ok(0) => return Ok(None),
+                Ok(_) => break,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e.into()),
+            }
+        }
+        if flags[0] & !HAS_ADJ_CLOSE != 0 {
+            return Err(MarketError::ParsingError(format!(
+                "unknown bar flags: {:#04x}",
+                flags[0]
+            )));
+        }
+        let has_adj_close = flags[0] & HAS_ADJ_CLOSE != 0;
+
+        let seconds = self
+            .previous_datetime
+            .wrapping_add(unzigzag(read_varint(&mut self.reader)?));
+        self.previous_datetime = seconds;
+        let datetime = DateTime::from_timestamp(seconds, 0)
+            .ok_or_else(|| MarketError::ParsingError(format!("invalid timestamp: {}", seconds)))?
+            .naive_utc();
+
+        let mut prices = [0.0; 5];
+        let count = if has_adj_close { 5 } else { 4 };
+        match self.price_decimals {
+            Some(decimals) => {
+                let mut quantized = [0i64; 5];
+                for value in quantized.iter_mut().take(count) {
+                    *value = self
+                        .previous_close
+                        .wrapping_add(unzigzag(read_varint(&mut self.reader)?));
+                }
+                self.previous_close = quantized[3];
+                for (price, value) in prices.iter_mut().zip(quantized) {
+                    *price = dequantize(value, decimals);
+                }
+            }
+            None => {
+                for price in prices.iter_mut().take(count) {
+                    *price = read_f64(&mut self.reader)?;
+                }
+            }
+        }
+        let volume = match self.volume_decimals {
+            Some(decimals) => dequantize(unzigzag(read_varint(&mut self.reader)?), decimals),
+            None => read_f64(&mut self.reader)?,
+        };
+
+        Ok(Some(Series {
+            datetime,
+            open: prices[0],
+            close: prices[3],
+            high: prices[1],
+            low: prices[2],
+            volume,
+            adj_close: has_adj_close.then_some(prices[4]),
+        }))
+    }
+}
+
+impl<R: Read> Iterator for BinaryReader<R> {
+    type Item = MarketResult<Series>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let bar = self.read_bar().transpose();
+        // stop after the end of the input or an error, a truncated bar can't be skipped
+        self.done = !matches!(bar, Some(Ok(_)));
+        bar
+    }
+}
+
+fn quantize(value: f64, decimals: u8) -> MarketResult<i64> {
+    let scaled = (value * 10f64.powi(decimals as i32)).round();
+    // i64::MAX as f64 rounds up to 2^63, which is out of range
+    if !scaled.is_finite() || scaled.abs() >= i64::MAX as f64 {
+        return Err(MarketError::ToWriter(format!(
+            "{} can't be quantized to {} decimals",
+            value, decimals
+        )));
+    }
+    Ok(scaled as i64)
+}
+
+fn dequantize(value: i64, decimals: u8) -> f64 {
+    value as f64 / 10f64.powi(decimals as i32)
+}
+
+fn zigzag(value: i64) -> u64 {
+    ((value << 1) ^ (value >> 63)) as u64
+}
+
+fn unzigzag(value: u64) -> i64 {
+    ((value >> 1) as i64) ^ -((value & 1) as i64)
+}
+
+fn write_varint(buffer: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        buffer.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    buffer.push(value as u8);
+}
+
+fn read_varint(reader: &mut impl Read) -> MarketResult<u64> {
+    let mut value = 0u64;
+    for shift in (0..64).step_by(7) {
+        let byte = read_u8(reader)?;
+        value |= ((byte & 0x7f) as u64) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+    Err(MarketError::ParsingError("varint too long".to_string()))
+}
+
+fn write_str(buffer: &mut Vec<u8>, value: &str) {
+    write_varint(buffer, value.len() as u64);
+    buffer.extend(value.as_bytes());
+}
+
+fn read_str(reader: &mut impl Read) -> MarketResult<String> {
+    let len = read_varint(reader)?;
+    let mut bytes = Vec::new();
+    reader.take(len).read_to_end(&mut bytes)?;
+    if bytes.len() as u64 != len {
+        return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+    }
+    String::from_utf8(bytes).map_err(|e| MarketError::ParsingError(e.to_string()))
+}
+
+fn read_u8(reader: &mut impl Read) -> MarketResult<u8> {
+    let mut byte = [0u8; 1];
+    reader.read_exact(&mut byte)?;
+    Ok(byte[0])
+}
+
+fn read_f64(reader: &mut impl Read) -> MarketResult<f64> {
+    let mut bytes = [0u8; 8];
+    reader.read_exact(&mut bytes)?;
+    Ok(f64::from_le_bytes(bytes))
+}
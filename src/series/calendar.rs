@@ -0,0 +1,90 @@
+//! The trading days and hours of a market, used to tell the missing bars from the closed market
+
+use chrono::{Datelike, NaiveDate, NaiveDateTime, NaiveTime, Weekday};
+use std::collections::HashSet;
+
+use crate::client::{interval::IntervalUnit, Interval};
+
+/// The days and hours a market trades, in the timezone of the MarketSeries it is applied to
+#[derive(Debug, Clone)]
+pub struct TradingCalendar {
+    weekends: bool,
+    holidays: HashSet<NaiveDate>,
+    session: Option<(NaiveTime, NaiveTime)>,
+}
+
+impl Default for TradingCalendar {
+    /// Always open, like the crypto markets
+    fn default() -> Self {
+        TradingCalendar::always_open()
+    }
+}
+
+impl TradingCalendar {
+    /// Open every day, around the clock
+    pub fn always_open() -> Self {
+        TradingCalendar {
+            weekends: true,
+            holidays: HashSet::new(),
+            session: None,
+        }
+    }
+
+    /// Open from Monday to Friday, around the clock
+    pub fn weekdays() -> Self {
+        TradingCalendar {
+            weekends: false,
+            ..TradingCalendar::always_open()
+        }
+    }
+
+    /// The regular session of the US stock exchanges, from 9:30 to 16:00 on weekdays
+    ///
+    /// The holidays are not included, add them with `with_holidays`.
+    pub fn us_equities() -> Self {
+        TradingCalendar::weekdays().with_session(
+            NaiveTime::from_hms_opt(9, 30, 0).unwrap_or_default(),
+            NaiveTime::from_hms_opt(16, 0, 0).unwrap_or_default(),
+        )
+    }
+
+    /// The dates the market is closed
+    pub fn with_holidays(mut self, holidays: impl IntoIterator<Item = NaiveDate>) -> Self {
+        self.holidays.extend(holidays);
+        self
+    }
+
+    /// The opening and closing time of a trading day, the bars start at or after the open and before the close
+    pub fn with_session(mut self, open: NaiveTime, close: NaiveTime) -> Self {
+        self.session = Some((open, close));
+        self
+    }
+
+    /// The opening time of the session, `None` if the market trades around the clock
+    pub fn session_open(&self) -> Option<NaiveTime> {
+        self.session.map(|(open, _)| open)
+    }
+
+    pub fn is_trading_day(&self, date: NaiveDate) -> bool {
+        let weekend = matches!(date.weekday(), Weekday::Sat | Weekday::Sun);
+        (self.weekends || !weekend) && !self.holidays.contains(&date)
+    }
+
+    /// Whether an intraday bar can start at this datetime
+    pub fn is_open(&self, datetime: NaiveDateTime) -> bool {
+        let in_session = match self.session {
+            Some((open, close)) => datetime.time() >= open && datetime.time() < close,
+            None => true,
+        };
+        in_session && self.is_trading_day(datetime.date())
+    }
+
+    /// Whether a bar of the interval is expected at this datetime, the weekly and longer bars always are
+    pub(crate) fn expects(&self, interval: Interval, datetime: NaiveDateTime) -> bool {
+        match interval.unit() {
+            IntervalUnit::Minute | IntervalUnit::Hour => self.is_open(datetime),
+            IntervalUnit::Day => self.is_trading_day(datetime.date()),
+            _ => true,
+        }
+    }
+}
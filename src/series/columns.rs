@@ -0,0 +1,164 @@
+//! A struct-of-arrays view on the bars of a MarketSeries, with one contiguous array per field
+//!
+//! The indicators walk a single field at a time, so they run on these arrays instead of the bars.
+
+use chrono::NaiveDateTime;
+use std::collections::VecDeque;
+
+use crate::{
+    client::{MarketSeries, Series},
+    errors::{MarketError, MarketResult},
+    indicators::{
+        bollinger::calculate_bollinger_bands, ema::calculate_ema, macd::calculate_macd,
+        rsi::calculate_rsi, sma::calculate_sma, stochastic::calculate_stochastic,
+        TripleIndicatorData,
+    },
+};
+
+/// The datetime, open, high, low, close and volume of the bars, as separate arrays
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SeriesColumns {
+    datetime: Vec<NaiveDateTime>,
+    open: Vec<f64>,
+    high: Vec<f64>,
+    low: Vec<f64>,
+    close: Vec<f64>,
+    volume: Vec<f64>,
+}
+
+impl SeriesColumns {
+    /// Build the columns from one array per field, all of them should have the same length
+    pub fn new(
+        datetime: Vec<NaiveDateTime>,
+        open: Vec<f64>,
+        high: Vec<f64>,
+        low: Vec<f64>,
+        close: Vec<f64>,
+        volume: Vec<f64>,
+    ) -> MarketResult<Self> {
+        let len = close.len();
+        let lengths = [
+            datetime.len(),
+            open.len(),
+            high.len(),
+            low.len(),
+            volume.len(),
+        ];
+        if lengths.iter().any(|&other| other != len) {
+            return Err(MarketError::ParsingError(format!(
+                "The columns have different lengths: datetime {}, open {}, high {}, low {}, close {}, volume {}",
+                lengths[0], lengths[1], lengths[2], lengths[3], len, lengths[4]
+            )));
+        }
+        Ok(SeriesColumns {
+            datetime,
+            open,
+            high,
+            low,
+            close,
+            volume,
+        })
+    }
+
+    /// Only the high, low and close columns, the ones the indicators walk, the others are left empty
+    pub(crate) fn prices(series: &[Series]) -> Self {
+        SeriesColumns {
+            high: series.iter().map(|s| s.high).collect(),
+            low: series.iter().map(|s| s.low).collect(),
+            close: series.iter().map(|s| s.close).collect(),
+            ..SeriesColumns::default()
+        }
+    }
+
+    pub fn datetime(&self) -> &[NaiveDateTime] {
+        &self.datetime
+    }
+
+    pub fn open(&self) -> &[f64] {
+        &self.open
+    }
+
+    pub fn high(&self) -> &[f64] {
+        &self.high
+    }
+
+    pub fn low(&self) -> &[f64] {
+        &self.low
+    }
+
+    pub fn close(&self) -> &[f64] {
+        &self.close
+    }
+
+    pub fn volume(&self) -> &[f64] {
+        &self.volume
+    }
+
+    /// The number of bars
+    pub fn len(&self) -> usize {
+        self.close.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.close.is_empty()
+    }
+
+    /// Simple Moving Average of the close prices, the first `period - 1` values are 0.0
+    pub fn sma(&self, period: usize) -> VecDeque<f64> {
+        calculate_sma(&self.close, period)
+    }
+
+    /// Exponential Moving Average of the close prices
+    pub fn ema(&self, period: usize) -> VecDeque<f64> {
+        calculate_ema(&self.close, period)
+    }
+
+    /// Relative Strength Index of the close prices, the first `period` values are 0.0
+    pub fn rsi(&self, period: usize) -> VecDeque<f64> {
+        calculate_rsi(&self.close, period)
+    }
+
+    /// Stochastic Oscillator (%K), the first `period` values are 0.0
+    pub fn stochastic(&self, period: usize) -> VecDeque<f64> {
+        calculate_stochastic(&self.high, &self.low, &self.close, period)
+    }
+
+    /// MACD, signal and histogram lines, for the fast, slow & signal EMA periods
+    pub fn macd(&self, fast: usize, slow: usize, signal: usize) -> TripleIndicatorData {
+        calculate_macd(&self.close, fast, slow, signal)
+    }
+
+    /// Upper, middle and lower Bollinger Bands, the first `period` upper and lower values are 0.0
+    pub fn bollinger_bands(&self, period: usize, std_dev: usize) -> TripleIndicatorData {
+        calculate_bollinger_bands(&self.high, &self.low, &self.close, period, std_dev)
+    }
+}
+
+impl From<&[Series]> for SeriesColumns {
+    fn from(series: &[Series]) -> Self {
+        let mut columns = SeriesColumns {
+            datetime: Vec::with_capacity(series.len()),
+            open: Vec::with_capacity(series.len()),
+            high: Vec::with_capacity(series.len()),
+            low: Vec::with_capacity(series.len()),
+            close: Vec::with_capacity(series.len()),
+            volume: Vec::with_capacity(series.len()),
+        };
+        for bar in series {
+            columns.datetime.push(bar.datetime);
+            columns.open.push(bar.open);
+            columns.high.push(bar.high);
+            columns.low.push(bar.low);
+            columns.close.push(bar.close);
+            columns.volume.push(bar.volume);
+        }
+        columns
+    }
+}
+
+impl MarketSeries {
+    /// The bars as one array per field, to run the indicators on a long series without building an EnhancedMarketSeries
+    pub fn to_columns(&self) -> SeriesColumns {
+        SeriesColumns::from(self.data.as_slice())
+    }
+}
@@ -0,0 +1,286 @@
+//! Write and read MarketSeries as CSV, and export the EnhancedMarketSeries with their indicators
+
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
+use chrono_tz::Tz;
+use csv::{ReaderBuilder, StringRecord, WriterBuilder};
+use std::io::{Read, Write};
+
+use crate::{
+    client::{actions::Adjustment, Interval, MarketSeries, Series},
+    errors::{MarketError, MarketResult},
+    indicators::EnhancedMarketSeries,
+};
+
+/// A column of the CSV written and read for a MarketSeries
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CsvColumn {
+    Datetime,
+    Open,
+    High,
+    Low,
+    Close,
+    Volume,
+    /// written only if a bar has an adjusted close, optional when reading
+    AdjClose,
+}
+
+const COLUMNS: [CsvColumn; 7] = [
+    CsvColumn::Datetime,
+    CsvColumn::Open,
+    CsvColumn::High,
+    CsvColumn::Low,
+    CsvColumn::Close,
+    CsvColumn::Volume,
+    CsvColumn::AdjClose,
+];
+
+/// The layout of the CSV: delimiter, datetime format, column names and timezone
+#[derive(Debug, Clone)]
+pub struct CsvOptions {
+    delimiter: u8,
+    date_format: String,
+    names: [String; 7],
+    timezone: Option<Tz>,
+}
+
+impl Default for CsvOptions {
+    /// comma separated, "%Y-%m-%d %H:%M:%S" datetimes, lower case column names like "open" and "adj_close"
+    fn default() -> Self {
+        CsvOptions {
+            delimiter: b',',
+            date_format: "%Y-%m-%d %H:%M:%S".to_string(),
+            names: [
+                "datetime",
+                "open",
+                "high",
+                "low",
+                "close",
+                "volume",
+                "adj_close",
+            ]
+            .map(String::from),
+            timezone: None,
+        }
+    }
+}
+
+impl CsvOptions {
+    pub fn with_delimiter(mut self, delimiter: u8) -> Self {
+        self.delimiter = delimiter;
+        self
+    }
+
+    /// A chrono format string, a date only format like "%Y-%m-%d" reads the datetimes at midnight
+    pub fn with_date_format(mut self, date_format: impl Into<String>) -> Self {
+        self.date_format = date_format.into();
+        self
+    }
+
+    /// Rename a column, like `CsvColumn::Datetime` to "Date"
+    pub fn with_column_name(mut self, column: CsvColumn, name: impl Into<String>) -> Self {
+        self.names[column as usize] = name.into();
+        self
+    }
+
+    /// The timezone of the datetimes written to and read from the CSV
+    ///
+    /// By default the bars are written in the timezone of their series, so the dates of the daily bars
+    /// don't move, and read as UTC.
+    pub fn with_timezone(mut self, timezone: Tz) -> Self {
+        self.timezone = Some(timezone);
+        self
+    }
+
+    fn name(&self, column: CsvColumn) -> &str {
+        &self.names[column as usize]
+    }
+
+    /// The datetime of the bar, stamped in `timezone`, converted to the timezone of the CSV if there is one
+    fn format_datetime(&self, series: &Series, timezone: Tz) -> String {
+        match self.timezone {
+            Some(to) => series
+                .datetime_in(timezone)
+                .with_timezone(&to)
+                .format(&self.date_format)
+                .to_string(),
+            None => series.datetime.format(&self.date_format).to_string(),
+        }
+    }
+
+    fn parse_datetime(&self, value: &str) -> MarketResult<NaiveDateTime> {
+        NaiveDateTime::parse_from_str(value, &self.date_format)
+            .or_else(|_| {
+                NaiveDate::parse_from_str(value, &self.date_format)
+                    .map(|date| date.and_time(NaiveTime::MIN))
+            })
+            .map_err(|e| MarketError::ParsingError(format!("datetime {}: {}", value, e)))
+    }
+}
+
+impl MarketSeries {
+    /// Writes the bars as CSV with the default options, see `CsvOptions`
+    pub fn to_csv(&self, writer: impl Write) -> MarketResult<()> {
+        self.to_csv_with(writer, &CsvOptions::default())
+    }
+
+    /// Writes the bars as CSV, the datetimes are converted to the timezone of the options, if set
+    pub fn to_csv_with(&self, writer: impl Write, options: &CsvOptions) -> MarketResult<()> {
+        let adj_close = self.data.iter().any(|s| s.adj_close.is_some());
+        let columns = if adj_close {
+            &COLUMNS[..]
+        } else {
+            &COLUMNS[..6]
+        };
+
+        let mut writer = WriterBuilder::new()
+            .delimiter(options.delimiter)
+            .from_writer(writer);
+        writer
+            .write_record(columns.iter().map(|column| options.name(*column)))
+            .map_err(to_writer)?;
+        for series in &self.data {
+            let mut record = vec![
+                options.format_datetime(series, self.timezone),
+                series.open.to_string(),
+                series.high.to_string(),
+                series.low.to_string(),
+                series.close.to_string(),
+                series.volume.to_string(),
+            ];
+            if adj_close {
+                record.push(cell(series.adj_close));
+            }
+            writer.write_record(&record).map_err(to_writer)?;
+        }
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// Reads the bars from a CSV with the default options, see `CsvOptions`
+    pub fn from_csv(
+        reader: impl Read,
+        symbol: impl Into<String>,
+        interval: Interval,
+    ) -> MarketResult<MarketSeries> {
+        MarketSeries::from_csv_with(reader, symbol, interval, &CsvOptions::default())
+    }
+
+    /// Reads the bars from a CSV with a header row, the columns are found by name in any order
+    ///
+    /// The other columns are ignored, the bars are considered as traded (`Adjustment::Raw`)
+    /// and sorted by datetime.
+    pub fn from_csv_with(
+        reader: impl Read,
+        symbol: impl Into<String>,
+        interval: Interval,
+        options: &CsvOptions,
+    ) -> MarketResult<MarketSeries> {
+        let mut reader = ReaderBuilder::new()
+            .delimiter(options.delimiter)
+            .trim(csv::Trim::All)
+            .from_reader(reader);
+        let headers = reader.headers().map_err(from_reader)?.clone();
+        let position = |column: CsvColumn| {
+            headers
+                .iter()
+                .position(|header| header == options.name(column))
+        };
+        let mut positions = [0; 6];
+        for (position_of, column) in positions.iter_mut().zip(COLUMNS) {
+            *position_of = position(column).ok_or_else(|| {
+                MarketError::ParsingError(format!("missing CSV column: {}", options.name(column)))
+            })?;
+        }
+        let adj_close = position(CsvColumn::AdjClose);
+
+        let mut data = Vec::new();
+        for record in reader.records() {
+            let record = record.map_err(from_reader)?;
+            let number = |position: usize| parse_number(&record, position);
+            data.push(Series {
+                datetime: options.parse_datetime(record.get(positions[0]).unwrap_or_default())?,
+                open: number(positions[1])?,
+                high: number(positions[2])?,
+                low: number(positions[3])?,
+                close: number(positions[4])?,
+                volume: number(positions[5])?,
+                adj_close: match adj_close {
+                    Some(position) if !record.get(position).unwrap_or_default().is_empty() => {
+                        Some(number(position)?)
+                    }
+                    _ => None,
+                },
+            });
+        }
+
+        // the lookups by datetime rely on sorted bars
+        data.sort_by_key(|s| s.datetime);
+
+        Ok(MarketSeries {
+            symbol: symbol.into(),
+            interval,
+            timezone: options.timezone.unwrap_or(Tz::UTC),
+            data,
+            adjustment: Adjustment::Raw,
+            actions: Vec::new(),
+        })
+    }
+}
+
+impl EnhancedMarketSeries {
+    /// Writes the bars and one column per computed indicator as CSV, with the default options
+    pub fn to_csv(&self, writer: impl Write) -> MarketResult<()> {
+        self.to_csv_with(writer, &CsvOptions::default())
+    }
+
+    /// Writes the bars and one column per computed indicator as CSV, like "SMA 10" or "MACD (12, 26, 9)/signal"
+    ///
+    /// The cells of the warm-up rows, before an indicator has enough bars, are left empty.
+    pub fn to_csv_with(&self, writer: impl Write, options: &CsvOptions) -> MarketResult<()> {
+        let columns = self.columns();
+
+        let mut writer = WriterBuilder::new()
+            .delimiter(options.delimiter)
+            .from_writer(writer);
+        let headers = COLUMNS[..6]
+            .iter()
+            .map(|column| options.name(*column).to_string())
+            .chain(columns.iter().map(|column| column.name.clone()));
+        writer.write_record(headers).map_err(to_writer)?;
+
+        for (row, series) in self.series.iter().enumerate() {
+            let record = [
+                options.format_datetime(series, self.timezone),
+                series.open.to_string(),
+                series.high.to_string(),
+                series.low.to_string(),
+                series.close.to_string(),
+                series.volume.to_string(),
+            ]
+            .into_iter()
+            .chain(columns.iter().map(|column| cell(column.value(row))));
+            writer.write_record(record).map_err(to_writer)?;
+        }
+        writer.flush()?;
+        Ok(())
+    }
+}
+
+fn cell(value: Option<f64>) -> String {
+    value.map(|value| value.to_string()).unwrap_or_default()
+}
+
+fn parse_number(record: &StringRecord, position: usize) -> MarketResult<f64> {
+    let value = record.get(position).unwrap_or_default();
+    value
+        .parse()
+        .map_err(|_| MarketError::ParsingError(format!("not a number: {:?}", value)))
+}
+
+fn to_writer(e: csv::Error) -> MarketError {
+    MarketError::ToWriter(e.to_string())
+}
+
+fn from_reader(e: csv::Error) -> MarketError {
+    MarketError::ParsingError(e.to_string())
+}
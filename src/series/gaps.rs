@@ -0,0 +1,69 @@
+//! Detect the bars missing from a MarketSeries, based on its interval and a trading calendar
+
+use chrono::NaiveDateTime;
+
+use crate::{client::MarketSeries, series::calendar::TradingCalendar};
+
+/// A run of consecutive missing bars, between two bars of the series
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Gap {
+    /// the datetime of the first missing bar
+    pub start: NaiveDateTime,
+    /// the datetime of the last missing bar
+    pub end: NaiveDateTime,
+    /// the number of missing bars
+    pub bars: usize,
+}
+
+impl MarketSeries {
+    /// The datetimes of the bars expected by the interval cadence but missing from the series
+    ///
+    /// The bars are expected one interval after each other, starting from the first bar.
+    /// The daily and longer bars are compared by day, ISO week or month, so a time of day moved by DST
+    /// or a week labelled by its first trading day is not reported as missing. The calendar excludes
+    /// the closed days of the daily bars, and the closed days and hours of the intraday bars.
+    /// The weekly and longer bars are all expected.
+    /// Only the holes between the first and the last bar can be detected.
+    pub fn missing_bars(&self, calendar: &TradingCalendar) -> Vec<NaiveDateTime> {
+        self.gaps_between(calendar).into_iter().flatten().collect()
+    }
+
+    /// The runs of consecutive missing bars, to re-fetch them with a start and an end, see `missing_bars`
+    pub fn gaps(&self, calendar: &TradingCalendar) -> Vec<Gap> {
+        self.gaps_between(calendar)
+            .into_iter()
+            .filter_map(|missing| {
+                Some(Gap {
+                    start: *missing.first()?,
+                    end: *missing.last()?,
+                    bars: missing.len(),
+                })
+            })
+            .collect()
+    }
+
+    /// The missing datetimes between each pair of consecutive bars
+    fn gaps_between(&self, calendar: &TradingCalendar) -> Vec<Vec<NaiveDateTime>> {
+        let mut datetimes: Vec<NaiveDateTime> = self.data.iter().map(|s| s.datetime).collect();
+        datetimes.sort();
+        datetimes.dedup();
+
+        datetimes
+            .windows(2)
+            .map(|pair| {
+                let mut missing = Vec::new();
+                let mut next = self.interval.advance(pair[0]);
+                let period = self.interval.period(pair[1]);
+                while let Some(datetime) = next.filter(|next| self.interval.period(*next) < period)
+                {
+                    if calendar.expects(self.interval, datetime) {
+                        missing.push(datetime);
+                    }
+                    next = self.interval.advance(datetime);
+                }
+                missing
+            })
+            .filter(|missing| !missing.is_empty())
+            .collect()
+    }
+}
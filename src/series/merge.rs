@@ -0,0 +1,108 @@
+//! Combine the bars of two MarketSeries, fetched in chunks or from different publishers
+
+use chrono::NaiveDateTime;
+use std::collections::BTreeMap;
+
+use crate::{
+    client::{interval::IntervalUnit, Interval, MarketSeries, Series},
+    errors::{MarketError, MarketResult},
+    Tz,
+};
+
+/// Which bar is kept when both series have a bar at the same datetime
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ConflictPolicy {
+    /// keep the bar of the series `merge` is called on
+    PreferLeft,
+    /// keep the bar of the merged series, e.g. the most recently fetched one
+    #[default]
+    PreferRight,
+    /// fail if the two bars differ, the identical ones are kept
+    Error,
+}
+
+impl MarketSeries {
+    /// Merges the bars of another series of the same symbol and interval, sorted and unique by datetime
+    ///
+    /// The other series is converted to the timezone of this one. The daily and longer bars are matched
+    /// by day, ISO week or month and keep the period they are labelled with, since the publishers stamp
+    /// the same session differently, like midnight local time, midnight UTC or the close.
+    /// The series must have the same adjustment, since adjusted and as traded prices can't be mixed.
+    /// The corporate actions are merged too. The duplicates within a single series are resolved
+    /// by keeping the last one.
+    pub fn merge(
+        mut self,
+        other: MarketSeries,
+        policy: ConflictPolicy,
+    ) -> MarketResult<MarketSeries> {
+        if self.symbol != other.symbol {
+            return Err(MarketError::MergeError(format!(
+                "the symbols {} and {} differ",
+                self.symbol, other.symbol
+            )));
+        }
+        if self.interval != other.interval {
+            return Err(MarketError::MergeError(format!(
+                "the intervals {} and {} differ",
+                self.interval, other.interval
+            )));
+        }
+        if self.adjustment != other.adjustment {
+            return Err(MarketError::MergeError(format!(
+                "the adjustments {:?} and {:?} differ",
+                self.adjustment, other.adjustment
+            )));
+        }
+        let interval = self.interval;
+
+        let mut bars: BTreeMap<NaiveDateTime, Series> = self
+            .data
+            .into_iter()
+            .map(|series| (interval.period(series.datetime), series))
+            .collect();
+        for series in other.data {
+            let series = convert(series, interval, other.timezone, self.timezone);
+            let period = interval.period(series.datetime);
+            match bars.get(&period) {
+                None => {
+                    bars.insert(period, series);
+                }
+                Some(existing) => match policy {
+                    ConflictPolicy::PreferLeft => {}
+                    ConflictPolicy::PreferRight => {
+                        bars.insert(period, series);
+                    }
+                    ConflictPolicy::Error if *existing == series => {}
+                    ConflictPolicy::Error => {
+                        return Err(MarketError::MergeError(format!(
+                            "the bars at {} differ",
+                            series.datetime
+                        )))
+                    }
+                },
+            }
+        }
+        self.data = bars.into_values().collect();
+
+        for action in other.actions {
+            if !self.actions.contains(&action) {
+                self.actions.push(action);
+            }
+        }
+        self.actions.sort_by_key(|action| action.date());
+
+        Ok(self)
+    }
+}
+
+/// The bar in another timezone, a daily or longer bar stays in the period it is labelled with,
+/// e.g. a session stamped at midnight UTC isn't moved to the evening before in New York
+fn convert(mut series: Series, interval: Interval, from: Tz, to: Tz) -> Series {
+    let converted = series.datetime_in(from).with_timezone(&to).naive_local();
+    series.datetime = match interval.unit() {
+        IntervalUnit::Minute | IntervalUnit::Hour => converted,
+        _ if interval.period(converted) == interval.period(series.datetime) => converted,
+        _ => interval.period(series.datetime),
+    };
+    series
+}
@@ -0,0 +1,56 @@
+//! Write and read MarketSeries as Parquet files, behind the `parquet` feature
+//!
+//! The files hold the record batches of the `arrow` module, with the symbol and the interval as schema metadata.
+
+use arrow::array::RecordBatch;
+use parquet::{
+    arrow::{arrow_reader::ParquetRecordBatchReaderBuilder, ArrowWriter},
+    file::reader::ChunkReader,
+};
+use std::io::Write;
+
+use crate::{
+    client::MarketSeries,
+    errors::{MarketError, MarketResult},
+    indicators::EnhancedMarketSeries,
+};
+
+impl MarketSeries {
+    /// Writes the bars as a Parquet file, see `to_record_batch` for the columns
+    pub fn to_parquet(&self, writer: impl Write + Send) -> MarketResult<()> {
+        write_parquet(writer, &self.to_record_batch()?)
+    }
+
+    /// Reads the bars of a Parquet file written by `to_parquet`, like a `std::fs::File`
+    pub fn from_parquet(reader: impl ChunkReader + 'static) -> MarketResult<MarketSeries> {
+        let builder = ParquetRecordBatchReaderBuilder::try_new(reader).map_err(from_parquet)?;
+        let schema = builder.schema().clone();
+        let batches = builder
+            .build()
+            .map_err(from_parquet)?
+            .collect::<Result<Vec<RecordBatch>, _>>()
+            .map_err(|e| MarketError::ParsingError(e.to_string()))?;
+        let batch = arrow::compute::concat_batches(&schema, &batches)
+            .map_err(|e| MarketError::ParsingError(e.to_string()))?;
+        MarketSeries::from_record_batch(&batch)
+    }
+}
+
+impl EnhancedMarketSeries {
+    /// Writes the bars and the computed indicators as a Parquet file, see `to_record_batch` for the columns
+    pub fn to_parquet(&self, writer: impl Write + Send) -> MarketResult<()> {
+        write_parquet(writer, &self.to_record_batch()?)
+    }
+}
+
+fn write_parquet(writer: impl Write + Send, batch: &RecordBatch) -> MarketResult<()> {
+    let to_writer = |e: parquet::errors::ParquetError| MarketError::ToWriter(e.to_string());
+    let mut writer = ArrowWriter::try_new(writer, batch.schema(), None).map_err(to_writer)?;
+    writer.write(batch).map_err(to_writer)?;
+    writer.close().map_err(to_writer)?;
+    Ok(())
+}
+
+fn from_parquet(e: parquet::errors::ParquetError) -> MarketError {
+    MarketError::ParsingError(e.to_string())
+}
@@ -0,0 +1,150 @@
+//! Convert MarketSeries and EnhancedMarketSeries to Polars data frames, behind the `polars` feature
+//!
+//! The first column holds the datetimes, as milliseconds tagged with the timezone of the series.
+
+use chrono::{DateTime, Utc};
+use chrono_tz::Tz;
+use polars::prelude::{
+    Column, DataFrame, DataType, IntoColumn, NamedFrom, PolarsError, TimeUnit, TimeZone,
+};
+
+use crate::{
+    client::{actions::Adjustment, Interval, MarketSeries, Series},
+    errors::{MarketError, MarketResult},
+    indicators::EnhancedMarketSeries,
+    publishers::parse_timezone,
+};
+
+const PRICES: [&str; 5] = ["open", "high", "low", "close", "volume"];
+
+impl MarketSeries {
+    /// A frame with the datetime, OHLCV and adjusted close columns
+    pub fn to_dataframe(&self) -> MarketResult<DataFrame> {
+        let mut columns = ohlcv_columns(&self.data, self.timezone)?;
+        columns.push(Column::new(
+            "adj_close".into(),
+            self.data.iter().map(|s| s.adj_close).collect::<Vec<_>>(),
+        ));
+        DataFrame::new_infer_height(columns).map_err(to_writer)
+    }
+
+    /// Reads the bars of a frame with "datetime", "open", "high", "low", "close" and "volume" columns
+    ///
+    /// The datetime column is a Datetime of any unit, its timezone becomes the timezone of the series,
+    /// UTC if it has none. The numeric columns are cast to f64, an optional "adj_close" column is read too.
+    /// The bars are considered as traded (`Adjustment::Raw`), so the crate's indicators can run on data loaded elsewhere.
+    pub fn from_dataframe(
+        frame: &DataFrame,
+        symbol: impl Into<String>,
+        interval: Interval,
+    ) -> MarketResult<MarketSeries> {
+        let datetime = frame.column("datetime").map_err(from_polars)?;
+        let (timezone, source) = match datetime.dtype() {
+            DataType::Datetime(_, Some(timezone)) => {
+                (parse_timezone(timezone.as_str())?, Some(timezone.clone()))
+            }
+            DataType::Datetime(_, None) => (Tz::UTC, None),
+            dtype => {
+                return Err(MarketError::ParsingError(format!(
+                    "the datetime column is a {}, not a Datetime",
+                    dtype
+                )))
+            }
+        };
+        let millis = datetime
+            .cast(&DataType::Datetime(TimeUnit::Milliseconds, source))
+            .map_err(from_polars)?;
+        let millis: Vec<Option<i64>> = millis
+            .datetime()
+            .map_err(from_polars)?
+            .physical()
+            .iter()
+            .collect();
+
+        let values = |name: &str| -> MarketResult<Vec<Option<f64>>> {
+            let column = frame
+                .column(name)
+                .and_then(|column| column.cast(&DataType::Float64))
+                .map_err(from_polars)?;
+            Ok(column.f64().map_err(from_polars)?.iter().collect())
+        };
+        let [open, high, low, close, volume] = PRICES.map(values);
+        let (open, high, low, close, volume) = (open?, high?, low?, close?, volume?);
+        let adj_close = match frame.column("adj_close") {
+            Ok(_) => values("adj_close")?,
+            Err(_) => vec![None; frame.height()],
+        };
+
+        let mut data = Vec::with_capacity(frame.height());
+        for row in 0..frame.height() {
+            let missing = || MarketError::ParsingError(format!("null value in row {}", row));
+            let datetime = millis[row]
+                .and_then(DateTime::<Utc>::from_timestamp_millis)
+                .ok_or_else(missing)?;
+            data.push(Series {
+                datetime: datetime.with_timezone(&timezone).naive_local(),
+                open: open[row].ok_or_else(missing)?,
+                close: close[row].ok_or_else(missing)?,
+                high: high[row].ok_or_else(missing)?,
+                low: low[row].ok_or_else(missing)?,
+                volume: volume[row].ok_or_else(missing)?,
+                adj_close: adj_close[row],
+            });
+        }
+
+        Ok(MarketSeries {
+            symbol: symbol.into(),
+            interval,
+            timezone,
+            data,
+            adjustment: Adjustment::Raw,
+            actions: Vec::new(),
+        })
+    }
+}
+
+impl EnhancedMarketSeries {
+    /// A frame with the datetime and OHLCV columns followed by one column per computed indicator
+    ///
+    /// The indicator columns are named like "SMA 10" or "MACD (12, 26, 9)/signal", the warm-up rows are null.
+    pub fn to_dataframe(&self) -> MarketResult<DataFrame> {
+        let mut columns = ohlcv_columns(&self.series, self.timezone)?;
+        for column in self.columns() {
+            let values: Vec<Option<f64>> = (0..self.series.len())
+                .map(|row| column.value(row))
+                .collect();
+            columns.push(Column::new(column.name.as_str().into(), values));
+        }
+        DataFrame::new_infer_height(columns).map_err(to_writer)
+    }
+}
+
+fn ohlcv_columns(data: &[Series], timezone: Tz) -> MarketResult<Vec<Column>> {
+    let millis: Vec<i64> = data
+        .iter()
+        .map(|s| s.datetime_in(timezone).timestamp_millis())
+        .collect();
+    let timezone = TimeZone::opt_try_new(Some(timezone.name())).map_err(to_writer)?;
+    let datetime = polars::prelude::Series::new("datetime".into(), millis)
+        .into_datetime(TimeUnit::Milliseconds, timezone)
+        .into_column();
+
+    let values: [fn(&Series) -> f64; 5] =
+        [|s| s.open, |s| s.high, |s| s.low, |s| s.close, |s| s.volume];
+    let mut columns = vec![datetime];
+    for (name, value) in PRICES.into_iter().zip(values) {
+        columns.push(Column::new(
+            name.into(),
+            data.iter().map(value).collect::<Vec<f64>>(),
+        ));
+    }
+    Ok(columns)
+}
+
+fn to_writer(e: PolarsError) -> MarketError {
+    MarketError::ToWriter(e.to_string())
+}
+
+fn from_polars(e: PolarsError) -> MarketError {
+    MarketError::ParsingError(e.to_string())
+}
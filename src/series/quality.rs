@@ -0,0 +1,346 @@
+//! Validate the bars of a MarketSeries and clean the anomalies sent by the publishers
+
+use chrono::NaiveDateTime;
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::{
+    client::{MarketSeries, Series},
+    series::calendar::TradingCalendar,
+};
+
+/// An anomaly found in the bars of a MarketSeries, `index` is the row in `data`
+#[derive(Debug, Clone, PartialEq)]
+pub enum Anomaly {
+    /// the datetime was already used by the row at index `first`
+    Duplicate {
+        index: usize,
+        first: usize,
+        datetime: NaiveDateTime,
+    },
+    /// the datetime is earlier than the one of the previous row
+    OutOfOrder {
+        index: usize,
+        datetime: NaiveDateTime,
+    },
+    /// a price or the volume is NaN, infinite or negative
+    InvalidValue { index: usize },
+    /// the high is lower than the low
+    HighBelowLow { index: usize, high: f64, low: f64 },
+    /// the open or the close lies outside [low, high]
+    OutOfRange {
+        index: usize,
+        field: &'static str,
+        value: f64,
+    },
+    /// a placeholder bar without any trade
+    ZeroVolume { index: usize },
+}
+
+impl Anomaly {
+    pub fn index(&self) -> usize {
+        match self {
+            Anomaly::Duplicate { index, .. }
+            | Anomaly::OutOfOrder { index, .. }
+            | Anomaly::InvalidValue { index }
+            | Anomaly::HighBelowLow { index, .. }
+            | Anomaly::OutOfRange { index, .. }
+            | Anomaly::ZeroVolume { index } => *index,
+        }
+    }
+
+    /// Whether the OHLCV values of the bar are inconsistent, unlike a duplicated or misplaced bar
+    pub fn is_invalid_bar(&self) -> bool {
+        matches!(
+            self,
+            Anomaly::InvalidValue { .. }
+                | Anomaly::HighBelowLow { .. }
+                | Anomaly::OutOfRange { .. }
+        )
+    }
+}
+
+impl fmt::Display for Anomaly {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Anomaly::Duplicate {
+                index,
+                first,
+                datetime,
+            } => write!(
+                f,
+                "row {}: duplicate of row {} at {}",
+                index, first, datetime
+            ),
+            Anomaly::OutOfOrder { index, datetime } => {
+                write!(f, "row {}: {} is before the previous row", index, datetime)
+            }
+            Anomaly::InvalidValue { index } => {
+                write!(f, "row {}: NaN, infinite or negative value", index)
+            }
+            Anomaly::HighBelowLow { index, high, low } => {
+                write!(f, "row {}: high {} is below low {}", index, high, low)
+            }
+            Anomaly::OutOfRange {
+                index,
+                field,
+                value,
+            } => write!(
+                f,
+                "row {}: {} {} is outside [low, high]",
+                index, field, value
+            ),
+            Anomaly::ZeroVolume { index } => write!(f, "row {}: zero volume", index),
+        }
+    }
+}
+
+/// Every anomaly found by `MarketSeries::validate`, in row order
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ValidationReport {
+    pub anomalies: Vec<Anomaly>,
+}
+
+impl ValidationReport {
+    pub fn is_clean(&self) -> bool {
+        self.anomalies.is_empty()
+    }
+
+    /// The rows with inconsistent OHLCV values, without repetition
+    pub fn invalid_rows(&self) -> Vec<usize> {
+        let mut rows: Vec<usize> = self
+            .anomalies
+            .iter()
+            .filter(|anomaly| anomaly.is_invalid_bar())
+            .map(Anomaly::index)
+            .collect();
+        rows.dedup();
+        rows
+    }
+}
+
+impl fmt::Display for ValidationReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "ValidationReport: {} anomalies", self.anomalies.len())?;
+        for anomaly in &self.anomalies {
+            writeln!(f, "{}", anomaly)?;
+        }
+        Ok(())
+    }
+}
+
+/// What `MarketSeries::clean` does with the bars having inconsistent OHLCV values
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum InvalidBars {
+    /// leave them as they are
+    Keep,
+    /// remove them
+    #[default]
+    Drop,
+    /// widen high and low to hold the open and the close, the bars with NaN, infinite or negative values are dropped
+    Repair,
+}
+
+/// The steps applied by `MarketSeries::clean`, in this order: sort, dedupe, zero volume, invalid bars, gaps
+#[derive(Debug, Clone)]
+pub struct CleanOptions {
+    sort: bool,
+    dedupe: bool,
+    drop_zero_volume: bool,
+    invalid_bars: InvalidBars,
+    fill_gaps: Option<TradingCalendar>,
+}
+
+impl Default for CleanOptions {
+    /// sort, dedupe and drop the invalid bars, keep the zero volume bars and the gaps
+    fn default() -> Self {
+        CleanOptions {
+            sort: true,
+            dedupe: true,
+            drop_zero_volume: false,
+            invalid_bars: InvalidBars::Drop,
+            fill_gaps: None,
+        }
+    }
+}
+
+impl CleanOptions {
+    /// Sort the bars by datetime, the order of the duplicates is preserved
+    pub fn with_sort(mut self, sort: bool) -> Self {
+        self.sort = sort;
+        self
+    }
+
+    /// Keep a single bar per datetime, the last one received, which is the most up to date
+    pub fn with_dedupe(mut self, dedupe: bool) -> Self {
+        self.dedupe = dedupe;
+        self
+    }
+
+    /// Remove the placeholder bars without any trade
+    pub fn with_drop_zero_volume(mut self, drop_zero_volume: bool) -> Self {
+        self.drop_zero_volume = drop_zero_volume;
+        self
+    }
+
+    pub fn with_invalid_bars(mut self, invalid_bars: InvalidBars) -> Self {
+        self.invalid_bars = invalid_bars;
+        self
+    }
+
+    /// Insert the missing bars with the previous close as OHLC and a zero volume
+    ///
+    /// Only the bars the calendar expects are inserted, the intraday gaps within its sessions
+    /// and the daily gaps on its trading days, as reported by `MarketSeries::missing_bars`.
+    pub fn with_fill_gaps(mut self, calendar: TradingCalendar) -> Self {
+        self.fill_gaps = Some(calendar);
+        self
+    }
+}
+
+impl MarketSeries {
+    /// Lists every duplicated, misplaced or inconsistent bar, the series is left untouched
+    pub fn validate(&self) -> ValidationReport {
+        let mut anomalies = Vec::new();
+        let mut seen: HashMap<NaiveDateTime, usize> = HashMap::new();
+        let mut previous: Option<NaiveDateTime> = None;
+
+        for (index, series) in self.data.iter().enumerate() {
+            if let Some(&first) = seen.get(&series.datetime) {
+                anomalies.push(Anomaly::Duplicate {
+                    index,
+                    first,
+                    datetime: series.datetime,
+                });
+            } else {
+                seen.insert(series.datetime, index);
+                if previous.is_some_and(|previous| series.datetime < previous) {
+                    anomalies.push(Anomaly::OutOfOrder {
+                        index,
+                        datetime: series.datetime,
+                    });
+                }
+            }
+            previous = Some(series.datetime);
+
+            anomalies.extend(bar_anomalies(index, series));
+            if series.volume == 0.0 {
+                anomalies.push(Anomaly::ZeroVolume { index });
+            }
+        }
+
+        ValidationReport { anomalies }
+    }
+
+    /// Fixes the anomalies reported by `validate`, as configured by the options
+    pub fn clean(mut self, options: &CleanOptions) -> MarketSeries {
+        if options.sort {
+            self.data.sort_by_key(|s| s.datetime);
+        }
+
+        if options.dedupe {
+            let mut positions: HashMap<NaiveDateTime, usize> = HashMap::new();
+            let mut data: Vec<Series> = Vec::with_capacity(self.data.len());
+            for series in self.data {
+                match positions.get(&series.datetime) {
+                    Some(&position) => data[position] = series,
+                    None => {
+                        positions.insert(series.datetime, data.len());
+                        data.push(series);
+                    }
+                }
+            }
+            self.data = data;
+        }
+
+        if options.drop_zero_volume {
+            self.data.retain(|s| s.volume != 0.0);
+        }
+
+        match options.invalid_bars {
+            InvalidBars::Keep => {}
+            InvalidBars::Drop => self.data.retain(|s| bar_anomalies(0, s).is_empty()),
+            InvalidBars::Repair => {
+                self.data.retain(has_valid_values);
+                for series in self.data.iter_mut() {
+                    series.high = series
+                        .high
+                        .max(series.open)
+                        .max(series.close)
+                        .max(series.low);
+                    series.low = series.low.min(series.open).min(series.close);
+                }
+            }
+        }
+
+        if let Some(calendar) = &options.fill_gaps {
+            self.fill_gaps(calendar);
+        }
+        self
+    }
+
+    fn fill_gaps(&mut self, calendar: &TradingCalendar) {
+        let interval = self.interval;
+        let mut data: Vec<Series> = Vec::with_capacity(self.data.len());
+        for series in std::mem::take(&mut self.data) {
+            if let Some(previous) = data.last().cloned() {
+                let mut next = interval.advance(previous.datetime);
+                let period = interval.period(series.datetime);
+                while let Some(datetime) = next.filter(|next| interval.period(*next) < period) {
+                    if calendar.expects(interval, datetime) {
+                        data.push(Series {
+                            datetime,
+                            open: previous.close,
+                            close: previous.close,
+                            high: previous.close,
+                            low: previous.close,
+                            volume: 0.0,
+                            adj_close: previous.adj_close,
+                        });
+                    }
+                    next = interval.advance(datetime);
+                }
+            }
+            data.push(series);
+        }
+        self.data = data;
+    }
+}
+
+fn has_valid_values(series: &Series) -> bool {
+    [
+        series.open,
+        series.high,
+        series.low,
+        series.close,
+        series.volume,
+    ]
+    .iter()
+    .all(|value| value.is_finite() && *value >= 0.0)
+}
+
+/// The anomalies of the OHLCV values of a single bar
+fn bar_anomalies(index: usize, series: &Series) -> Vec<Anomaly> {
+    if !has_valid_values(series) {
+        return vec![Anomaly::InvalidValue { index }];
+    }
+
+    let mut anomalies = Vec::new();
+    if series.high < series.low {
+        anomalies.push(Anomaly::HighBelowLow {
+            index,
+            high: series.high,
+            low: series.low,
+        });
+    }
+    for (field, value) in [("open", series.open), ("close", series.close)] {
+        if value < series.low || value > series.high {
+            anomalies.push(Anomaly::OutOfRange {
+                index,
+                field,
+                value,
+            });
+        }
+    }
+    anomalies
+}
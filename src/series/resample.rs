@@ -0,0 +1,141 @@
+//! Aggregate the bars of a MarketSeries into a coarser interval
+
+use chrono::{Datelike, Duration, NaiveDate, NaiveDateTime, NaiveTime};
+
+use crate::{
+    client::{interval::IntervalUnit, Interval, MarketSeries, Series},
+    errors::{MarketError, MarketResult},
+    series::calendar::TradingCalendar,
+};
+
+impl MarketSeries {
+    /// Aggregates the bars into a coarser interval: first open, max high, min low, last close, summed volume
+    ///
+    /// The buckets are computed in the timezone of the series, so a UTC series should be converted
+    /// to the exchange timezone first (`to_timezone`) to get daily bars matching the trading sessions.
+    /// - intraday buckets are aligned on midnight, use `resample_with_calendar` to align them on the session open
+    /// - daily buckets hold the bars of a calendar day
+    /// - weekly buckets hold the bars of an ISO week, from Monday to Sunday
+    /// - monthly buckets hold the bars of a calendar month
+    ///
+    /// Every bucket is labelled with its start. Resampling to a finer interval, to an intraday interval
+    /// that is not a multiple of the current one, or from weekly to monthly bars fails.
+    pub fn resample(&self, interval: Interval) -> MarketResult<MarketSeries> {
+        self.resample_from(interval, NaiveTime::MIN)
+    }
+
+    /// Same as `resample`, with the intraday buckets aligned on the session open of the calendar,
+    /// e.g. the hourly bars of a US stock start at 9:30, 10:30, ...
+    pub fn resample_with_calendar(
+        &self,
+        interval: Interval,
+        calendar: &TradingCalendar,
+    ) -> MarketResult<MarketSeries> {
+        self.resample_from(interval, calendar.session_open().unwrap_or(NaiveTime::MIN))
+    }
+
+    /// The intraday buckets are aligned on the `anchor` time of every day
+    fn resample_from(&self, interval: Interval, anchor: NaiveTime) -> MarketResult<MarketSeries> {
+        let from = self.interval.nominal_duration().num_minutes();
+        let to = interval.nominal_duration().num_minutes();
+        if to < from {
+            return Err(MarketError::ResampleError(format!(
+                "{} bars can't be resampled to the finer {} interval",
+                self.interval, interval
+            )));
+        }
+        let not_multiple = || {
+            MarketError::ResampleError(format!(
+                "{} is not a multiple of {}",
+                interval, self.interval
+            ))
+        };
+        match (interval.unit(), interval.months()) {
+            (IntervalUnit::Minute | IntervalUnit::Hour, _) if to % from != 0 => {
+                return Err(not_multiple())
+            }
+            (IntervalUnit::Day | IntervalUnit::Week, _) if interval.multiplier() > 1 => {
+                return Err(MarketError::ResampleError(format!(
+                    "the {} buckets are not supported, only single days and weeks",
+                    interval
+                )))
+            }
+            (_, Some(months)) => {
+                if 12 % months != 0 {
+                    return Err(MarketError::ResampleError(format!(
+                        "the {} buckets don't divide a year",
+                        interval
+                    )));
+                }
+                // e.g. weekly bars overlap the months
+                let fits = match self.interval.months() {
+                    Some(source) => months % source == 0,
+                    None => self.interval.is_intraday() || self.interval == Interval::Daily,
+                };
+                if !fits {
+                    return Err(MarketError::ResampleError(format!(
+                        "{} bars overlap the {} buckets, resample from daily bars instead",
+                        self.interval, interval
+                    )));
+                }
+            }
+            _ => {}
+        }
+
+        let mut sorted: Vec<&Series> = self.data.iter().collect();
+        sorted.sort_by_key(|s| s.datetime);
+
+        let mut data: Vec<Series> = Vec::new();
+        let mut last_label = None;
+        for series in sorted {
+            let date = series.datetime.date();
+            let label = match interval.unit() {
+                IntervalUnit::Day => midnight(date),
+                IntervalUnit::Week => {
+                    midnight(date - Duration::days(date.weekday().num_days_from_monday() as i64))
+                }
+                IntervalUnit::Month | IntervalUnit::Quarter | IntervalUnit::Year => {
+                    let months = interval.months().unwrap_or(1);
+                    let month = date.month0() - date.month0() % months;
+                    midnight(NaiveDate::from_ymd_opt(date.year(), month + 1, 1).unwrap_or(date))
+                }
+                IntervalUnit::Minute | IntervalUnit::Hour => {
+                    // the bars before the anchor, like the pre-market ones, fall in the buckets before it
+                    let anchor = date.and_time(anchor);
+                    let elapsed = (series.datetime - anchor).num_minutes();
+                    anchor + Duration::minutes(elapsed - elapsed.rem_euclid(to))
+                }
+            };
+
+            match data.last_mut() {
+                Some(bucket) if last_label == Some(label) => {
+                    bucket.high = bucket.high.max(series.high);
+                    bucket.low = bucket.low.min(series.low);
+                    bucket.close = series.close;
+                    bucket.volume += series.volume;
+                    bucket.adj_close = series.adj_close;
+                }
+                _ => {
+                    data.push(Series {
+                        datetime: label,
+                        ..series.clone()
+                    });
+                    last_label = Some(label);
+                }
+            }
+        }
+
+        Ok(MarketSeries {
+            symbol: self.symbol.clone(),
+            interval,
+            timezone: self.timezone,
+            data,
+            adjustment: self.adjustment,
+            actions: self.actions.clone(),
+        })
+    }
+}
+
+fn midnight(date: NaiveDate) -> NaiveDateTime {
+    date.and_time(NaiveTime::MIN)
+}
@@ -0,0 +1,156 @@
+//! Borrowed views on the bars of a MarketSeries or an EnhancedMarketSeries, found by binary search
+//!
+//! The bars must be sorted by datetime, as returned by the publishers or by `clean`.
+
+use chrono::NaiveDateTime;
+use std::collections::{HashMap, VecDeque};
+use std::ops::{Bound, Range, RangeBounds};
+
+use crate::{
+    client::{MarketSeries, Series},
+    indicators::{EnhancedMarketSeries, Indicators},
+};
+
+impl MarketSeries {
+    /// The bars within a datetime range, like `start..end` or `start..=end`
+    pub fn range(&self, range: impl RangeBounds<NaiveDateTime>) -> &[Series] {
+        &self.data[rows(&self.data, range)]
+    }
+
+    /// The last bar at or before the datetime
+    pub fn as_of(&self, datetime: NaiveDateTime) -> Option<&Series> {
+        as_of_row(&self.data, datetime).map(|row| &self.data[row])
+    }
+
+    /// The first `n` bars, or all of them if there are fewer
+    pub fn head(&self, n: usize) -> &[Series] {
+        &self.data[..n.min(self.data.len())]
+    }
+
+    /// The last `n` bars, or all of them if there are fewer
+    pub fn tail(&self, n: usize) -> &[Series] {
+        &self.data[self.data.len().saturating_sub(n)..]
+    }
+
+    /// Every run of `size` consecutive bars, overlapping, like `slice::windows`
+    pub fn windows(&self, size: usize) -> std::slice::Windows<'_, Series> {
+        self.data.windows(size.max(1))
+    }
+}
+
+impl EnhancedMarketSeries {
+    /// The rows within a datetime range, with their indicator values
+    pub fn range(&self, range: impl RangeBounds<NaiveDateTime>) -> EnhancedSlice<'_> {
+        self.slice(rows(&self.series, range))
+    }
+
+    /// The last row at or before the datetime
+    pub fn as_of(&self, datetime: NaiveDateTime) -> Option<EnhancedSlice<'_>> {
+        as_of_row(&self.series, datetime).map(|row| self.slice(row..row + 1))
+    }
+
+    /// The first `n` rows, or all of them if there are fewer
+    pub fn head(&self, n: usize) -> EnhancedSlice<'_> {
+        self.slice(0..n.min(self.series.len()))
+    }
+
+    /// The last `n` rows, or all of them if there are fewer
+    pub fn tail(&self, n: usize) -> EnhancedSlice<'_> {
+        self.slice(self.series.len().saturating_sub(n)..self.series.len())
+    }
+
+    /// Every run of `size` consecutive rows, overlapping
+    pub fn windows(&self, size: usize) -> impl Iterator<Item = EnhancedSlice<'_>> {
+        let size = size.max(1);
+        (0..(self.series.len() + 1).saturating_sub(size))
+            .map(move |row| self.slice(row..row + size))
+    }
+
+    fn slice(&self, rows: Range<usize>) -> EnhancedSlice<'_> {
+        EnhancedSlice {
+            offset: rows.start,
+            series: &self.series[rows],
+            indicators: &self.indicators,
+        }
+    }
+}
+
+/// Consecutive rows of an EnhancedMarketSeries, the indicator values are looked up for the same rows
+#[derive(Debug, Clone, Copy)]
+pub struct EnhancedSlice<'a> {
+    /// the bars of the rows
+    pub series: &'a [Series],
+    indicators: &'a Indicators,
+    offset: usize,
+}
+
+impl<'a> EnhancedSlice<'a> {
+    /// The row of the first bar in the EnhancedMarketSeries
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    pub fn len(&self) -> usize {
+        self.series.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.series.is_empty()
+    }
+
+    /// The values of a single value indicator for the rows, like "SMA 20" or "RSI 14"
+    pub fn values(&self, name: &str) -> Option<Vec<f64>> {
+        let indicators = self.indicators;
+        [
+            &indicators.sma,
+            &indicators.ema,
+            &indicators.rsi,
+            &indicators.stochastic,
+        ]
+        .into_iter()
+        .find_map(|map| map.get(name))
+        .map(|values| self.rows(values).collect())
+    }
+
+    /// The values of a three values indicator for the rows, like "MACD (12, 26, 9)" or "BB (20, 2)"
+    pub fn triples(&self, name: &str) -> Option<Vec<(f64, f64, f64)>> {
+        let indicators = self.indicators;
+        let triples: [&HashMap<String, _>; 2] = [&indicators.macd, &indicators.bb];
+        triples
+            .into_iter()
+            .find_map(|map| map.get(name))
+            .map(|(first, second, third)| {
+                self.rows(first)
+                    .zip(self.rows(second))
+                    .zip(self.rows(third))
+                    .map(|((first, second), third)| (first, second, third))
+                    .collect()
+            })
+    }
+
+    fn rows(&self, values: &'a VecDeque<f64>) -> impl Iterator<Item = f64> + 'a {
+        let end = (self.offset + self.series.len()).min(values.len());
+        values.range(self.offset.min(end)..end).copied()
+    }
+}
+
+/// The rows of the sorted bars within a datetime range
+fn rows(data: &[Series], range: impl RangeBounds<NaiveDateTime>) -> Range<usize> {
+    let start = match range.start_bound() {
+        Bound::Included(start) => data.partition_point(|s| s.datetime < *start),
+        Bound::Excluded(start) => data.partition_point(|s| s.datetime <= *start),
+        Bound::Unbounded => 0,
+    };
+    let end = match range.end_bound() {
+        Bound::Included(end) => data.partition_point(|s| s.datetime <= *end),
+        Bound::Excluded(end) => data.partition_point(|s| s.datetime < *end),
+        Bound::Unbounded => data.len(),
+    };
+    start..end.max(start)
+}
+
+/// The row of the last bar at or before the datetime
+fn as_of_row(data: &[Series], datetime: NaiveDateTime) -> Option<usize> {
+    data.partition_point(|s| s.datetime <= datetime)
+        .checked_sub(1)
+}
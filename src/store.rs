@@ -0,0 +1,438 @@
+//! Persist MarketSeries to an embedded SQLite database, behind the `sqlite` feature
+//!
+//! The bars are keyed by (publisher, symbol, interval, datetime): writing a series again updates the
+//! overlapping bars and inserts the new ones. The datetimes are stored as "YYYY-MM-DD HH:MM:SS" text,
+//! which sorts chronologically, in the timezone of the first series written for the key.
+
+use chrono::{NaiveDate, NaiveDateTime};
+use rusqlite::{params, params_from_iter, Connection, OptionalExtension};
+use std::borrow::Cow;
+use std::ops::{Bound, RangeBounds};
+use std::path::Path;
+
+use crate::{
+    client::{
+        actions::{Adjustment, CorporateAction},
+        Interval, MarketSeries, Series,
+    },
+    errors::{MarketError, MarketResult},
+    publishers::parse_timezone,
+    Tz,
+};
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS series (
+    publisher TEXT NOT NULL,
+    symbol TEXT NOT NULL,
+    interval TEXT NOT NULL,
+    timezone TEXT NOT NULL,
+    adjustment TEXT NOT NULL,
+    PRIMARY KEY (publisher, symbol, interval)
+);
+CREATE TABLE IF NOT EXISTS bars (
+    publisher TEXT NOT NULL,
+    symbol TEXT NOT NULL,
+    interval TEXT NOT NULL,
+    datetime TEXT NOT NULL,
+    open REAL NOT NULL,
+    high REAL NOT NULL,
+    low REAL NOT NULL,
+    close REAL NOT NULL,
+    volume REAL NOT NULL,
+    adj_close REAL,
+    PRIMARY KEY (publisher, symbol, interval, datetime)
+) WITHOUT ROWID;
+CREATE TABLE IF NOT EXISTS actions (
+    publisher TEXT NOT NULL,
+    symbol TEXT NOT NULL,
+    date TEXT NOT NULL,
+    kind TEXT NOT NULL,
+    value REAL NOT NULL,
+    PRIMARY KEY (publisher, symbol, date, kind)
+) WITHOUT ROWID;
+";
+
+const DATETIME_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+const DATE_FORMAT: &str = "%Y-%m-%d";
+
+/// A local store of MarketSeries, kept in a SQLite database
+///
+/// The calls are blocking, use `tokio::task::spawn_blocking` to call them from an async context.
+#[derive(Debug)]
+pub struct SqliteStore {
+    conn: Connection,
+}
+
+/// The bars stored for a (publisher, symbol, interval) key
+#[derive(Debug, Clone, PartialEq)]
+pub struct Coverage {
+    pub publisher: String,
+    pub symbol: String,
+    pub interval: Interval,
+    pub timezone: Tz,
+    /// datetime of the first stored bar
+    pub first: NaiveDateTime,
+    /// datetime of the last stored bar
+    pub last: NaiveDateTime,
+    /// number of stored bars
+    pub bars: usize,
+}
+
+impl SqliteStore {
+    /// Opens the database file, created with the store schema if missing
+    pub fn open(path: impl AsRef<Path>) -> MarketResult<Self> {
+        SqliteStore::init(Connection::open(path).map_err(from_sqlite)?)
+    }
+
+    /// A store living in memory, dropped with it
+    pub fn open_in_memory() -> MarketResult<Self> {
+        SqliteStore::init(Connection::open_in_memory().map_err(from_sqlite)?)
+    }
+
+    fn init(conn: Connection) -> MarketResult<Self> {
+        conn.execute_batch(SCHEMA).map_err(from_sqlite)?;
+        Ok(SqliteStore { conn })
+    }
+
+    /// Writes the bars and the corporate actions of the series, returns the number of bars written
+    ///
+    /// The bars already stored at the same datetimes are replaced, so writing the same series twice is harmless.
+    /// The intraday series is converted to the timezone of the bars already stored for the key, the daily
+    /// and longer ones must be in that timezone, since they are keyed by the datetime of their date.
+    /// It must have their adjustment, since adjusted and as traded prices can't be mixed.
+    /// Everything is written in a single transaction.
+    pub fn upsert(&mut self, publisher: &str, series: &MarketSeries) -> MarketResult<usize> {
+        let interval = series.interval.to_string();
+        let tx = self.conn.transaction().map_err(from_sqlite)?;
+
+        let stored: Option<(String, String)> = tx
+            .query_row(
+                "SELECT timezone, adjustment FROM series
+                 WHERE publisher = ?1 AND symbol = ?2 AND interval = ?3",
+                params![publisher, series.symbol, interval],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()
+            .map_err(from_sqlite)?;
+        let series = match stored {
+            Some((timezone, adjustment)) => {
+                let adjustment: Adjustment = adjustment.parse()?;
+                if adjustment != series.adjustment {
+                    return Err(MarketError::StorageError(format!(
+                        "{} {} is stored with the {} adjustment, not {}",
+                        series.symbol, interval, adjustment, series.adjustment
+                    )));
+                }
+                let timezone = parse_timezone(&timezone)?;
+                if timezone == series.timezone {
+                    Cow::Borrowed(series)
+                } else if !series.interval.is_intraday() {
+                    // converted, the bars labelled by their date would move off the stored ones
+                    return Err(MarketError::StorageError(format!(
+                        "{} {} is stored in {}, the daily and longer bars can't be converted from {}",
+                        series.symbol, interval, timezone, series.timezone
+                    )));
+                } else {
+                    Cow::Owned(series.clone().to_timezone(timezone))
+                }
+            }
+            None => {
+                tx.execute(
+                    "INSERT INTO series (publisher, symbol, interval, timezone, adjustment)
+                     VALUES (?1, ?2, ?3, ?4, ?5)",
+                    params![
+                        publisher,
+                        series.symbol,
+                        interval,
+                        series.timezone.name(),
+                        series.adjustment.to_string()
+                    ],
+                )
+                .map_err(from_sqlite)?;
+                Cow::Borrowed(series)
+            }
+        };
+
+        {
+            let mut insert = tx
+                .prepare(
+                    "INSERT INTO bars (publisher, symbol, interval, datetime, open, high, low, close, volume, adj_close)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)
+                     ON CONFLICT (publisher, symbol, interval, datetime) DO UPDATE SET
+                     open = excluded.open, high = excluded.high, low = excluded.low, close = excluded.close,
+                     volume = excluded.volume, adj_close = excluded.adj_close",
+                )
+                .map_err(from_sqlite)?;
+            for bar in series.data.iter() {
+                insert
+                    .execute(params![
+                        publisher,
+                        series.symbol,
+                        interval,
+                        bar.datetime.format(DATETIME_FORMAT).to_string(),
+                        bar.open,
+                        bar.high,
+                        bar.low,
+                        bar.close,
+                        bar.volume,
+                        bar.adj_close
+                    ])
+                    .map_err(from_sqlite)?;
+            }
+
+            let mut insert = tx
+                .prepare(
+                    "INSERT INTO actions (publisher, symbol, date, kind, value) VALUES (?1, ?2, ?3, ?4, ?5)
+                     ON CONFLICT (publisher, symbol, date, kind) DO UPDATE SET value = excluded.value",
+                )
+                .map_err(from_sqlite)?;
+            for action in series.actions.iter() {
+                let (kind, value) = match action {
+                    CorporateAction::Dividend { amount, .. } => ("dividend", amount),
+                    CorporateAction::Split { ratio, .. } => ("split", ratio),
+                };
+                insert
+                    .execute(params![
+                        publisher,
+                        series.symbol,
+                        action.date().format(DATE_FORMAT).to_string(),
+                        kind,
+                        value
+                    ])
+                    .map_err(from_sqlite)?;
+            }
+        }
+
+        tx.commit().map_err(from_sqlite)?;
+        Ok(series.data.len())
+    }
+
+    /// Reads the stored bars within the datetime range, like `..` for all of them
+    ///
+    /// The series has the timezone and the adjustment of the stored bars, and the corporate actions dated within the days of the range.
+    /// Returns `None` if nothing was ever stored for the key.
+    pub fn load(
+        &self,
+        publisher: &str,
+        symbol: &str,
+        interval: Interval,
+        range: impl RangeBounds<NaiveDateTime>,
+    ) -> MarketResult<Option<MarketSeries>> {
+        let key = [
+            publisher.to_string(),
+            symbol.to_string(),
+            interval.to_string(),
+        ];
+        let stored: Option<(String, String)> = self
+            .conn
+            .query_row(
+                "SELECT timezone, adjustment FROM series
+                 WHERE publisher = ?1 AND symbol = ?2 AND interval = ?3",
+                params_from_iter(&key),
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()
+            .map_err(from_sqlite)?;
+        let Some((timezone, adjustment)) = stored else {
+            return Ok(None);
+        };
+
+        let datetime = |bound: &NaiveDateTime| bound.format(DATETIME_FORMAT).to_string();
+        let (clause, bounds) = range_clause(
+            "datetime",
+            4,
+            [
+                condition(range.start_bound(), ">=", ">", datetime),
+                condition(range.end_bound(), "<=", "<", datetime),
+            ],
+        );
+        let mut select = self
+            .conn
+            .prepare(&format!(
+                "SELECT datetime, open, high, low, close, volume, adj_close FROM bars
+                 WHERE publisher = ?1 AND symbol = ?2 AND interval = ?3{} ORDER BY datetime",
+                clause
+            ))
+            .map_err(from_sqlite)?;
+        let rows = select
+            .query_map(params_from_iter(key.iter().chain(bounds.iter())), |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    Series {
+                        datetime: NaiveDateTime::MIN,
+                        open: row.get(1)?,
+                        high: row.get(2)?,
+                        low: row.get(3)?,
+                        close: row.get(4)?,
+                        volume: row.get(5)?,
+                        adj_close: row.get(6)?,
+                    },
+                ))
+            })
+            .map_err(from_sqlite)?;
+        let mut data = Vec::new();
+        for row in rows {
+            let (datetime, mut bar) = row.map_err(from_sqlite)?;
+            bar.datetime = NaiveDateTime::parse_from_str(&datetime, DATETIME_FORMAT)
+                .map_err(|e| MarketError::ParsingError(format!("{}: {}", datetime, e)))?;
+            data.push(bar);
+        }
+
+        let date = |bound: &NaiveDateTime| bound.date().format(DATE_FORMAT).to_string();
+        let (clause, bounds) = range_clause(
+            "date",
+            3,
+            [
+                condition(range.start_bound(), ">=", ">=", date),
+                condition(range.end_bound(), "<=", "<=", date),
+            ],
+        );
+        let mut select = self
+            .conn
+            .prepare(&format!(
+                "SELECT date, kind, value FROM actions
+                 WHERE publisher = ?1 AND symbol = ?2{} ORDER BY date, kind",
+                clause
+            ))
+            .map_err(from_sqlite)?;
+        let rows = select
+            .query_map(
+                params_from_iter(key[..2].iter().chain(bounds.iter())),
+                |row| {
+                    Ok((
+                        row.get::<_, String>(0)?,
+                        row.get::<_, String>(1)?,
+                        row.get::<_, f64>(2)?,
+                    ))
+                },
+            )
+            .map_err(from_sqlite)?;
+        let mut actions = Vec::new();
+        for row in rows {
+            let (date, kind, value) = row.map_err(from_sqlite)?;
+            let date = NaiveDate::parse_from_str(&date, DATE_FORMAT)
+                .map_err(|e| MarketError::ParsingError(format!("{}: {}", date, e)))?;
+            actions.push(match kind.as_str() {
+                "split" => CorporateAction::Split { date, ratio: value },
+                "dividend" => CorporateAction::Dividend {
+                    date,
+                    amount: value,
+                },
+                _ => {
+                    return Err(MarketError::StorageError(format!(
+                        "unknown corporate action: {}",
+                        kind
+                    )))
+                }
+            });
+        }
+
+        Ok(Some(MarketSeries {
+            symbol: symbol.to_string(),
+            interval,
+            timezone: parse_timezone(&timezone)?,
+            data,
+            adjustment: adjustment.parse()?,
+            actions,
+        }))
+    }
+
+    /// The symbols with stored bars, sorted
+    pub fn symbols(&self) -> MarketResult<Vec<String>> {
+        let mut select = self
+            .conn
+            .prepare("SELECT DISTINCT symbol FROM bars ORDER BY symbol")
+            .map_err(from_sqlite)?;
+        let symbols = select
+            .query_map([], |row| row.get(0))
+            .map_err(from_sqlite)?
+            .collect::<Result<Vec<String>, _>>()
+            .map_err(from_sqlite)?;
+        Ok(symbols)
+    }
+
+    /// The first and last datetimes and the number of bars stored for every (publisher, symbol, interval) key
+    pub fn coverage(&self) -> MarketResult<Vec<Coverage>> {
+        let mut select = self
+            .conn
+            .prepare(
+                "SELECT bars.publisher, bars.symbol, bars.interval, series.timezone,
+                        MIN(bars.datetime), MAX(bars.datetime), COUNT(*)
+                 FROM bars JOIN series USING (publisher, symbol, interval)
+                 GROUP BY bars.publisher, bars.symbol, bars.interval
+                 ORDER BY bars.symbol, bars.publisher, bars.interval",
+            )
+            .map_err(from_sqlite)?;
+        let rows = select
+            .query_map([], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, String>(2)?,
+                    row.get::<_, String>(3)?,
+                    row.get::<_, String>(4)?,
+                    row.get::<_, String>(5)?,
+                    row.get::<_, i64>(6)?,
+                ))
+            })
+            .map_err(from_sqlite)?;
+
+        let parse = |datetime: &str| {
+            NaiveDateTime::parse_from_str(datetime, DATETIME_FORMAT)
+                .map_err(|e| MarketError::ParsingError(format!("{}: {}", datetime, e)))
+        };
+        let mut coverage = Vec::new();
+        for row in rows {
+            let (publisher, symbol, interval, timezone, first, last, bars) =
+                row.map_err(from_sqlite)?;
+            coverage.push(Coverage {
+                publisher,
+                symbol,
+                interval: interval.parse()?,
+                timezone: parse_timezone(&timezone)?,
+                first: parse(&first)?,
+                last: parse(&last)?,
+                bars: bars as usize,
+            });
+        }
+        Ok(coverage)
+    }
+}
+
+/// The comparison operator and the formatted value of a range bound, `None` if unbounded
+fn condition(
+    bound: Bound<&NaiveDateTime>,
+    included: &'static str,
+    excluded: &'static str,
+    format: impl Fn(&NaiveDateTime) -> String,
+) -> Option<(&'static str, String)> {
+    match bound {
+        Bound::Included(bound) => Some((included, format(bound))),
+        Bound::Excluded(bound) => Some((excluded, format(bound))),
+        Bound::Unbounded => None,
+    }
+}
+
+/// The SQL conditions on the column, bound to the parameters numbered from `first`, and their values
+fn range_clause(
+    column: &str,
+    first: usize,
+    conditions: [Option<(&'static str, String)>; 2],
+) -> (String, Vec<String>) {
+    let mut clause = String::new();
+    let mut values = Vec::new();
+    for (operator, value) in conditions.into_iter().flatten() {
+        clause.push_str(&format!(
+            " AND {} {} ?{}",
+            column,
+            operator,
+            first + values.len()
+        ));
+        values.push(value);
+    }
+    (clause, values)
+}
+
+fn from_sqlite(e: rusqlite::Error) -> MarketError {
+    MarketError::StorageError(e.to_string())
+}
@@ -0,0 +1,151 @@
+//! Parse the corporate actions returned by the publishers and back-adjust the series with them
+
+use chrono::NaiveDate;
+use market_data::{
+    Adjustment, CorporateAction, Interval, MarketClient, MarketSeries, Series, Tz, YahooFin,
+    YahooRange,
+};
+use wiremock::{matchers::query_param, Mock, MockServer, ResponseTemplate};
+
+fn date(day: u32) -> NaiveDate {
+    NaiveDate::from_ymd_opt(2024, 6, day).unwrap()
+}
+
+fn bar(day: u32, close: f64, volume: f64) -> Series {
+    Series {
+        datetime: date(day).and_hms_opt(0, 0, 0).unwrap(),
+        open: close,
+        close,
+        high: close,
+        low: close,
+        volume,
+        adj_close: None,
+    }
+}
+
+fn assert_close(actual: f64, expected: f64) {
+    assert!(
+        (actual - expected).abs() < 1e-9,
+        "{} != {}",
+        actual,
+        expected
+    );
+}
+
+#[test]
+fn adjust_for_split_and_dividend() {
+    let series = MarketSeries {
+        symbol: "NVDA".to_string(),
+        interval: Interval::Daily,
+        timezone: Tz::America__New_York,
+        data: vec![
+            bar(6, 1200.0, 100.0),
+            bar(7, 1000.0, 100.0),
+            bar(10, 120.0, 1000.0),
+            bar(11, 121.0, 1000.0),
+            bar(12, 120.0, 1000.0),
+        ],
+        adjustment: Adjustment::Raw,
+        actions: vec![
+            CorporateAction::Split {
+                date: date(10),
+                ratio: 10.0,
+            },
+            CorporateAction::Dividend {
+                date: date(12),
+                amount: 1.21,
+            },
+        ],
+    };
+
+    let adjusted = series.adjust();
+    assert_eq!(adjusted.adjustment, Adjustment::SplitsAndDividends);
+
+    let dividend = 1.0 - 1.21 / 121.0;
+    assert_close(adjusted.data[4].close, 120.0);
+    assert_close(adjusted.data[3].close, 121.0 * dividend);
+    assert_close(adjusted.data[1].close, 100.0 * dividend);
+    assert_close(adjusted.data[0].close, 120.0 * dividend);
+    assert_close(adjusted.data[0].volume, 1000.0);
+    assert_close(adjusted.data[2].volume, 1000.0);
+    assert_eq!(adjusted.data[0].adj_close, Some(adjusted.data[0].close));
+}
+
+#[test]
+fn adjust_skips_the_splits_applied_by_the_publisher() {
+    let series = MarketSeries {
+        symbol: "NVDA".to_string(),
+        interval: Interval::Daily,
+        timezone: Tz::America__New_York,
+        data: vec![bar(7, 100.0, 1000.0), bar(10, 120.0, 1000.0)],
+        adjustment: Adjustment::Splits,
+        actions: vec![CorporateAction::Split {
+            date: date(10),
+            ratio: 10.0,
+        }],
+    };
+
+    let adjusted = series.adjust();
+    assert_close(adjusted.data[0].close, 100.0);
+    assert_close(adjusted.data[0].volume, 1000.0);
+}
+
+#[tokio::test]
+async fn yahoo_finance_events() {
+    let server = MockServer::start().await;
+    Mock::given(query_param("events", "div,splits"))
+        .respond_with(ResponseTemplate::new(200).set_body_string(
+            r#"{"chart": {"result": [{
+                "meta": {"symbol": "AAPL", "exchangeTimezoneName": "America/New_York"},
+                "timestamp": [1723469400, 1723555800],
+                "events": {
+                    "dividends": {"1723469400": {"amount": 0.25, "date": 1723469400}},
+                    "splits": {"1598880600": {"date": 1598880600, "numerator": 4, "denominator": 1, "splitRatio": "4:1"}}
+                },
+                "indicators": {
+                    "quote": [{"open": [216.07, 219.01], "high": [219.51, 221.89], "low": [215.6, 219.01], "close": [217.53, 221.27], "volume": [38028100, 44155300]}],
+                    "adjclose": [{"adjclose": [216.77, 220.5]}]
+                }
+            }], "error": null}}"#,
+        ))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let client = MarketClient::new(YahooFin::new().with_base_url(server.uri()));
+    let series = client
+        .fetch(client.site.daily_series("AAPL", YahooRange::Day5))
+        .await
+        .unwrap();
+
+    assert_eq!(series.adjustment, Adjustment::Splits);
+    assert_eq!(series.data[0].adj_close, Some(216.77));
+    assert_eq!(
+        series.actions,
+        vec![
+            CorporateAction::Split {
+                date: NaiveDate::from_ymd_opt(2020, 8, 31).unwrap(),
+                ratio: 4.0,
+            },
+            CorporateAction::Dividend {
+                date: NaiveDate::from_ymd_opt(2024, 8, 12).unwrap(),
+                amount: 0.25,
+            },
+        ]
+    );
+}
+
+#[test]
+fn adjustment_names_round_trip() {
+    for adjustment in [
+        Adjustment::Raw,
+        Adjustment::Splits,
+        Adjustment::SplitsAndDividends,
+    ] {
+        assert_eq!(
+            adjustment.to_string().parse::<Adjustment>().unwrap(),
+            adjustment
+        );
+    }
+    assert!("splits".parse::<Adjustment>().is_err());
+}
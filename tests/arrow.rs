@@ -0,0 +1,113 @@
+//! Convert MarketSeries to Arrow record batches and Parquet files, run with `--features parquet`
+#![cfg(feature = "arrow")]
+
+use arrow::array::{Array, Float64Array, RecordBatch, TimestampMillisecondArray};
+use chrono::{NaiveDate, NaiveDateTime};
+use market_data::{Adjustment, Interval, MarketError, MarketSeries, Series, Tz};
+use std::sync::Arc;
+
+fn datetime(day: u32) -> NaiveDateTime {
+    NaiveDate::from_ymd_opt(2024, 3, day)
+        .unwrap()
+        .and_hms_opt(9, 30, 0)
+        .unwrap()
+}
+
+fn series() -> MarketSeries {
+    MarketSeries {
+        symbol: "AAPL".to_string(),
+        interval: Interval::Daily,
+        timezone: Tz::America__New_York,
+        data: (0..5)
+            .map(|i| Series {
+                datetime: datetime(4 + i),
+                open: 10.0,
+                close: 10.0 + i as f64,
+                high: 15.0,
+                low: 9.0,
+                volume: 1000.0,
+                adj_close: (i > 2).then_some(9.5 + i as f64),
+            })
+            .collect(),
+        adjustment: Adjustment::Splits,
+        actions: Vec::new(),
+    }
+}
+
+#[test]
+fn record_batch_round_trip() {
+    let series = series();
+    let batch = series.to_record_batch().unwrap();
+    assert_eq!(batch.num_rows(), 5);
+    assert_eq!(batch.schema().metadata()["symbol"], "AAPL");
+    assert_eq!(batch.schema().metadata()["interval"], "Daily");
+
+    let datetimes = batch
+        .column_by_name("datetime")
+        .unwrap()
+        .as_any()
+        .downcast_ref::<TimestampMillisecondArray>()
+        .unwrap();
+    // 9:30 in New York is 14:30 UTC before the daylight saving time
+    assert_eq!(datetimes.value(0), 1709562600000);
+    assert_eq!(datetimes.timezone(), Some("America/New_York"));
+
+    let read = MarketSeries::from_record_batch(&batch).unwrap();
+    assert_eq!(read.symbol, "AAPL");
+    assert_eq!(read.interval, Interval::Daily);
+    assert_eq!(read.timezone, Tz::America__New_York);
+    assert_eq!(read.adjustment, Adjustment::Splits);
+    assert_eq!(read.data, series.data);
+}
+
+#[test]
+fn unknown_adjustment_is_rejected() {
+    let batch = series().to_record_batch().unwrap();
+    let mut metadata = batch.schema().metadata().clone();
+    metadata.insert("adjustment".to_string(), "Dividends".to_string());
+    let schema = batch.schema().as_ref().clone().with_metadata(metadata);
+    let batch = RecordBatch::try_new(Arc::new(schema), batch.columns().to_vec()).unwrap();
+
+    assert!(matches!(
+        MarketSeries::from_record_batch(&batch),
+        Err(MarketError::ParsingError(_))
+    ));
+}
+
+#[test]
+fn enhanced_indicator_columns_with_null_warm_up() {
+    let batch = series()
+        .enhance_data()
+        .with_sma(3)
+        .with_bb(2, 2)
+        .calculate()
+        .to_record_batch()
+        .unwrap();
+
+    let sma = batch
+        .column_by_name("SMA 3")
+        .unwrap()
+        .as_any()
+        .downcast_ref::<Float64Array>()
+        .unwrap();
+    assert_eq!(sma.null_count(), 2);
+    assert_eq!(sma.value(2), 11.0);
+    assert!(batch.column_by_name("BB (2, 2)/upper").is_some());
+    assert!(batch.column_by_name("BB (2, 2)/lower").is_some());
+}
+
+#[cfg(feature = "parquet")]
+#[test]
+fn parquet_round_trip() {
+    let series = series();
+    let path = std::env::temp_dir().join(format!("market-data-{}.parquet", std::process::id()));
+    series
+        .to_parquet(std::fs::File::create(&path).unwrap())
+        .unwrap();
+
+    let read = MarketSeries::from_parquet(std::fs::File::open(&path).unwrap()).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(read.symbol, series.symbol);
+    assert_eq!(read.timezone, series.timezone);
+    assert_eq!(read.data, series.data);
+}
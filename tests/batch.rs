@@ -0,0 +1,141 @@
+//! Fetch several symbols with fetch_many and fetch_stream, and implement a minimal Publisher
+
+use futures::StreamExt;
+use market_data::{
+    Interval, MarketClient, MarketError, MarketResult, MarketSeries, Publisher, SeriesRequest,
+    Twelvedata,
+};
+use std::time::{Duration, Instant};
+use url::Url;
+use wiremock::{
+    matchers::{method, path, query_param},
+    Mock, MockServer, ResponseTemplate,
+};
+
+const DELAY: Duration = Duration::from_millis(300);
+
+/// MSFT answers slower than AAPL, NOPE is unknown
+async fn server() -> MockServer {
+    let server = MockServer::start().await;
+    let body = include_str!("fixtures/twelvedata_daily.json");
+    for (symbol, delay) in [("AAPL", DELAY), ("MSFT", DELAY * 2)] {
+        Mock::given(method("GET"))
+            .and(path("/time_series"))
+            .and(query_param("symbol", symbol))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_string(body)
+                    .set_delay(delay),
+            )
+            .mount(&server)
+            .await;
+    }
+    Mock::given(method("GET"))
+        .and(query_param("symbol", "NOPE"))
+        .respond_with(ResponseTemplate::new(404).set_delay(DELAY))
+        .mount(&server)
+        .await;
+    server
+}
+
+#[tokio::test]
+async fn fetch_many_keeps_the_request_order_and_the_failures() {
+    let server = server().await;
+    let client = MarketClient::new(Twelvedata::new("demo").with_base_url(server.uri()));
+    let requests = ["MSFT", "NOPE", "AAPL"].map(|symbol| client.site.daily_series(symbol, 3));
+
+    let start = Instant::now();
+    let results = client.fetch_many(requests, 3).await;
+    // the three requests ran at the same time, the slowest one takes 2 delays
+    assert!(start.elapsed() < DELAY * 4);
+
+    let symbols: Vec<&str> = results.iter().map(|(symbol, _)| symbol.as_str()).collect();
+    assert_eq!(symbols, ["MSFT", "NOPE", "AAPL"]);
+    assert_eq!(results[0].1.as_ref().unwrap().data.len(), 3);
+    assert!(matches!(
+        &results[1].1,
+        Err(MarketError::SymbolNotFound { symbol, .. }) if symbol == "NOPE"
+    ));
+    assert!(results[2].1.is_ok());
+}
+
+#[tokio::test]
+async fn fetch_many_bounds_the_concurrency() {
+    let server = server().await;
+    let client = MarketClient::new(Twelvedata::new("demo").with_base_url(server.uri()));
+    let requests = ["AAPL", "AAPL", "AAPL"].map(|symbol| client.site.daily_series(symbol, 3));
+
+    let start = Instant::now();
+    let results = client.fetch_many(requests, 1).await;
+    assert!(start.elapsed() >= DELAY * 3);
+    assert!(results.iter().all(|(_, result)| result.is_ok()));
+}
+
+#[tokio::test]
+async fn fetch_stream_yields_the_fastest_first() {
+    let server = server().await;
+    let client = MarketClient::new(Twelvedata::new("demo").with_base_url(server.uri()));
+    let requests = ["MSFT", "AAPL"].map(|symbol| client.site.daily_series(symbol, 3));
+
+    let symbols: Vec<String> = client
+        .fetch_stream(requests, 2)
+        .map(|(symbol, result)| {
+            assert!(result.is_ok());
+            symbol
+        })
+        .collect()
+        .await;
+    assert_eq!(symbols, ["AAPL", "MSFT"]);
+}
+
+/// A publisher implementing only the required methods
+struct Minimal {
+    base_url: String,
+}
+
+impl Publisher for Minimal {
+    type Request = String;
+
+    fn create_endpoint(&self, request: &String) -> MarketResult<Url> {
+        Ok(Url::parse(&format!("{}/{}", self.base_url, request))?)
+    }
+
+    fn transform_data(&self, _data: String, request: &String) -> MarketResult<MarketSeries> {
+        Ok(MarketSeries {
+            symbol: request.clone(),
+            interval: Interval::Daily,
+            timezone: market_data::Tz::UTC,
+            data: Vec::new(),
+            adjustment: Default::default(),
+            actions: Vec::new(),
+        })
+    }
+}
+
+#[tokio::test]
+async fn minimal_publisher_gets_the_defaults() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .respond_with(ResponseTemplate::new(200))
+        .mount(&server)
+        .await;
+    let client = MarketClient::new(Minimal {
+        base_url: server.uri(),
+    });
+
+    assert_eq!(client.site.name(), "Minimal");
+    assert!(client.site.capabilities().intervals.is_empty());
+    assert!(matches!(
+        client
+            .fetch_series(&SeriesRequest::new("AAPL", Interval::Daily))
+            .await,
+        Err(MarketError::UnsuportedInterval(_))
+    ));
+    assert_ne!(
+        client.site.cache_key(&"AAPL".to_string()),
+        client.site.cache_key(&"MSFT".to_string())
+    );
+
+    let results = client.fetch_many(["AAPL".to_string()], 2).await;
+    assert!(results[0].1.is_ok());
+}
@@ -0,0 +1,209 @@
+//! Write and read MarketSeries in the compact binary format
+
+use chrono::{Duration, NaiveDate, NaiveDateTime};
+use market_data::{
+    Adjustment, BinaryHeader, BinaryOptions, BinaryReader, BinaryWriter, CorporateAction, Interval,
+    MarketError, MarketSeries, Series, Tz,
+};
+
+fn start() -> NaiveDateTime {
+    NaiveDate::from_ymd_opt(2024, 3, 4)
+        .unwrap()
+        .and_hms_opt(9, 30, 0)
+        .unwrap()
+}
+
+fn series(bars: usize) -> MarketSeries {
+    MarketSeries {
+        symbol: "AAPL".to_string(),
+        interval: Interval::Min1,
+        timezone: Tz::America__New_York,
+        data: (0..bars)
+            .map(|i| {
+                let close = 170.0 + (i % 13) as f64 * 0.01 - (i % 5) as f64 * 0.25;
+                Series {
+                    datetime: start() + Duration::minutes(i as i64),
+                    open: close - 0.03,
+                    close,
+                    high: close + 0.12,
+                    low: close - 0.07,
+                    volume: 1000.0 + (i % 17) as f64,
+                    adj_close: (i % 2 == 0).then_some(close * 0.99),
+                }
+            })
+            .collect(),
+        adjustment: Adjustment::Splits,
+        actions: vec![
+            CorporateAction::Dividend {
+                date: NaiveDate::from_ymd_opt(2024, 2, 9).unwrap(),
+                amount: 0.24,
+            },
+            CorporateAction::Split {
+                date: NaiveDate::from_ymd_opt(2020, 8, 31).unwrap(),
+                ratio: 4.0,
+            },
+        ],
+    }
+}
+
+#[test]
+fn lossless_round_trip() {
+    let mut series = series(500);
+    series.data[3].close = 170.123456789;
+    series.data[4].volume = 0.5;
+    let bytes = series.to_bytes().unwrap();
+
+    let read = MarketSeries::from_bytes(&bytes).unwrap();
+    assert_eq!(read.symbol, series.symbol);
+    assert_eq!(read.interval, series.interval);
+    assert_eq!(read.timezone, series.timezone);
+    assert_eq!(read.adjustment, series.adjustment);
+    assert_eq!(read.actions, series.actions);
+    assert_eq!(read.data, series.data);
+    assert!(bytes.len() < serde_json::to_vec(&series).unwrap().len() / 2);
+}
+
+#[test]
+fn quantized_round_trip_is_identical_within_the_decimals() {
+    // prices with 2 decimals, as parsed from a publisher response
+    let cents = |price: f64| (price * 100.0).round() / 100.0;
+    let mut series = series(500);
+    for bar in series.data.iter_mut() {
+        bar.open = cents(bar.open);
+        bar.high = cents(bar.high);
+        bar.low = cents(bar.low);
+        bar.close = cents(bar.close);
+        bar.adj_close = bar.adj_close.map(cents);
+    }
+    let options = BinaryOptions::default()
+        .with_price_decimals(2)
+        .with_volume_decimals(0);
+    let mut bytes = Vec::new();
+    series.to_binary_with(&mut bytes, &options).unwrap();
+    let lossless = series.to_bytes().unwrap();
+    assert!(bytes.len() < lossless.len() / 3);
+
+    let read = MarketSeries::from_binary(bytes.as_slice()).unwrap();
+    assert_eq!(read.data, series.data);
+
+    // the prices with more decimals are rounded
+    series.data[0].close = 170.126;
+    let mut bytes = Vec::new();
+    series.to_binary_with(&mut bytes, &options).unwrap();
+    let read = MarketSeries::from_bytes(&bytes).unwrap();
+    assert_eq!(read.data[0].close, 170.13);
+}
+
+#[test]
+fn streaming_writes_and_reads() {
+    let series = series(50);
+    let mut writer = BinaryWriter::new(
+        Vec::new(),
+        &BinaryHeader::from(&series),
+        &Default::default(),
+    )
+    .unwrap();
+    for bar in series.data.iter() {
+        writer.write(bar).unwrap();
+    }
+    let bytes = writer.finish().unwrap();
+
+    let reader = BinaryReader::new(bytes.as_slice()).unwrap();
+    assert_eq!(reader.header().symbol, "AAPL");
+    assert_eq!(reader.header().actions.len(), 2);
+    let mut count = 0;
+    for (bar, expected) in reader.zip(series.data.iter()) {
+        assert_eq!(&bar.unwrap(), expected);
+        count += 1;
+    }
+    assert_eq!(count, 50);
+}
+
+#[test]
+fn rejects_invalid_input() {
+    let bytes = series(10).to_bytes().unwrap();
+    assert!(matches!(
+        MarketSeries::from_bytes(b"{\"symbol\": \"AAPL\"}"),
+        Err(MarketError::ParsingError(_))
+    ));
+
+    let mut newer = bytes.clone();
+    newer[4] = 2;
+    assert!(matches!(
+        MarketSeries::from_bytes(&newer),
+        Err(MarketError::ParsingError(_))
+    ));
+
+    // a truncated last bar is an error, not a shorter series
+    assert!(MarketSeries::from_bytes(&bytes[..bytes.len() - 3]).is_err());
+}
+
+#[test]
+fn rejects_unknown_flags_and_actions() {
+    let series = series(10);
+    let bytes = series.to_bytes().unwrap();
+    let rejected = |offset: usize, value: u8| {
+        let mut bytes = bytes.clone();
+        bytes[offset] = value;
+        matches!(
+            MarketSeries::from_bytes(&bytes),
+            Err(MarketError::ParsingError(_))
+        )
+    };
+
+    // the quantization flags follow the magic and the version
+    assert!(rejected(5, 1 << 2));
+    // the symbol, the interval and the timezone, then the adjustment and the count of actions
+    let first_action = 6
+        + 1
+        + series.symbol.len()
+        + 1
+        + series.interval.to_string().len()
+        + 1
+        + series.timezone.name().len()
+        + 2;
+    assert_eq!(bytes[first_action], 0);
+    assert!(rejected(first_action, 2));
+    // the last bar has no adjusted close: the flags, one byte of datetime and five f64
+    assert!(rejected(bytes.len() - 42, 1 << 1));
+}
+
+#[test]
+fn writing_continues_after_a_rejected_bar() {
+    let series = series(5);
+    let options = BinaryOptions::default()
+        .with_price_decimals(4)
+        .with_volume_decimals(0);
+    let mut writer = BinaryWriter::new(Vec::new(), &BinaryHeader::from(&series), &options).unwrap();
+    for (i, bar) in series.data.iter().enumerate() {
+        if i == 2 {
+            let mut invalid = bar.clone();
+            invalid.high = f64::NAN;
+            assert!(writer.write(&invalid).is_err());
+            let mut invalid = bar.clone();
+            invalid.volume = f64::INFINITY;
+            assert!(writer.write(&invalid).is_err());
+        } else {
+            writer.write(bar).unwrap();
+        }
+    }
+    let bytes = writer.finish().unwrap();
+
+    let read = BinaryReader::new(bytes.as_slice())
+        .unwrap()
+        .collect::<Result<Vec<Series>, _>>()
+        .unwrap();
+    let expected: Vec<&Series> = series
+        .data
+        .iter()
+        .enumerate()
+        .filter(|(i, _)| *i != 2)
+        .map(|(_, bar)| bar)
+        .collect();
+    assert_eq!(read.len(), expected.len());
+    for (bar, expected) in read.iter().zip(expected) {
+        assert_eq!(bar.datetime, expected.datetime);
+        assert!((bar.close - expected.close).abs() < 1e-9);
+        assert_eq!(bar.volume, expected.volume);
+    }
+}
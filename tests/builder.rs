@@ -0,0 +1,91 @@
+//! Configure the HTTP transport of a MarketClient with MarketClientBuilder
+
+use market_data::{Finnhub, MarketClient, MarketError};
+use std::time::Duration;
+use wiremock::{matchers::method, Mock, MockServer, ResponseTemplate};
+
+async fn server(template: ResponseTemplate) -> MockServer {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .respond_with(template)
+        .mount(&server)
+        .await;
+    server
+}
+
+/// The user agent of the single request received by the server
+async fn user_agent(server: &MockServer) -> Option<String> {
+    let requests = server.received_requests().await.unwrap();
+    assert_eq!(requests.len(), 1);
+    requests[0]
+        .headers
+        .get("user-agent")
+        .map(|value| value.to_str().unwrap().to_string())
+}
+
+#[tokio::test]
+async fn default_and_custom_user_agent() {
+    let server = server(ResponseTemplate::new(404)).await;
+    let client = MarketClient::builder(Finnhub::new("demo").with_base_url(server.uri()))
+        .build()
+        .unwrap();
+    let _ = client.fetch(client.site.quote("AAPL")).await;
+    assert!(user_agent(&server)
+        .await
+        .unwrap()
+        .starts_with("market-data-rust/"));
+
+    let server = self::server(ResponseTemplate::new(404)).await;
+    let client = MarketClient::builder(Finnhub::new("demo").with_base_url(server.uri()))
+        .with_user_agent("my-app/1.0")
+        .build()
+        .unwrap();
+    let _ = client.fetch(client.site.quote("AAPL")).await;
+    assert_eq!(user_agent(&server).await.as_deref(), Some("my-app/1.0"));
+}
+
+#[tokio::test]
+async fn timeout_aborts_slow_responses() {
+    let server = server(ResponseTemplate::new(200).set_delay(Duration::from_secs(2))).await;
+    let client = MarketClient::builder(Finnhub::new("demo").with_base_url(server.uri()))
+        .with_timeout(Duration::from_millis(100))
+        .build()
+        .unwrap();
+
+    let result = client.fetch(client.site.quote("AAPL")).await;
+    assert!(matches!(result, Err(MarketError::RetriveDataError(e)) if e.is_timeout()));
+}
+
+#[tokio::test]
+async fn requests_go_through_the_proxy() {
+    let proxy = server(ResponseTemplate::new(404)).await;
+    // the publisher host doesn't resolve, only the proxy can answer
+    let client = MarketClient::builder(
+        Finnhub::new("demo").with_base_url("http://publisher.invalid/api/v1"),
+    )
+    .with_proxy(reqwest::Proxy::http(proxy.uri()).unwrap())
+    .build()
+    .unwrap();
+
+    let result = client.fetch(client.site.quote("AAPL")).await;
+    assert!(matches!(result, Err(MarketError::SymbolNotFound { .. })));
+    let requests = proxy.received_requests().await.unwrap();
+    assert_eq!(requests.len(), 1);
+    assert_eq!(requests[0].url.host_str(), Some("publisher.invalid"));
+}
+
+#[tokio::test]
+async fn with_client_ignores_the_transport_settings() {
+    let server = server(ResponseTemplate::new(404)).await;
+    let client = MarketClient::builder(Finnhub::new("demo").with_base_url(server.uri()))
+        .with_user_agent("ignored/1.0")
+        .with_timeout(Duration::from_nanos(1))
+        .with_client(reqwest::Client::new())
+        .build()
+        .unwrap();
+
+    // neither the timeout nor the user agent were applied to the provided client
+    let result = client.fetch(client.site.quote("AAPL")).await;
+    assert!(matches!(result, Err(MarketError::SymbolNotFound { .. })));
+    assert_eq!(user_agent(&server).await, None);
+}
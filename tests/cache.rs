@@ -0,0 +1,213 @@
+//! The on-disk cache: fresh hits, incremental refreshes and rolling windows
+
+use market_data::{Adjustment, FileCache, Finnhub, MarketClient, Twelvedata};
+use std::{path::PathBuf, time::Duration};
+use wiremock::{
+    matchers::{method, path, query_param},
+    Mock, MockServer, ResponseTemplate,
+};
+
+/// A cache directory of its own for each test
+async fn cache(name: &str) -> FileCache {
+    let dir: PathBuf =
+        std::env::temp_dir().join(format!("market-data-cache-{}-{}", name, std::process::id()));
+    let cache = FileCache::new(dir);
+    cache.clear().await.unwrap();
+    cache
+}
+
+#[tokio::test]
+async fn fresh_entry_is_served_without_a_request() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_string(include_str!("fixtures/twelvedata_daily.json")),
+        )
+        .expect(1)
+        .mount(&server)
+        .await;
+    let cache = cache("fresh").await.with_max_age(Duration::from_secs(3600));
+    let client = MarketClient::new(Twelvedata::new("demo").with_base_url(server.uri()))
+        .with_cache(cache.clone());
+
+    let first = client
+        .fetch(client.site.daily_series("AAPL", 3))
+        .await
+        .unwrap();
+    let second = client
+        .fetch(client.site.daily_series("AAPL", 3))
+        .await
+        .unwrap();
+    assert_eq!(first.data, second.data);
+    cache.clear().await.unwrap();
+}
+
+#[tokio::test]
+async fn stale_entry_requests_only_the_new_bars() {
+    let server = MockServer::start().await;
+    // 2024-03-04 and 2024-03-05
+    Mock::given(path("/stock/candle"))
+        .and(query_param("from", "1709510400"))
+        .respond_with(ResponseTemplate::new(200).set_body_string(
+            r#"{"c":[175.1,170.0],"h":[176.9,172.04],"l":[173.79,169.62],"o":[176.15,170.76],"s":"ok","t":[1709510400,1709596800],"v":[81510100,50000000]}"#,
+        ))
+        .expect(1)
+        .mount(&server)
+        .await;
+    // from the last cached bar, which was still incomplete, up to 2024-03-06
+    Mock::given(path("/stock/candle"))
+        .and(query_param("from", "1709596800"))
+        .respond_with(ResponseTemplate::new(200).set_body_string(
+            r#"{"c":[170.12,169.12],"h":[172.04,171.24],"l":[169.62,168.68],"o":[170.76,171.06],"s":"ok","t":[1709596800,1709683200],"v":[95132400,68587700]}"#,
+        ))
+        .expect(1)
+        .mount(&server)
+        .await;
+    let cache = cache("stale").await.with_max_age(Duration::ZERO);
+    let client = MarketClient::new(Finnhub::new("demo").with_base_url(server.uri()))
+        .with_cache(cache.clone());
+    let request = client.site.daily_series("AAPL", 1709510400, 1709769600);
+
+    assert_eq!(client.fetch(request.clone()).await.unwrap().data.len(), 2);
+    let refreshed = client.fetch(request).await.unwrap();
+    let closes: Vec<f64> = refreshed.data.iter().map(|s| s.close).collect();
+    assert_eq!(closes, [175.1, 170.12, 169.12]);
+    assert_eq!(refreshed.data[1].volume, 95132400.0);
+    cache.clear().await.unwrap();
+}
+
+#[tokio::test]
+async fn rolling_window_keeps_its_size() {
+    let server = MockServer::start().await;
+    let fixture = include_str!("fixtures/twelvedata_daily.json");
+    Mock::given(method("GET"))
+        .respond_with(ResponseTemplate::new(200).set_body_string(fixture))
+        .up_to_n_times(1)
+        .mount(&server)
+        .await;
+    // a day later, 2024-03-04 went out of the last 3 bars
+    let next_day = r#"{"meta":{"symbol":"AAPL","interval":"1day","exchange_timezone":"America/New_York"},"values":[{"datetime":"2024-03-07","open":"169.14999","high":"170.73000","low":"168.49001","close":"169.00000","volume":"71765100"},{"datetime":"2024-03-06","open":"171.06000","high":"171.24001","low":"168.67999","close":"169.12000","volume":"68587700"},{"datetime":"2024-03-05","open":"170.75999","high":"172.03999","low":"169.62000","close":"170.12000","volume":"95132400"}],"status":"ok"}"#;
+    Mock::given(method("GET"))
+        .respond_with(ResponseTemplate::new(200).set_body_string(next_day))
+        .mount(&server)
+        .await;
+    let cache = cache("rolling").await.with_max_age(Duration::ZERO);
+    let client = MarketClient::new(Twelvedata::new("demo").with_base_url(server.uri()))
+        .with_cache(cache.clone());
+
+    client
+        .fetch(client.site.daily_series("AAPL", 3))
+        .await
+        .unwrap();
+    let refreshed = client
+        .fetch(client.site.daily_series("AAPL", 3))
+        .await
+        .unwrap();
+    let dates: Vec<String> = refreshed
+        .data
+        .iter()
+        .map(|s| s.datetime.date().to_string())
+        .collect();
+    assert_eq!(dates, ["2024-03-05", "2024-03-06", "2024-03-07"]);
+    cache.clear().await.unwrap();
+}
+
+/// The Finnhub candles from 2024-03-04 to 2024-03-06, divided by `ratio` after a split
+fn candles(days: &[usize], ratio: f64) -> String {
+    let t = [1709510400, 1709596800, 1709683200];
+    let o = [176.15, 170.76, 171.06];
+    let c = [175.1, 170.12, 169.12];
+    let price = |values: &[f64; 3]| {
+        let values: Vec<String> = days
+            .iter()
+            .map(|&day| (values[day] / ratio).to_string())
+            .collect();
+        values.join(",")
+    };
+    let times: Vec<String> = days.iter().map(|&day| t[day].to_string()).collect();
+    format!(
+        r#"{{"c":[{close}],"h":[{close}],"l":[{close}],"o":[{open}],"s":"ok","t":[{times}],"v":[{volumes}]}}"#,
+        close = price(&c),
+        open = price(&o),
+        times = times.join(","),
+        volumes = vec!["1000"; days.len()].join(","),
+    )
+}
+
+#[tokio::test]
+async fn entry_that_cant_be_merged_is_rewritten() {
+    let server = MockServer::start().await;
+    Mock::given(path("/stock/candle"))
+        .and(query_param("from", "1709510400"))
+        .respond_with(ResponseTemplate::new(200).set_body_string(candles(&[0, 1], 1.0)))
+        .expect(2)
+        .mount(&server)
+        .await;
+    Mock::given(path("/stock/candle"))
+        .and(query_param("from", "1709596800"))
+        .respond_with(ResponseTemplate::new(200).set_body_string(candles(&[1, 2], 1.0)))
+        .expect(1)
+        .mount(&server)
+        .await;
+    let cache = cache("unmergeable").await.with_max_age(Duration::ZERO);
+    let client = MarketClient::new(Finnhub::new("demo").with_base_url(server.uri()))
+        .with_cache(cache.clone());
+    let request = client.site.daily_series("AAPL", 1709510400, 1709769600);
+    client.fetch(request.clone()).await.unwrap();
+
+    // an entry written with another adjustment, e.g. by a previous version
+    let file = std::fs::read_dir(cache.dir().join("finnhub"))
+        .unwrap()
+        .next()
+        .unwrap()
+        .unwrap()
+        .path();
+    let mut entry: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(&file).unwrap()).unwrap();
+    entry["adjustment"] = "Raw".into();
+    std::fs::write(&file, entry.to_string()).unwrap();
+
+    let refetched = client.fetch(request).await.unwrap();
+    assert_eq!(refetched.adjustment, Adjustment::Splits);
+    assert_eq!(refetched.data.len(), 2);
+    let entry: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(&file).unwrap()).unwrap();
+    assert_eq!(entry["adjustment"], "Splits");
+    cache.clear().await.unwrap();
+}
+
+#[tokio::test]
+async fn adjusted_entry_is_rewritten_after_a_split() {
+    let server = MockServer::start().await;
+    Mock::given(path("/stock/candle"))
+        .and(query_param("from", "1709510400"))
+        .respond_with(ResponseTemplate::new(200).set_body_string(candles(&[0, 1], 1.0)))
+        .up_to_n_times(1)
+        .expect(1)
+        .mount(&server)
+        .await;
+    // a 4:1 split since the last fetch, the cached bars are on the old basis
+    Mock::given(path("/stock/candle"))
+        .and(query_param("from", "1709596800"))
+        .respond_with(ResponseTemplate::new(200).set_body_string(candles(&[1, 2], 4.0)))
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(path("/stock/candle"))
+        .and(query_param("from", "1709510400"))
+        .respond_with(ResponseTemplate::new(200).set_body_string(candles(&[0, 1, 2], 4.0)))
+        .expect(1)
+        .mount(&server)
+        .await;
+    let cache = cache("split").await.with_max_age(Duration::ZERO);
+    let client = MarketClient::new(Finnhub::new("demo").with_base_url(server.uri()))
+        .with_cache(cache.clone());
+    let request = client.site.daily_series("AAPL", 1709510400, 1709769600);
+
+    client.fetch(request.clone()).await.unwrap();
+    let refreshed = client.fetch(request).await.unwrap();
+    let closes: Vec<f64> = refreshed.data.iter().map(|s| s.close).collect();
+    assert_eq!(closes, [175.1 / 4.0, 170.12 / 4.0, 169.12 / 4.0]);
+    cache.clear().await.unwrap();
+}
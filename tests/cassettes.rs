@@ -0,0 +1,285 @@
+//! Replay the cassettes shipped in `cassettes/`, to catch regressions in the publishers' parsing offline
+
+use chrono::{NaiveDate, NaiveDateTime};
+use market_data::{
+    AlphaVantage, Cassette, Finnhub, Interval, MarketClient, MarketError, MarketSeries, Massive,
+    OutputSize, Publisher, RetryPolicy, Twelvedata, Tz, YahooFin, YahooRange,
+};
+use std::time::Duration;
+use tokio::time::Instant;
+use wiremock::{matchers::method, Mock, MockServer, ResponseTemplate};
+
+fn replay<T: Publisher>(site: T, cassette: &str) -> MarketClient<T> {
+    MarketClient::new(site).with_cassette(Cassette::replay_from_str(cassette).unwrap())
+}
+
+fn datetime(s: &str) -> NaiveDateTime {
+    NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M:%S").unwrap()
+}
+
+fn assert_sorted(series: &MarketSeries) {
+    assert!(series
+        .data
+        .windows(2)
+        .all(|w| w[0].datetime < w[1].datetime));
+}
+
+fn assert_close(actual: f64, expected: f64) {
+    assert!(
+        (actual - expected).abs() < 0.001,
+        "{} != {}",
+        actual,
+        expected
+    );
+}
+
+#[tokio::test]
+async fn alphavantage() {
+    let client = replay(
+        AlphaVantage::new("demo"),
+        include_str!("../cassettes/alphavantage.json"),
+    );
+
+    let daily = client
+        .fetch(client.site.daily_series("AAPL", OutputSize::Compact))
+        .await
+        .unwrap();
+    assert!(matches!(daily.interval, Interval::Daily));
+    assert_eq!(daily.timezone, Tz::US__Eastern);
+    assert_eq!(daily.data.len(), 3);
+    assert_sorted(&daily);
+    assert_eq!(daily.data[0].datetime, datetime("2024-03-04 00:00:00"));
+    assert_close(daily.data[0].open, 176.15);
+    assert_close(daily.data[2].close, 169.12);
+    assert_eq!(daily.data[2].volume, 68_587_707.0);
+
+    let request = client
+        .site
+        .intraday_series("AAPL", OutputSize::Compact, Interval::Min5)
+        .unwrap();
+    let intraday = client.fetch(request).await.unwrap();
+    assert!(matches!(intraday.interval, Interval::Min5));
+    assert_eq!(intraday.data.len(), 3);
+    assert_sorted(&intraday);
+    assert_eq!(intraday.data[0].datetime, datetime("2024-03-06 19:45:00"));
+    assert_close(intraday.data[2].high, 169.19);
+
+    let weekly = client
+        .fetch(client.site.weekly_series("AAPL", OutputSize::Compact))
+        .await
+        .unwrap();
+    assert!(matches!(weekly.interval, Interval::Weekly));
+    assert_eq!(weekly.data.len(), 2);
+    assert_eq!(weekly.data[1].datetime, datetime("2024-03-08 00:00:00"));
+}
+
+#[tokio::test]
+async fn finnhub() {
+    let client = replay(
+        Finnhub::new("demo"),
+        include_str!("../cassettes/finnhub.json"),
+    );
+
+    let daily = client
+        .fetch(client.site.daily_series("AAPL", 1709510400, 1709683200))
+        .await
+        .unwrap();
+    assert!(matches!(daily.interval, Interval::Daily));
+    assert_eq!(daily.timezone, Tz::UTC);
+    assert_eq!(daily.data.len(), 3);
+    assert_eq!(daily.data[0].datetime, datetime("2024-03-04 00:00:00"));
+    assert_close(daily.data[1].low, 169.62);
+
+    let request = client
+        .site
+        .intraday_series("AAPL", 1709757000, 1709757600, Interval::Min5)
+        .unwrap();
+    let intraday = client.fetch(request).await.unwrap();
+    assert!(matches!(intraday.interval, Interval::Min5));
+    assert_eq!(intraday.data.len(), 3);
+    assert_eq!(intraday.data[0].datetime, datetime("2024-03-06 20:30:00"));
+    assert_eq!(intraday.data[2].volume, 1_210_887.0);
+
+    let quote = client.fetch(client.site.quote("AAPL")).await.unwrap();
+    assert_eq!(quote.data.len(), 1);
+    assert_close(quote.data[0].close, 169.12);
+    assert_close(quote.data[0].open, 171.06);
+}
+
+#[tokio::test]
+async fn massive() {
+    let client = replay(
+        Massive::new("demo"),
+        include_str!("../cassettes/massive.json"),
+    );
+
+    let daily = client
+        .fetch(
+            client
+                .site
+                .daily_series("AAPL", "2024-03-04", "2024-03-06", 5000),
+        )
+        .await
+        .unwrap();
+    assert_eq!(daily.symbol, "AAPL");
+    assert!(matches!(daily.interval, Interval::Daily));
+    assert_eq!(daily.data.len(), 3);
+    assert_eq!(daily.data[0].datetime, datetime("2024-03-04 05:00:00"));
+    assert_close(daily.data[0].high, 176.9);
+
+    let request = client
+        .site
+        .intraday_series("AAPL", "2024-03-06", "2024-03-06", Interval::Hour1, 5000)
+        .unwrap();
+    let hourly = client.fetch(request).await.unwrap();
+    assert!(matches!(hourly.interval, Interval::Hour1));
+    assert_eq!(hourly.data.len(), 3);
+    assert_sorted(&hourly);
+    assert_eq!(hourly.data[2].volume, 9_912_837.0);
+}
+
+#[tokio::test]
+async fn twelvedata() {
+    let client = replay(
+        Twelvedata::new("demo"),
+        include_str!("../cassettes/twelvedata.json"),
+    );
+
+    let daily = client
+        .fetch(client.site.daily_series("AAPL", 3))
+        .await
+        .unwrap();
+    assert!(matches!(daily.interval, Interval::Daily));
+    assert_eq!(daily.data.len(), 3);
+    assert_sorted(&daily);
+    assert_eq!(daily.data[0].datetime, datetime("2024-03-04 00:00:00"));
+    assert_close(daily.data[0].close, 175.1);
+
+    let request = client
+        .site
+        .intraday_series("AAPL", 3, Interval::Min15)
+        .unwrap();
+    let intraday = client.fetch(request).await.unwrap();
+    assert!(matches!(intraday.interval, Interval::Min15));
+    assert_eq!(intraday.timezone, Tz::America__New_York);
+    assert_eq!(intraday.data.len(), 3);
+    assert_eq!(intraday.data[0].datetime, datetime("2024-03-06 15:15:00"));
+    assert_eq!(
+        intraday.datetimes().next().unwrap().naive_utc(),
+        datetime("2024-03-06 20:15:00")
+    );
+    assert_eq!(intraday.data[2].volume, 3_212_451.0);
+}
+
+#[tokio::test]
+async fn yahoo_finance() {
+    let client = replay(
+        YahooFin::new(),
+        include_str!("../cassettes/yahoo_finance.json"),
+    );
+
+    let daily = client
+        .fetch(client.site.daily_series("AAPL", YahooRange::Day5))
+        .await
+        .unwrap();
+    assert!(matches!(daily.interval, Interval::Daily));
+    assert_eq!(daily.data.len(), 3);
+    assert_eq!(
+        daily.data[0].datetime.date(),
+        NaiveDate::from_ymd_opt(2024, 3, 4).unwrap()
+    );
+    assert_close(daily.data[0].open, 176.15);
+
+    // the bar with null values is skipped
+    let request = client
+        .site
+        .intraday_series("AAPL", Interval::Min5, YahooRange::Day1)
+        .unwrap();
+    let intraday = client.fetch(request).await.unwrap();
+    assert!(matches!(intraday.interval, Interval::Min5));
+    assert_eq!(intraday.timezone, Tz::America__New_York);
+    assert_eq!(intraday.data.len(), 3);
+    assert_eq!(intraday.data[1].datetime, datetime("2024-03-06 15:30:00"));
+
+    let utc = intraday.to_utc();
+    assert_eq!(utc.timezone, Tz::UTC);
+    assert_eq!(utc.data[1].datetime, datetime("2024-03-06 20:30:00"));
+
+    let weekly = client
+        .fetch(client.site.weekly_series("AAPL", YahooRange::Month1))
+        .await
+        .unwrap();
+    assert!(matches!(weekly.interval, Interval::Weekly));
+    assert_eq!(weekly.data.len(), 2);
+}
+
+#[tokio::test]
+async fn missing_recording() {
+    let client = replay(
+        YahooFin::new(),
+        include_str!("../cassettes/yahoo_finance.json"),
+    );
+
+    let result = client
+        .fetch(client.site.daily_series("MSFT", YahooRange::Day5))
+        .await;
+    assert!(matches!(result, Err(MarketError::NoRecordedResponse(_))));
+}
+
+#[tokio::test]
+async fn record_then_replay() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_string(include_str!("fixtures/twelvedata_daily.json")),
+        )
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let path =
+        std::env::temp_dir().join(format!("market-data-cassette-{}.json", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+
+    let site = Twelvedata::new("secret-key").with_base_url(server.uri());
+    let client = MarketClient::new(site).with_cassette(Cassette::record(&path).await.unwrap());
+    let recorded = client
+        .fetch(client.site.daily_series("AAPL", 3))
+        .await
+        .unwrap();
+
+    let content = std::fs::read_to_string(&path).unwrap();
+    assert!(!content.contains("secret-key"));
+
+    let site = Twelvedata::new("another-key").with_base_url(server.uri());
+    let client = MarketClient::new(site).with_cassette(Cassette::replay(&path).await.unwrap());
+    let replayed = client
+        .fetch(client.site.daily_series("AAPL", 3))
+        .await
+        .unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(recorded.data.len(), replayed.data.len());
+    assert_eq!(recorded.data[0].datetime, replayed.data[0].datetime);
+}
+
+#[tokio::test(start_paused = true)]
+async fn replayed_errors_are_retried_without_waiting() {
+    let cassette = r#"{"interactions":[{"url":"https://query1.finance.yahoo.com/v8/finance/chart/AAPL?metrics=high&interval=1d&range=5d&events=div%2Csplits","status":503,"body":"unavailable"}]}"#;
+    let client = replay(YahooFin::new(), cassette).with_retry(
+        RetryPolicy::default()
+            .with_max_attempts(3)
+            .with_base_delay(Duration::from_secs(10)),
+    );
+
+    let start = Instant::now();
+    let result = client
+        .fetch(client.site.daily_series("AAPL", YahooRange::Day5))
+        .await;
+    assert!(matches!(
+        result,
+        Err(MarketError::RetriesExhausted { attempts: 3, .. })
+    ));
+    assert_eq!(start.elapsed(), Duration::ZERO);
+}
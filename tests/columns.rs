@@ -0,0 +1,113 @@
+//! Run the indicators on the struct-of-arrays view of a MarketSeries
+
+use chrono::{NaiveDate, NaiveDateTime};
+use market_data::{Adjustment, Interval, MarketError, MarketSeries, Series, SeriesColumns, Tz};
+
+fn datetime(day: u32) -> NaiveDateTime {
+    NaiveDate::from_ymd_opt(2024, 3, day)
+        .unwrap()
+        .and_hms_opt(0, 0, 0)
+        .unwrap()
+}
+
+fn series() -> MarketSeries {
+    MarketSeries {
+        symbol: "AAPL".to_string(),
+        interval: Interval::Daily,
+        timezone: Tz::America__New_York,
+        data: (1..=30)
+            .map(|day| {
+                let close = 100.0 + (day as f64).sin() * 5.0;
+                Series {
+                    datetime: datetime(day),
+                    open: close - 1.0,
+                    close,
+                    high: close + 2.0,
+                    low: close - 2.0,
+                    volume: 1000.0 * day as f64,
+                    adj_close: None,
+                }
+            })
+            .collect(),
+        adjustment: Adjustment::Raw,
+        actions: Vec::new(),
+    }
+}
+
+#[test]
+fn columns_hold_the_bar_fields() {
+    let series = series();
+    let columns = series.to_columns();
+    assert_eq!(columns.len(), 30);
+    assert_eq!(columns.datetime()[2], datetime(3));
+    assert_eq!(columns.close()[4], series.data[4].close);
+    assert_eq!(columns.high()[4], series.data[4].high);
+    assert_eq!(columns.volume()[29], 30_000.0);
+    assert_eq!(
+        SeriesColumns::from(&series.data[10..]).open()[0],
+        series.data[10].open
+    );
+    assert!(SeriesColumns::default().is_empty());
+}
+
+#[test]
+fn indicators_on_columns_match_the_enhanced_series() {
+    let columns = series().to_columns();
+    let enhanced = series()
+        .enhance_data()
+        .with_sma(5)
+        .with_ema(5)
+        .with_rsi(14)
+        .with_stochastic(14)
+        .with_macd(12, 26, 9)
+        .with_bb(20, 2)
+        .calculate();
+
+    assert_eq!(columns.sma(5), enhanced.indicators.sma["SMA 5"]);
+    assert_eq!(columns.ema(5), enhanced.indicators.ema["EMA 5"]);
+    assert_eq!(columns.rsi(14), enhanced.indicators.rsi["RSI 14"]);
+    assert_eq!(
+        columns.stochastic(14),
+        enhanced.indicators.stochastic["STO 14"]
+    );
+    assert_eq!(
+        columns.macd(12, 26, 9),
+        enhanced.indicators.macd["MACD (12, 26, 9)"]
+    );
+    assert_eq!(
+        columns.bollinger_bands(20, 2),
+        enhanced.indicators.bb["BB (20, 2)"]
+    );
+
+    let sma = columns.sma(5);
+    assert_eq!(sma[3], 0.0);
+    let expected = columns.close()[..5].iter().sum::<f64>() / 5.0;
+    assert_eq!(sma[4], expected);
+}
+
+#[test]
+fn columns_are_built_from_arrays_of_the_same_length() {
+    let series = series();
+    let from_bars = series.to_columns();
+    let columns = SeriesColumns::new(
+        from_bars.datetime().to_vec(),
+        from_bars.open().to_vec(),
+        from_bars.high().to_vec(),
+        from_bars.low().to_vec(),
+        from_bars.close().to_vec(),
+        from_bars.volume().to_vec(),
+    )
+    .unwrap();
+    assert_eq!(columns, from_bars);
+    assert_eq!(columns.rsi(14), from_bars.rsi(14));
+
+    let short = SeriesColumns::new(
+        from_bars.datetime().to_vec(),
+        from_bars.open().to_vec(),
+        from_bars.high().to_vec(),
+        from_bars.low()[1..].to_vec(),
+        from_bars.close().to_vec(),
+        from_bars.volume().to_vec(),
+    );
+    assert!(matches!(short, Err(MarketError::ParsingError(_))));
+}
@@ -0,0 +1,171 @@
+//! Write and read MarketSeries as CSV, and export the indicators of EnhancedMarketSeries
+
+use chrono::{NaiveDate, NaiveDateTime};
+use market_data::{
+    Adjustment, CsvColumn, CsvOptions, Interval, MarketError, MarketSeries, Series, Tz,
+};
+
+fn datetime(day: u32) -> NaiveDateTime {
+    NaiveDate::from_ymd_opt(2024, 3, day)
+        .unwrap()
+        .and_hms_opt(0, 0, 0)
+        .unwrap()
+}
+
+fn series() -> MarketSeries {
+    MarketSeries {
+        symbol: "AAPL".to_string(),
+        interval: Interval::Daily,
+        timezone: Tz::America__New_York,
+        data: (0..5)
+            .map(|i| Series {
+                datetime: datetime(4 + i),
+                open: 10.0,
+                close: 10.0 + i as f64,
+                high: 15.5,
+                low: 9.25,
+                volume: 1000.0,
+                adj_close: None,
+            })
+            .collect(),
+        adjustment: Adjustment::Raw,
+        actions: Vec::new(),
+    }
+}
+
+#[test]
+fn round_trip_with_the_default_options() {
+    let series = series();
+    let mut csv = Vec::new();
+    series.to_csv(&mut csv).unwrap();
+
+    // written in the timezone of the series, read as UTC
+    let text = String::from_utf8(csv.clone()).unwrap();
+    assert!(text.starts_with(
+        "datetime,open,high,low,close,volume\n2024-03-04 00:00:00,10,15.5,9.25,10,1000\n"
+    ));
+
+    let read = MarketSeries::from_csv(csv.as_slice(), "AAPL", Interval::Daily).unwrap();
+    assert_eq!(read.timezone, Tz::UTC);
+    assert_eq!(read.data, series.data);
+}
+
+#[test]
+fn round_trip_in_utc() {
+    let series = series();
+    let options = CsvOptions::default().with_timezone(Tz::UTC);
+    let mut csv = Vec::new();
+    series.to_csv_with(&mut csv, &options).unwrap();
+
+    // midnight in New York, written in UTC
+    let text = String::from_utf8(csv.clone()).unwrap();
+    assert!(text.starts_with(
+        "datetime,open,high,low,close,volume\n2024-03-04 05:00:00,10,15.5,9.25,10,1000\n"
+    ));
+
+    let read =
+        MarketSeries::from_csv_with(csv.as_slice(), "AAPL", Interval::Daily, &options).unwrap();
+    assert_eq!(read.timezone, Tz::UTC);
+    assert!(read.datetimes().eq(series.datetimes()));
+    assert_eq!(read.to_timezone(Tz::America__New_York).data, series.data);
+}
+
+#[test]
+fn daily_dates_keep_the_timezone_of_the_series() {
+    let mut series = series();
+    series.timezone = Tz::Asia__Tokyo;
+    let options = CsvOptions::default().with_date_format("%Y-%m-%d");
+    let mut csv = Vec::new();
+    series.to_csv_with(&mut csv, &options).unwrap();
+
+    let text = String::from_utf8(csv).unwrap();
+    let dates: Vec<&str> = text
+        .lines()
+        .skip(1)
+        .map(|line| line.split(',').next().unwrap())
+        .collect();
+    assert_eq!(
+        dates,
+        [
+            "2024-03-04",
+            "2024-03-05",
+            "2024-03-06",
+            "2024-03-07",
+            "2024-03-08"
+        ]
+    );
+}
+
+#[test]
+fn round_trip_in_the_timezone_of_the_series() {
+    let series = series();
+    let options = CsvOptions::default().with_timezone(Tz::America__New_York);
+    let mut csv = Vec::new();
+    series.to_csv_with(&mut csv, &options).unwrap();
+
+    let read =
+        MarketSeries::from_csv_with(csv.as_slice(), "AAPL", Interval::Daily, &options).unwrap();
+    assert_eq!(read.timezone, Tz::America__New_York);
+    assert_eq!(read.data, series.data);
+}
+
+#[test]
+fn unsorted_rows_are_sorted() {
+    let csv = "datetime,open,high,low,close,volume\n\
+               2024-03-05 00:00:00,11,11,11,11,100\n\
+               2024-03-04 00:00:00,10,10,10,10,100\n";
+    let series = MarketSeries::from_csv(csv.as_bytes(), "AAPL", Interval::Daily).unwrap();
+    assert_eq!(series.data[0].datetime, datetime(4));
+    assert_eq!(series.data[1].close, 11.0);
+}
+
+#[test]
+fn read_a_spreadsheet_export() {
+    let csv = "Date;Open;High;Low;Close;Adj Close;Volume\n\
+               2024-03-04;10.5;11;10;10.8;10.7;1200\n\
+               2024-03-05;10.8;11.2;10.6;11;;1300\n";
+    let options = CsvOptions::default()
+        .with_delimiter(b';')
+        .with_date_format("%Y-%m-%d")
+        .with_column_name(CsvColumn::Datetime, "Date")
+        .with_column_name(CsvColumn::Open, "Open")
+        .with_column_name(CsvColumn::High, "High")
+        .with_column_name(CsvColumn::Low, "Low")
+        .with_column_name(CsvColumn::Close, "Close")
+        .with_column_name(CsvColumn::Volume, "Volume")
+        .with_column_name(CsvColumn::AdjClose, "Adj Close")
+        .with_timezone(Tz::America__New_York);
+
+    let series =
+        MarketSeries::from_csv_with(csv.as_bytes(), "AAPL", Interval::Daily, &options).unwrap();
+    assert_eq!(series.timezone, Tz::America__New_York);
+    assert_eq!(series.data[0].datetime, datetime(4));
+    assert_eq!(series.data[0].adj_close, Some(10.7));
+    assert_eq!(series.data[1].adj_close, None);
+    assert_eq!(series.data[1].volume, 1300.0);
+
+    let missing = MarketSeries::from_csv(csv.as_bytes(), "AAPL", Interval::Daily);
+    assert!(matches!(missing, Err(MarketError::ParsingError(_))));
+}
+
+#[test]
+fn enhanced_wide_export() {
+    let enhanced = series()
+        .enhance_data()
+        .with_sma(3)
+        .with_macd(2, 3, 2)
+        .calculate();
+    let mut csv = Vec::new();
+    enhanced.to_csv(&mut csv).unwrap();
+
+    let text = String::from_utf8(csv).unwrap();
+    let mut lines = text.lines();
+    assert_eq!(
+        lines.next().unwrap(),
+        "datetime,open,high,low,close,volume,SMA 3,\"MACD (2, 3, 2)/macd\",\"MACD (2, 3, 2)/signal\",\"MACD (2, 3, 2)/histogram\""
+    );
+    let first: Vec<&str> = lines.next().unwrap().split(',').collect();
+    assert_eq!(first[6], "");
+    let third: Vec<&str> = lines.nth(1).unwrap().split(',').collect();
+    assert_eq!(third[6], "11");
+}
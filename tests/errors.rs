@@ -0,0 +1,223 @@
+//! Map the error payloads and status codes of the publishers into structured MarketError variants
+
+use market_data::{
+    AlphaVantage, Finnhub, MarketClient, MarketError, Massive, OutputSize, RetryPolicy, Twelvedata,
+    YahooFin, YahooRange,
+};
+use std::time::Duration;
+use wiremock::{matchers::method, Mock, MockServer, ResponseTemplate};
+
+async fn respond(template: ResponseTemplate) -> MockServer {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .respond_with(template)
+        .mount(&server)
+        .await;
+    server
+}
+
+/// Fetches AAPL from an Alpha Vantage stub answering with the JSON body
+async fn alphavantage_error(body: &str) -> MarketError {
+    let server = respond(ResponseTemplate::new(200).set_body_string(body)).await;
+    let client = MarketClient::new(AlphaVantage::new("demo").with_base_url(server.uri()));
+    client
+        .fetch(client.site.daily_series("AAPL", OutputSize::Compact))
+        .await
+        .unwrap_err()
+}
+
+#[tokio::test]
+async fn alphavantage_rate_limit_note() {
+    let error = alphavantage_error(
+        r#"{"Note": "Thank you for using Alpha Vantage! Our standard API call frequency is 5 calls per minute and 500 calls per day."}"#,
+    )
+    .await;
+    assert!(matches!(
+        error,
+        MarketError::RateLimited {
+            publisher: "alphavantage",
+            retry_after: None
+        }
+    ));
+}
+
+#[tokio::test]
+async fn alphavantage_daily_quota_exhausted() {
+    let error = alphavantage_error(
+        r#"{"Information": "Thank you for using Alpha Vantage! Our standard API rate limit is 25 requests per day."}"#,
+    )
+    .await;
+    assert!(matches!(error, MarketError::QuotaExhausted { limit: 25 }));
+}
+
+#[tokio::test]
+async fn alphavantage_invalid_api_key() {
+    let error = alphavantage_error(
+        r#"{"Information": "the parameter apikey is invalid or missing. Please claim your free API key on (https://www.alphavantage.co/support/#api-key)."}"#,
+    )
+    .await;
+    assert!(matches!(
+        error,
+        MarketError::Unauthorized {
+            publisher: "alphavantage",
+            ..
+        }
+    ));
+}
+
+#[tokio::test]
+async fn alphavantage_invalid_symbol() {
+    let server = respond(ResponseTemplate::new(200).set_body_string(
+        r#"{"Error Message": "Invalid API call. Please retry or visit the documentation (https://www.alphavantage.co/documentation/) for TIME_SERIES_DAILY."}"#,
+    ))
+    .await;
+
+    let client = MarketClient::new(AlphaVantage::new("demo").with_base_url(server.uri()));
+    let result = client
+        .fetch(client.site.daily_series("NOPE", OutputSize::Compact))
+        .await;
+    assert!(matches!(
+        result,
+        Err(MarketError::SymbolNotFound { symbol, .. }) if symbol == "NOPE"
+    ));
+}
+
+#[tokio::test]
+async fn finnhub_rate_limit_with_retry_after() {
+    let server = respond(
+        ResponseTemplate::new(429)
+            .insert_header("Retry-After", "7")
+            .set_body_string(r#"{"error": "API limit reached. Please try again later."}"#),
+    )
+    .await;
+
+    let client = MarketClient::new(Finnhub::new("demo").with_base_url(server.uri()));
+    let result = client.fetch(client.site.quote("AAPL")).await;
+    let error = result.unwrap_err();
+    assert_eq!(error.publisher(), Some("finnhub"));
+    assert_eq!(error.retry_after(), Some(Duration::from_secs(7)));
+}
+
+#[tokio::test]
+async fn retry_after_above_the_max_delay_is_not_retried() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .respond_with(ResponseTemplate::new(429).insert_header("Retry-After", "60"))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let client = MarketClient::new(Finnhub::new("demo").with_base_url(server.uri())).with_retry(
+        RetryPolicy::default()
+            .with_max_attempts(3)
+            .with_max_delay(Duration::from_secs(1)),
+    );
+    let result = client.fetch(client.site.quote("AAPL")).await;
+    assert!(matches!(
+        result,
+        Err(MarketError::RateLimited {
+            retry_after: Some(retry_after),
+            ..
+        }) if retry_after == Duration::from_secs(60)
+    ));
+}
+
+#[tokio::test]
+async fn finnhub_no_data() {
+    let server = respond(ResponseTemplate::new(200).set_body_string(r#"{"s": "no_data"}"#)).await;
+
+    let client = MarketClient::new(Finnhub::new("demo").with_base_url(server.uri()));
+    let result = client
+        .fetch(client.site.daily_series("AAPL", 1709510400, 1709683200))
+        .await;
+    assert!(matches!(
+        result,
+        Err(MarketError::NoData {
+            publisher: "finnhub"
+        })
+    ));
+}
+
+#[tokio::test]
+async fn twelvedata_invalid_key() {
+    let server = respond(ResponseTemplate::new(200).set_body_string(
+        r#"{"code": 401, "message": "**apikey** parameter is incorrect or not specified.", "status": "error"}"#,
+    ))
+    .await;
+
+    let client = MarketClient::new(Twelvedata::new("demo").with_base_url(server.uri()));
+    let result = client.fetch(client.site.daily_series("AAPL", 3)).await;
+    assert!(matches!(
+        result,
+        Err(MarketError::Unauthorized {
+            publisher: "twelvedata",
+            ..
+        })
+    ));
+}
+
+#[tokio::test]
+async fn massive_not_authorized() {
+    let server = respond(ResponseTemplate::new(403).set_body_string(
+        r#"{"status": "NOT_AUTHORIZED", "request_id": "1", "message": "You are not entitled to this data."}"#,
+    ))
+    .await;
+
+    let client = MarketClient::new(Massive::new("demo").with_base_url(server.uri()));
+    let result = client
+        .fetch(
+            client
+                .site
+                .daily_series("AAPL", "2024-03-04", "2024-03-06", 5000),
+        )
+        .await;
+    assert!(matches!(
+        result,
+        Err(MarketError::Unauthorized {
+            publisher: "massive",
+            ..
+        })
+    ));
+}
+
+#[tokio::test]
+async fn yahoo_finance_symbol_not_found() {
+    let server = respond(ResponseTemplate::new(404).set_body_string(
+        r#"{"chart": {"result": null, "error": {"code": "Not Found", "description": "No data found, symbol may be delisted"}}}"#,
+    ))
+    .await;
+
+    let client = MarketClient::new(YahooFin::new().with_base_url(server.uri()));
+    let result = client
+        .fetch(client.site.daily_series("NOPE", YahooRange::Day5))
+        .await;
+    assert!(matches!(
+        result,
+        Err(MarketError::SymbolNotFound {
+            publisher: "yahoo_finance",
+            ..
+        })
+    ));
+}
+
+#[tokio::test]
+async fn server_error_after_retries() {
+    let server = respond(ResponseTemplate::new(503).set_body_string("unavailable")).await;
+
+    let site = YahooFin::new().with_base_url(server.uri());
+    let client = MarketClient::new(site).with_retry(
+        RetryPolicy::default()
+            .with_max_attempts(2)
+            .with_base_delay(Duration::from_millis(1)),
+    );
+    let result = client
+        .fetch(client.site.daily_series("AAPL", YahooRange::Day5))
+        .await;
+    match result {
+        Err(MarketError::RetriesExhausted { attempts, source }) => {
+            assert_eq!(attempts, 2);
+            assert!(matches!(*source, MarketError::Http { status: 503, .. }));
+        }
+        other => panic!("unexpected result: {:?}", other),
+    }
+}
@@ -0,0 +1,130 @@
+//! FallbackClient and fetch_series against local stubs: the fallback order and the date range filtering
+
+use chrono::{NaiveDate, NaiveDateTime};
+use market_data::{
+    FallbackClient, Finnhub, Interval, IntervalUnit, MarketClient, MarketError, SeriesRequest,
+    Twelvedata, Tz, YahooFin,
+};
+use wiremock::{
+    matchers::{method, query_param},
+    Mock, MockServer, ResponseTemplate,
+};
+
+async fn respond(response: ResponseTemplate, expected: u64) -> MockServer {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .respond_with(response)
+        .expect(expected)
+        .mount(&server)
+        .await;
+    server
+}
+
+fn datetime(s: &str) -> NaiveDateTime {
+    NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M:%S").unwrap()
+}
+
+#[tokio::test]
+async fn the_first_publisher_that_succeeds_wins() {
+    let finnhub = respond(ResponseTemplate::new(500), 1).await;
+    let twelvedata = respond(
+        ResponseTemplate::new(200).set_body_string(include_str!("fixtures/twelvedata_daily.json")),
+        1,
+    )
+    .await;
+    let yahoo = respond(ResponseTemplate::new(500), 0).await;
+
+    let client = FallbackClient::new()
+        .with(MarketClient::new(
+            Finnhub::new("demo").with_base_url(finnhub.uri()),
+        ))
+        .with(MarketClient::new(
+            Twelvedata::new("demo").with_base_url(twelvedata.uri()),
+        ))
+        .with(MarketClient::new(
+            YahooFin::new().with_base_url(yahoo.uri()),
+        ));
+    assert_eq!(
+        client.publishers(),
+        ["finnhub", "twelvedata", "yahoo_finance"]
+    );
+
+    let request = SeriesRequest::new("AAPL", Interval::Daily)
+        .with_start(NaiveDate::from_ymd_opt(2024, 3, 1).unwrap())
+        .with_end(NaiveDate::from_ymd_opt(2024, 3, 8).unwrap());
+    let served = client.fetch(&request).await.unwrap();
+
+    assert_eq!(served.publisher, "twelvedata");
+    assert_eq!(served.series.data.len(), 3);
+    assert_eq!(served.skipped.len(), 1);
+    assert_eq!(served.skipped[0].0, "finnhub");
+    assert!(matches!(
+        served.skipped[0].1,
+        MarketError::Http { status: 500, .. }
+    ));
+}
+
+#[tokio::test]
+async fn unsupported_interval_is_skipped_without_a_request() {
+    let finnhub = respond(ResponseTemplate::new(500), 0).await;
+    let twelvedata = respond(ResponseTemplate::new(429), 1).await;
+
+    let client = FallbackClient::new()
+        .with(MarketClient::new(
+            Finnhub::new("demo").with_base_url(finnhub.uri()),
+        ))
+        .with(MarketClient::new(
+            Twelvedata::new("demo").with_base_url(twelvedata.uri()),
+        ));
+    let request =
+        SeriesRequest::new("AAPL", Interval::new(45, IntervalUnit::Minute).unwrap()).with_bars(10);
+
+    match client.fetch(&request).await {
+        Err(MarketError::AllPublishersFailed(errors)) => {
+            assert_eq!(errors.len(), 2);
+            assert_eq!(errors[0].0, "finnhub");
+            assert!(matches!(errors[0].1, MarketError::UnsuportedInterval(_)));
+            assert_eq!(errors[1].0, "twelvedata");
+            assert!(matches!(errors[1].1, MarketError::RateLimited { .. }));
+        }
+        other => panic!("unexpected result: {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn bounds_are_sent_in_utc_and_the_bars_filtered() {
+    let server = MockServer::start().await;
+    // the API may return bars on the edges of the range, 13:30 is before the start
+    Mock::given(method("GET"))
+        .and(query_param("timezone", "UTC"))
+        .and(query_param("start_date", "2024-03-06 14:30:00"))
+        .and(query_param("end_date", "2024-03-06 16:30:00"))
+        .respond_with(ResponseTemplate::new(200).set_body_string(
+            r#"{"meta":{"symbol":"AAPL","interval":"1h","exchange_timezone":"America/New_York"},"values":[
+                {"datetime":"2024-03-06 16:30:00","open":"170.1","high":"170.5","low":"169.9","close":"170.2","volume":"1000"},
+                {"datetime":"2024-03-06 15:30:00","open":"170.0","high":"170.3","low":"169.8","close":"170.1","volume":"1000"},
+                {"datetime":"2024-03-06 14:30:00","open":"171.0","high":"171.2","low":"169.9","close":"170.0","volume":"1000"},
+                {"datetime":"2024-03-06 13:30:00","open":"171.1","high":"171.3","low":"170.8","close":"171.0","volume":"1000"}
+            ],"status":"ok"}"#,
+        ))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let client = MarketClient::new(Twelvedata::new("demo").with_base_url(server.uri()));
+    let request = SeriesRequest::new("AAPL", Interval::Hour1)
+        .with_start(datetime("2024-03-06 14:30:00"))
+        .with_end(datetime("2024-03-06 16:30:00"));
+    let series = client.fetch_series(&request).await.unwrap();
+
+    assert_eq!(series.timezone, Tz::UTC);
+    let datetimes: Vec<NaiveDateTime> = series.data.iter().map(|s| s.datetime).collect();
+    assert_eq!(
+        datetimes,
+        [
+            datetime("2024-03-06 14:30:00"),
+            datetime("2024-03-06 15:30:00"),
+            datetime("2024-03-06 16:30:00"),
+        ]
+    );
+}
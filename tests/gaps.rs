@@ -0,0 +1,112 @@
+//! Detect the bars missing from a MarketSeries
+
+use chrono::{NaiveDate, NaiveDateTime};
+use market_data::{Adjustment, Gap, Interval, MarketSeries, Series, TradingCalendar, Tz};
+
+fn datetime(s: &str) -> NaiveDateTime {
+    NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M").unwrap()
+}
+
+fn series(interval: Interval, datetimes: &[&str]) -> MarketSeries {
+    MarketSeries {
+        symbol: "AAPL".to_string(),
+        interval,
+        timezone: Tz::America__New_York,
+        data: datetimes
+            .iter()
+            .map(|s| Series {
+                datetime: datetime(s),
+                open: 1.0,
+                close: 1.0,
+                high: 1.0,
+                low: 1.0,
+                volume: 100.0,
+                adj_close: None,
+            })
+            .collect(),
+        adjustment: Adjustment::Raw,
+        actions: Vec::new(),
+    }
+}
+
+#[test]
+fn intraday_gaps_within_the_session() {
+    let series = series(
+        Interval::Hour1,
+        &[
+            "2024-03-07 14:30",
+            "2024-03-07 15:30",
+            "2024-03-08 09:30",
+            "2024-03-08 12:30",
+            "2024-03-11 09:30",
+        ],
+    );
+    let calendar = TradingCalendar::us_equities();
+
+    assert_eq!(
+        series.missing_bars(&calendar),
+        vec![
+            datetime("2024-03-08 10:30"),
+            datetime("2024-03-08 11:30"),
+            datetime("2024-03-08 13:30"),
+            datetime("2024-03-08 14:30"),
+            datetime("2024-03-08 15:30"),
+        ]
+    );
+    assert_eq!(
+        series.gaps(&calendar),
+        vec![
+            Gap {
+                start: datetime("2024-03-08 10:30"),
+                end: datetime("2024-03-08 11:30"),
+                bars: 2,
+            },
+            Gap {
+                start: datetime("2024-03-08 13:30"),
+                end: datetime("2024-03-08 15:30"),
+                bars: 3,
+            },
+        ]
+    );
+}
+
+#[test]
+fn daily_gaps_skip_weekends_and_holidays() {
+    let series = series(
+        Interval::Daily,
+        &["2024-03-27 00:00", "2024-04-02 00:00", "2024-04-03 00:00"],
+    );
+
+    let without_calendar = series.missing_bars(&TradingCalendar::default());
+    assert_eq!(without_calendar.len(), 5);
+
+    let good_friday = NaiveDate::from_ymd_opt(2024, 3, 29).unwrap();
+    let calendar = TradingCalendar::weekdays().with_holidays([good_friday]);
+    assert_eq!(
+        series.missing_bars(&calendar),
+        vec![datetime("2024-03-28 00:00"), datetime("2024-04-01 00:00")]
+    );
+}
+
+#[test]
+fn daily_and_weekly_bars_are_compared_by_date() {
+    // midnight in New York, stamped in UTC: 04:00 before the end of DST, 05:00 after
+    let mut daily = series(
+        Interval::Daily,
+        &["2024-11-01 04:00", "2024-11-04 05:00", "2024-11-05 05:00"],
+    );
+    daily.timezone = Tz::UTC;
+    assert!(daily
+        .missing_bars(&TradingCalendar::us_equities())
+        .is_empty());
+
+    // the week of Martin Luther King Jr. Day starts on Tuesday, the next week is missing
+    let weekly = series(
+        Interval::Weekly,
+        &["2024-01-08 00:00", "2024-01-16 00:00", "2024-01-29 00:00"],
+    );
+    assert_eq!(
+        weekly.missing_bars(&TradingCalendar::us_equities()),
+        vec![datetime("2024-01-23 00:00")]
+    );
+}
@@ -0,0 +1,121 @@
+//! Parse the interval notation of every publisher and convert it to a duration
+
+use chrono::{DateTime, Duration, Utc};
+use market_data::{Interval, IntervalUnit, MarketError, SeriesRequest};
+
+fn interval(multiplier: u32, unit: IntervalUnit) -> Interval {
+    Interval::new(multiplier, unit).unwrap()
+}
+
+#[test]
+fn parse_publisher_notations() {
+    let cases = [
+        // AlphaVantage
+        ("5min", Interval::Min5),
+        ("60min", Interval::Hour1),
+        ("daily", Interval::Daily),
+        ("monthly", Interval::Monthly),
+        // Twelvedata
+        ("45min", interval(45, IntervalUnit::Minute)),
+        ("8h", interval(8, IntervalUnit::Hour)),
+        ("1day", Interval::Daily),
+        ("1week", Interval::Weekly),
+        ("1month", Interval::Monthly),
+        // Yahoo Finance
+        ("2m", interval(2, IntervalUnit::Minute)),
+        ("90m", interval(90, IntervalUnit::Minute)),
+        ("1d", Interval::Daily),
+        ("5d", interval(5, IntervalUnit::Day)),
+        ("1wk", Interval::Weekly),
+        ("1mo", Interval::Monthly),
+        ("3mo", interval(3, IntervalUnit::Month)),
+        // Finnhub
+        ("1", Interval::Min1),
+        ("60", Interval::Hour1),
+        ("D", Interval::Daily),
+        ("W", Interval::Weekly),
+        ("M", Interval::Monthly),
+        // Massive
+        ("15 minute", Interval::Min15),
+        ("1 quarter", Interval::Quarterly),
+        ("2 year", interval(2, IntervalUnit::Year)),
+    ];
+    for (notation, expected) in cases {
+        let parsed: Interval = notation.parse().unwrap();
+        assert_eq!(parsed, expected, "{}", notation);
+    }
+}
+
+#[test]
+fn unknown_notations_are_rejected() {
+    for notation in ["", "0min", "1fortnight", "min5", "1.5h", "daily!"] {
+        let result = notation.parse::<Interval>();
+        assert!(
+            matches!(result, Err(MarketError::InvalidInterval(_))),
+            "{}",
+            notation
+        );
+    }
+    assert!(Interval::new(0, IntervalUnit::Day).is_err());
+}
+
+#[test]
+fn oversized_multipliers_are_rejected() {
+    // the months or the days don't fit
+    for notation in [
+        "4294967295 years",
+        "1431655766 quarters",
+        "4294967295 months",
+    ] {
+        let result = notation.parse::<Interval>();
+        assert!(
+            matches!(result, Err(MarketError::InvalidInterval(_))),
+            "{}",
+            notation
+        );
+    }
+    assert!(Interval::new(u32::MAX / 12, IntervalUnit::Year).is_err());
+
+    // the largest ones are still usable
+    let days: Interval = "4294967295 days".parse().unwrap();
+    let request = SeriesRequest::new("AAPL", days).with_bars(usize::MAX);
+    assert_eq!(request.range().0, DateTime::<Utc>::MIN_UTC);
+    assert!(request.range().1 > request.range().0);
+}
+
+#[test]
+fn display_round_trip() {
+    let intervals = [
+        Interval::Min1,
+        Interval::Hour4,
+        Interval::Daily,
+        Interval::Yearly,
+        interval(45, IntervalUnit::Minute),
+        interval(3, IntervalUnit::Week),
+    ];
+    for interval in intervals {
+        assert_eq!(interval.to_string().parse::<Interval>().unwrap(), interval);
+
+        let json = serde_json::to_string(&interval).unwrap();
+        assert_eq!(serde_json::from_str::<Interval>(&json).unwrap(), interval);
+    }
+    assert_eq!(Interval::Min5.to_string(), "5 minutes");
+    assert_eq!(Interval::Daily.to_string(), "Daily");
+
+    // the series serialized before the intervals were generalised
+    let legacy: Interval = serde_json::from_str("\"Min5\"").unwrap();
+    assert_eq!(legacy, Interval::Min5);
+}
+
+#[test]
+fn duration() {
+    assert_eq!(Interval::Min15.to_duration(), Some(Duration::minutes(15)));
+    assert_eq!(
+        interval(90, IntervalUnit::Minute).to_duration(),
+        Some(Duration::minutes(90))
+    );
+    assert_eq!(Interval::Weekly.to_duration(), Some(Duration::weeks(1)));
+    assert_eq!(Interval::Quarterly.to_duration(), None);
+    assert!(Interval::Hour2.is_intraday());
+    assert!(!Interval::Daily.is_intraday());
+}
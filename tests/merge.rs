@@ -0,0 +1,139 @@
+//! Merge the bars of MarketSeries fetched in chunks or from different publishers
+
+use chrono::{NaiveDate, NaiveDateTime};
+use market_data::{
+    Adjustment, ConflictPolicy, CorporateAction, Interval, MarketError, MarketSeries, Series, Tz,
+};
+
+fn datetime(day: u32, hour: u32) -> NaiveDateTime {
+    NaiveDate::from_ymd_opt(2024, 3, day)
+        .unwrap()
+        .and_hms_opt(hour, 0, 0)
+        .unwrap()
+}
+
+fn bar(datetime: NaiveDateTime, close: f64) -> Series {
+    Series {
+        datetime,
+        open: close,
+        close,
+        high: close,
+        low: close,
+        volume: 100.0,
+        adj_close: None,
+    }
+}
+
+fn series(timezone: Tz, data: Vec<Series>) -> MarketSeries {
+    MarketSeries {
+        symbol: "AAPL".to_string(),
+        interval: Interval::Hour1,
+        timezone,
+        data,
+        adjustment: Adjustment::Raw,
+        actions: Vec::new(),
+    }
+}
+
+#[test]
+fn merge_overlapping_chunks() {
+    let older = series(
+        Tz::UTC,
+        vec![
+            bar(datetime(5, 14), 1.0),
+            bar(datetime(5, 16), 3.0),
+            bar(datetime(5, 15), 2.0),
+        ],
+    );
+    let newer = series(
+        Tz::UTC,
+        vec![bar(datetime(5, 16), 3.5), bar(datetime(5, 17), 4.0)],
+    );
+
+    let right = older
+        .clone()
+        .merge(newer.clone(), ConflictPolicy::PreferRight)
+        .unwrap();
+    let closes: Vec<f64> = right.data.iter().map(|s| s.close).collect();
+    assert_eq!(closes, vec![1.0, 2.0, 3.5, 4.0]);
+
+    let left = older
+        .clone()
+        .merge(newer.clone(), ConflictPolicy::PreferLeft)
+        .unwrap();
+    assert_eq!(left.data[2].close, 3.0);
+
+    let result = older.merge(newer, ConflictPolicy::Error);
+    assert!(matches!(result, Err(MarketError::MergeError(_))));
+}
+
+#[test]
+fn merge_converts_the_timezone_and_keeps_the_actions() {
+    let mut utc = series(Tz::UTC, vec![bar(datetime(5, 14), 1.0)]);
+    utc.actions = vec![CorporateAction::Dividend {
+        date: NaiveDate::from_ymd_opt(2024, 3, 5).unwrap(),
+        amount: 0.25,
+    }];
+    let new_york = series(
+        Tz::America__New_York,
+        vec![bar(datetime(5, 9), 1.0), bar(datetime(5, 10), 2.0)],
+    );
+
+    let merged = new_york.merge(utc.clone(), ConflictPolicy::Error).unwrap();
+    assert_eq!(merged.timezone, Tz::America__New_York);
+    assert_eq!(merged.data.len(), 2);
+    assert_eq!(merged.actions, utc.actions);
+}
+
+#[test]
+fn merge_rejects_another_symbol_or_interval() {
+    let aapl = series(Tz::UTC, vec![bar(datetime(5, 14), 1.0)]);
+    let mut msft = aapl.clone();
+    msft.symbol = "MSFT".to_string();
+    assert!(aapl
+        .clone()
+        .merge(msft, ConflictPolicy::PreferRight)
+        .is_err());
+
+    let mut daily = aapl.clone();
+    daily.interval = Interval::Daily;
+    assert!(aapl.merge(daily, ConflictPolicy::PreferRight).is_err());
+}
+
+#[test]
+fn merge_matches_the_daily_bars_by_session() {
+    let daily = |timezone: Tz, data: Vec<Series>| MarketSeries {
+        interval: Interval::Daily,
+        ..series(timezone, data)
+    };
+    let new_york = daily(
+        Tz::America__New_York,
+        vec![bar(datetime(4, 0), 1.0), bar(datetime(5, 0), 2.0)],
+    );
+    // the same sessions stamped at midnight UTC, and at the close
+    let utc = daily(
+        Tz::UTC,
+        vec![bar(datetime(5, 0), 2.5), bar(datetime(6, 0), 3.0)],
+    );
+    let at_close = daily(
+        Tz::America__New_York,
+        vec![bar(datetime(6, 16), 3.5), bar(datetime(7, 16), 4.0)],
+    );
+
+    let merged = new_york
+        .merge(utc, ConflictPolicy::PreferRight)
+        .unwrap()
+        .merge(at_close, ConflictPolicy::PreferLeft)
+        .unwrap();
+    let bars: Vec<(NaiveDateTime, f64)> =
+        merged.data.iter().map(|s| (s.datetime, s.close)).collect();
+    assert_eq!(
+        bars,
+        [
+            (datetime(4, 0), 1.0),
+            (datetime(5, 0), 2.5),
+            (datetime(6, 0), 3.0),
+            (datetime(7, 16), 4.0),
+        ]
+    );
+}
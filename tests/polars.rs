@@ -0,0 +1,90 @@
+//! Convert MarketSeries to Polars data frames, run with `--features polars`
+#![cfg(feature = "polars")]
+
+use chrono::{NaiveDate, NaiveDateTime};
+use market_data::{Adjustment, Interval, MarketSeries, Series, Tz};
+use polars::prelude::{Column, DataFrame, DataType, IntoColumn, NamedFrom, TimeUnit};
+
+fn datetime(day: u32) -> NaiveDateTime {
+    NaiveDate::from_ymd_opt(2024, 3, day)
+        .unwrap()
+        .and_hms_opt(0, 0, 0)
+        .unwrap()
+}
+
+fn series() -> MarketSeries {
+    MarketSeries {
+        symbol: "AAPL".to_string(),
+        interval: Interval::Daily,
+        timezone: Tz::America__New_York,
+        data: (0..6)
+            .map(|i| Series {
+                datetime: datetime(4 + i),
+                open: 10.0,
+                close: 10.0 + i as f64,
+                high: 16.0,
+                low: 9.0,
+                volume: 1000.0,
+                adj_close: None,
+            })
+            .collect(),
+        adjustment: Adjustment::Raw,
+        actions: Vec::new(),
+    }
+}
+
+#[test]
+fn dataframe_round_trip() {
+    let series = series();
+    let frame = series.to_dataframe().unwrap();
+    assert_eq!(frame.height(), 6);
+    assert_eq!(
+        frame.column("datetime").unwrap().dtype().to_string(),
+        "datetime[ms, America/New_York]"
+    );
+
+    let read = MarketSeries::from_dataframe(&frame, "AAPL", Interval::Daily).unwrap();
+    assert_eq!(read.timezone, Tz::America__New_York);
+    assert_eq!(read.data, series.data);
+}
+
+#[test]
+fn enhanced_frame_has_null_warm_up_rows() {
+    let frame = series()
+        .enhance_data()
+        .with_rsi(3)
+        .with_macd(2, 3, 2)
+        .calculate()
+        .to_dataframe()
+        .unwrap();
+
+    let rsi = frame.column("RSI 3").unwrap();
+    assert_eq!(rsi.null_count(), 3);
+    assert!(frame.column("MACD (2, 3, 2)/histogram").is_ok());
+}
+
+#[test]
+fn indicators_on_a_frame_loaded_elsewhere() {
+    let millis: Vec<i64> = (0..4).map(|day| 1709510400000 + day * 86_400_000).collect();
+    let datetimes = polars::prelude::Series::new("datetime".into(), millis)
+        .into_datetime(TimeUnit::Milliseconds, None)
+        .into_column();
+    let frame = DataFrame::new_infer_height(vec![
+        datetimes,
+        Column::new("open".into(), [1.0, 2.0, 3.0, 4.0]),
+        Column::new("high".into(), [1.0, 2.0, 3.0, 4.0]),
+        Column::new("low".into(), [1.0, 2.0, 3.0, 4.0]),
+        Column::new("close".into(), [1.0, 2.0, 3.0, 4.0]),
+        Column::new("volume".into(), [100i64, 200, 300, 400]),
+    ])
+    .unwrap();
+    assert_eq!(frame.column("volume").unwrap().dtype(), &DataType::Int64);
+
+    let series = MarketSeries::from_dataframe(&frame, "AAPL", Interval::Daily).unwrap();
+    assert_eq!(series.timezone, Tz::UTC);
+    assert_eq!(series.data[0].datetime, datetime(4));
+    assert_eq!(series.data[3].volume, 400.0);
+
+    let enhanced = series.enhance_data().with_sma(2).calculate();
+    assert_eq!(enhanced.indicators.sma["SMA 2"][3], 3.5);
+}
@@ -0,0 +1,203 @@
+//! Run MarketClient::fetch for every publisher against a local HTTP stub serving canned responses
+
+use chrono::NaiveDate;
+use market_data::{
+    AlphaVantage, Finnhub, Interval, MarketClient, MarketSeries, Massive, OutputSize, Twelvedata,
+    YahooFin, YahooRange,
+};
+use wiremock::{
+    matchers::{method, path, query_param},
+    Mock, MockServer, ResponseTemplate,
+};
+
+async fn serve(server: &MockServer, endpoint: &str, body: &str) {
+    Mock::given(method("GET"))
+        .and(path(endpoint))
+        .and(query_param("symbol", "AAPL"))
+        .respond_with(ResponseTemplate::new(200).set_body_string(body))
+        .expect(1)
+        .mount(server)
+        .await;
+}
+
+fn assert_daily_aapl(series: &MarketSeries) {
+    assert_eq!(series.symbol, "AAPL");
+    assert!(matches!(series.interval, Interval::Daily));
+    assert_eq!(series.data.len(), 3);
+
+    let dates: Vec<NaiveDate> = series.data.iter().map(|s| s.datetime.date()).collect();
+    assert_eq!(
+        dates,
+        vec![
+            NaiveDate::from_ymd_opt(2024, 3, 4).unwrap(),
+            NaiveDate::from_ymd_opt(2024, 3, 5).unwrap(),
+            NaiveDate::from_ymd_opt(2024, 3, 6).unwrap(),
+        ]
+    );
+
+    let first = &series.data[0];
+    assert!((first.open - 176.15).abs() < 0.01);
+    assert!((first.high - 176.90).abs() < 0.01);
+    assert!((first.low - 173.79).abs() < 0.01);
+    assert!((first.close - 175.10).abs() < 0.01);
+    assert!((first.volume - 81_510_100.0).abs() < 10.0);
+}
+
+#[tokio::test]
+async fn alphavantage_daily() {
+    let server = MockServer::start().await;
+    serve(
+        &server,
+        "/query",
+        include_str!("fixtures/alphavantage_daily.json"),
+    )
+    .await;
+
+    let client = MarketClient::new(AlphaVantage::new("demo").with_base_url(server.uri()));
+    let request = client.site.daily_series("AAPL", OutputSize::Compact);
+    let series = client.fetch(request).await.unwrap();
+
+    assert_daily_aapl(&series);
+}
+
+#[tokio::test]
+async fn finnhub_daily() {
+    let server = MockServer::start().await;
+    serve(
+        &server,
+        "/stock/candle",
+        include_str!("fixtures/finnhub_daily.json"),
+    )
+    .await;
+
+    let client = MarketClient::new(Finnhub::new("demo").with_base_url(server.uri()));
+    let request = client.site.daily_series("AAPL", 1709510400, 1709683200);
+    let series = client.fetch(request).await.unwrap();
+
+    assert_daily_aapl(&series);
+}
+
+#[tokio::test]
+async fn massive_daily() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/AAPL/range/1/day/2024-03-04/2024-03-06"))
+        .respond_with(
+            ResponseTemplate::new(200).set_body_string(include_str!("fixtures/massive_daily.json")),
+        )
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let client = MarketClient::new(Massive::new("demo").with_base_url(server.uri()));
+    let request = client
+        .site
+        .daily_series("AAPL", "2024-03-04", "2024-03-06", 5000);
+    let series = client.fetch(request).await.unwrap();
+
+    assert_daily_aapl(&series);
+}
+
+#[tokio::test]
+async fn twelvedata_daily() {
+    let server = MockServer::start().await;
+    serve(
+        &server,
+        "/time_series",
+        include_str!("fixtures/twelvedata_daily.json"),
+    )
+    .await;
+
+    let client = MarketClient::new(Twelvedata::new("demo").with_base_url(server.uri()));
+    let request = client.site.daily_series("AAPL", 3);
+    let series = client.fetch(request).await.unwrap();
+
+    assert_daily_aapl(&series);
+}
+
+#[tokio::test]
+async fn yahoo_finance_daily() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/AAPL"))
+        .and(query_param("interval", "1d"))
+        .and(query_param("range", "5d"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_string(include_str!("fixtures/yahoo_finance_daily.json")),
+        )
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let client = MarketClient::new(YahooFin::new().with_base_url(server.uri()));
+    let request = client.site.daily_series("AAPL", YahooRange::Day5);
+    let series = client.fetch(request).await.unwrap();
+
+    assert_daily_aapl(&series);
+}
+
+#[tokio::test]
+async fn yahoo_finance_monthly_interval() {
+    let server = MockServer::start().await;
+    // "1m" would be 1 minute for Yahoo Finance
+    Mock::given(method("GET"))
+        .and(path("/AAPL"))
+        .and(query_param("interval", "1mo"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_string(include_str!("fixtures/yahoo_finance_daily.json")),
+        )
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let client = MarketClient::new(YahooFin::new().with_base_url(server.uri()));
+    let request = client.site.monthly_series("AAPL", YahooRange::Year1);
+    let series = client.fetch(request).await.unwrap();
+
+    assert!(matches!(series.interval, Interval::Monthly));
+}
+
+#[tokio::test]
+async fn prices_keep_their_full_precision() {
+    let server = MockServer::start().await;
+    // parsed from a string by Twelvedata, from a JSON number by Finnhub
+    serve(
+        &server,
+        "/time_series",
+        r#"{"meta":{"symbol":"AAPL","interval":"1day"},"values":[{"datetime":"2024-03-06","open":"12345.6789","high":"12345.6789","low":"0.0001","close":"98765.4321","volume":"100"}],"status":"ok"}"#,
+    )
+    .await;
+    Mock::given(method("GET"))
+        .and(path("/stock/candle"))
+        .respond_with(ResponseTemplate::new(200).set_body_string(
+            r#"{"c":[98765.4321],"h":[12345.6789],"l":[0.0001],"o":[12345.6789],"s":"ok","t":[1709683200],"v":[100]}"#,
+        ))
+        .mount(&server)
+        .await;
+
+    let twelvedata = MarketClient::new(Twelvedata::new("demo").with_base_url(server.uri()));
+    let finnhub = MarketClient::new(Finnhub::new("demo").with_base_url(server.uri()));
+    let series = [
+        twelvedata
+            .fetch(twelvedata.site.daily_series("AAPL", 1))
+            .await
+            .unwrap(),
+        finnhub
+            .fetch(finnhub.site.daily_series("AAPL", 1709683200, 1709683200))
+            .await
+            .unwrap(),
+    ];
+
+    for series in series {
+        let bar = &series.data[0];
+        assert_eq!(bar.open, 12345.6789);
+        assert_eq!(bar.low, 0.0001);
+        assert_eq!(bar.close, 98765.4321);
+        // and through the JSON serialization of the series
+        let json = serde_json::to_string(&series).unwrap();
+        let read: MarketSeries = serde_json::from_str(&json).unwrap();
+        assert_eq!(read.data, series.data);
+    }
+}
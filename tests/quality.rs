@@ -0,0 +1,242 @@
+//! Validate and clean the bars of a MarketSeries
+
+use chrono::{NaiveDate, NaiveDateTime};
+use market_data::{
+    Adjustment, Anomaly, CleanOptions, Interval, InvalidBars, MarketSeries, Series,
+    TradingCalendar, Tz,
+};
+
+fn datetime(s: &str) -> NaiveDateTime {
+    NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M").unwrap()
+}
+
+fn bar(s: &str, open: f64, high: f64, low: f64, close: f64, volume: f64) -> Series {
+    Series {
+        datetime: datetime(s),
+        open,
+        close,
+        high,
+        low,
+        volume,
+        adj_close: None,
+    }
+}
+
+fn series(interval: Interval, data: Vec<Series>) -> MarketSeries {
+    MarketSeries {
+        symbol: "AAPL".to_string(),
+        interval,
+        timezone: Tz::America__New_York,
+        data,
+        adjustment: Adjustment::Raw,
+        actions: Vec::new(),
+    }
+}
+
+fn messy() -> MarketSeries {
+    series(
+        Interval::Min5,
+        vec![
+            bar("2024-03-06 09:30", 10.0, 11.0, 9.0, 10.5, 100.0),
+            bar("2024-03-06 09:40", 10.5, 10.0, 11.0, 10.5, 100.0),
+            bar("2024-03-06 09:35", 10.5, 11.0, 10.0, 12.0, 100.0),
+            bar("2024-03-06 09:40", 10.5, 11.0, 10.0, 10.8, 200.0),
+            bar("2024-03-06 09:45", 10.8, 10.8, 10.8, 10.8, 0.0),
+            bar("2024-03-06 09:50", f64::NAN, 11.0, 10.0, 10.5, 100.0),
+        ],
+    )
+}
+
+#[test]
+fn validate_reports_every_anomaly_with_its_row() {
+    let report = messy().validate();
+    assert!(!report.is_clean());
+    assert_eq!(
+        report.anomalies,
+        vec![
+            Anomaly::HighBelowLow {
+                index: 1,
+                high: 10.0,
+                low: 11.0
+            },
+            Anomaly::OutOfRange {
+                index: 1,
+                field: "open",
+                value: 10.5
+            },
+            Anomaly::OutOfRange {
+                index: 1,
+                field: "close",
+                value: 10.5
+            },
+            Anomaly::OutOfOrder {
+                index: 2,
+                datetime: datetime("2024-03-06 09:35")
+            },
+            Anomaly::OutOfRange {
+                index: 2,
+                field: "close",
+                value: 12.0
+            },
+            Anomaly::Duplicate {
+                index: 3,
+                first: 1,
+                datetime: datetime("2024-03-06 09:40")
+            },
+            Anomaly::ZeroVolume { index: 4 },
+            Anomaly::InvalidValue { index: 5 },
+        ]
+    );
+    assert_eq!(report.invalid_rows(), vec![1, 2, 5]);
+}
+
+#[test]
+fn clean_with_the_default_options() {
+    let cleaned = messy().clean(&CleanOptions::default());
+
+    let datetimes: Vec<NaiveDateTime> = cleaned.data.iter().map(|s| s.datetime).collect();
+    assert_eq!(
+        datetimes,
+        vec![
+            datetime("2024-03-06 09:30"),
+            datetime("2024-03-06 09:40"),
+            datetime("2024-03-06 09:45"),
+        ]
+    );
+    // the last duplicate is kept, the bar at 09:35 is dropped as invalid
+    assert_eq!(cleaned.data[1].volume, 200.0);
+    assert!(cleaned
+        .validate()
+        .anomalies
+        .iter()
+        .all(|anomaly| matches!(anomaly, Anomaly::ZeroVolume { .. })));
+}
+
+#[test]
+fn clean_repairs_and_fills_gaps() {
+    let options = CleanOptions::default()
+        .with_invalid_bars(InvalidBars::Repair)
+        .with_drop_zero_volume(true)
+        .with_fill_gaps(TradingCalendar::us_equities());
+    let cleaned = messy().clean(&options);
+
+    let datetimes: Vec<NaiveDateTime> = cleaned.data.iter().map(|s| s.datetime).collect();
+    assert_eq!(
+        datetimes,
+        vec![
+            datetime("2024-03-06 09:30"),
+            datetime("2024-03-06 09:35"),
+            datetime("2024-03-06 09:40"),
+        ]
+    );
+    assert_eq!(cleaned.data[1].high, 12.0);
+    assert!(cleaned.validate().is_clean());
+
+    let daily = series(
+        Interval::Daily,
+        vec![
+            bar("2024-03-07 00:00", 10.0, 11.0, 9.0, 10.5, 100.0),
+            bar("2024-03-12 00:00", 10.5, 11.0, 10.0, 10.8, 100.0),
+        ],
+    )
+    .clean(&CleanOptions::default().with_fill_gaps(TradingCalendar::weekdays()));
+    let filled: Vec<NaiveDateTime> = daily.data.iter().map(|s| s.datetime).collect();
+    assert_eq!(
+        filled,
+        vec![
+            datetime("2024-03-07 00:00"),
+            datetime("2024-03-08 00:00"),
+            datetime("2024-03-11 00:00"),
+            datetime("2024-03-12 00:00"),
+        ]
+    );
+    assert_eq!(daily.data[1].close, 10.5);
+    assert_eq!(daily.data[2].volume, 0.0);
+}
+
+#[test]
+fn fill_gaps_compares_the_daily_and_longer_bars_by_date() {
+    // midnight in New York, stamped in UTC: 04:00 before the end of DST, 05:00 after
+    let mut utc = series(
+        Interval::Daily,
+        vec![
+            bar("2024-11-01 04:00", 10.0, 11.0, 9.0, 10.5, 100.0),
+            bar("2024-11-04 05:00", 10.5, 11.0, 10.0, 10.8, 100.0),
+            bar("2024-11-05 05:00", 10.8, 11.0, 10.0, 10.9, 100.0),
+        ],
+    );
+    utc.timezone = Tz::UTC;
+    let cleaned = utc
+        .clone()
+        .clean(&CleanOptions::default().with_fill_gaps(TradingCalendar::weekdays()));
+    assert_eq!(cleaned.data, utc.data);
+
+    // labelled by the last trading day of the month, April is missing
+    let monthly = series(
+        Interval::Monthly,
+        vec![
+            bar("2024-02-29 00:00", 10.0, 11.0, 9.0, 10.5, 100.0),
+            bar("2024-03-28 00:00", 10.5, 11.0, 10.0, 10.8, 100.0),
+            bar("2024-05-31 00:00", 10.8, 11.0, 10.0, 10.9, 100.0),
+        ],
+    )
+    .clean(&CleanOptions::default().with_fill_gaps(TradingCalendar::weekdays()));
+    let filled: Vec<NaiveDateTime> = monthly.data.iter().map(|s| s.datetime).collect();
+    assert_eq!(
+        filled,
+        vec![
+            datetime("2024-02-29 00:00"),
+            datetime("2024-03-28 00:00"),
+            datetime("2024-04-28 00:00"),
+            datetime("2024-05-31 00:00"),
+        ]
+    );
+}
+
+#[test]
+fn fill_gaps_stays_within_the_sessions() {
+    let options = CleanOptions::default().with_fill_gaps(TradingCalendar::us_equities());
+    // the series ends at the close of a day, the 16:00 bar is the last one, and resumes at the next open
+    let cleaned = series(
+        Interval::Min30,
+        vec![
+            bar("2024-03-06 15:00", 10.0, 11.0, 9.0, 10.5, 100.0),
+            bar("2024-03-06 15:30", 10.5, 11.0, 10.0, 10.8, 100.0),
+            bar("2024-03-07 09:30", 10.8, 11.0, 10.0, 10.9, 100.0),
+            bar("2024-03-07 10:30", 10.9, 11.0, 10.0, 10.7, 100.0),
+        ],
+    )
+    .clean(&options);
+    let filled: Vec<NaiveDateTime> = cleaned.data.iter().map(|s| s.datetime).collect();
+    assert_eq!(
+        filled,
+        vec![
+            datetime("2024-03-06 15:00"),
+            datetime("2024-03-06 15:30"),
+            datetime("2024-03-07 09:30"),
+            datetime("2024-03-07 10:00"),
+            datetime("2024-03-07 10:30"),
+        ]
+    );
+
+    // the holidays of the calendar are not filled either
+    let daily = series(
+        Interval::Daily,
+        vec![
+            bar("2024-03-28 00:00", 10.0, 11.0, 9.0, 10.5, 100.0),
+            bar("2024-04-02 00:00", 10.5, 11.0, 10.0, 10.8, 100.0),
+        ],
+    )
+    .clean(&CleanOptions::default().with_fill_gaps(
+        TradingCalendar::weekdays().with_holidays([NaiveDate::from_ymd_opt(2024, 3, 29).unwrap()]),
+    ));
+    let filled: Vec<NaiveDateTime> = daily.data.iter().map(|s| s.datetime).collect();
+    assert_eq!(
+        filled,
+        vec![
+            datetime("2024-03-28 00:00"),
+            datetime("2024-04-01 00:00"),
+            datetime("2024-04-02 00:00"),
+        ]
+    );
+}
@@ -0,0 +1,67 @@
+//! Client-side rate limiting, on the paused tokio clock
+
+use market_data::{Finnhub, MarketClient, MarketError, RateLimit};
+use std::time::Duration;
+use tokio::time::Instant;
+use wiremock::{matchers::method, Mock, MockServer, ResponseTemplate};
+
+async fn client(limit: RateLimit) -> (MockServer, MarketClient<Finnhub>) {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .respond_with(ResponseTemplate::new(404))
+        .mount(&server)
+        .await;
+    // without idle connections, no pool timer lets the paused clock jump while a request is in flight
+    let client = MarketClient::builder(Finnhub::new("demo").with_base_url(server.uri()))
+        .with_pool_max_idle_per_host(0)
+        .with_rate_limit(Some(limit))
+        .build()
+        .unwrap();
+    (server, client)
+}
+
+/// Fetches a quote, only the requests refused by the limiter fail with `QuotaExhausted`
+async fn send(client: &MarketClient<Finnhub>) -> Result<(), MarketError> {
+    match client.fetch(client.site.quote("AAPL")).await {
+        Err(MarketError::QuotaExhausted { limit }) => Err(MarketError::QuotaExhausted { limit }),
+        _ => Ok(()),
+    }
+}
+
+#[tokio::test(start_paused = true)]
+async fn token_bucket_waits_for_the_refill() {
+    let (server, client) = client(RateLimit::new(2, Duration::from_secs(10))).await;
+
+    let start = Instant::now();
+    send(&client).await.unwrap();
+    send(&client).await.unwrap();
+    assert!(start.elapsed() < Duration::from_secs(1));
+
+    // the bucket is empty, one token is refilled every 5 seconds
+    send(&client).await.unwrap();
+    assert!(start.elapsed() >= Duration::from_secs(5));
+
+    tokio::time::advance(Duration::from_secs(10)).await;
+    let refilled = Instant::now();
+    send(&client).await.unwrap();
+    send(&client).await.unwrap();
+    assert!(refilled.elapsed() < Duration::from_secs(1));
+    assert_eq!(server.received_requests().await.unwrap().len(), 5);
+}
+
+#[tokio::test(start_paused = true)]
+async fn daily_quota_resets_the_next_day() {
+    let (server, client) = client(RateLimit::daily(2)).await;
+
+    send(&client).await.unwrap();
+    send(&client).await.unwrap();
+    assert!(matches!(
+        send(&client).await,
+        Err(MarketError::QuotaExhausted { limit: 2 })
+    ));
+    assert_eq!(server.received_requests().await.unwrap().len(), 2);
+
+    tokio::time::advance(Duration::from_secs(24 * 60 * 60)).await;
+    send(&client).await.unwrap();
+    assert_eq!(server.received_requests().await.unwrap().len(), 3);
+}
@@ -0,0 +1,118 @@
+//! The bounds of a provider-neutral SeriesRequest, and the intervals supported by the publishers
+
+use chrono::{DateTime, Duration, NaiveDate, NaiveDateTime, Utc};
+use market_data::{
+    Capabilities, Finnhub, Interval, IntervalUnit, IntoUtc, MarketError, Massive, Publisher,
+    SeriesRequest, Twelvedata,
+};
+
+fn utc(s: &str) -> DateTime<Utc> {
+    NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M:%S")
+        .unwrap()
+        .and_utc()
+}
+
+#[test]
+fn naive_bounds_are_taken_as_utc() {
+    let date = NaiveDate::from_ymd_opt(2024, 3, 8).unwrap();
+    assert_eq!(date.into_utc(), utc("2024-03-08 00:00:00"));
+    let datetime = date.and_hms_opt(14, 30, 0).unwrap();
+    assert_eq!(datetime.into_utc(), utc("2024-03-08 14:30:00"));
+
+    let request = SeriesRequest::new("AAPL", Interval::Daily)
+        .with_start(date)
+        .with_end(datetime);
+    assert_eq!(
+        request.range(),
+        (utc("2024-03-08 00:00:00"), utc("2024-03-08 14:30:00"))
+    );
+}
+
+#[test]
+fn lookback_covers_the_bar_count() {
+    let end = utc("2024-03-08 00:00:00");
+    let start = |interval: Interval, bars: Option<usize>| {
+        let mut request = SeriesRequest::new("AAPL", interval).with_end(end);
+        request.bars = bars;
+        end - request.range().0
+    };
+
+    // 5 trading days a week, plus 4 days for the holidays
+    assert_eq!(start(Interval::Daily, Some(100)), Duration::days(144));
+    // without a count, 100 bars are covered
+    assert_eq!(start(Interval::Daily, None), Duration::days(144));
+    // 13 hourly bars are 2 sessions of 6.5 hours
+    assert_eq!(start(Interval::Hour1, Some(13)), Duration::days(6));
+    assert_eq!(start(Interval::Weekly, Some(10)), Duration::weeks(11));
+    let two_weeks = Interval::new(2, IntervalUnit::Week).unwrap();
+    assert_eq!(start(two_weeks, Some(10)), Duration::weeks(21));
+}
+
+#[test]
+fn capabilities_match_the_listed_intervals() {
+    let finnhub = Finnhub::new("demo").capabilities();
+    assert!(finnhub.supports(&Interval::Daily));
+    assert!(finnhub.supports(&Interval::Min15));
+    assert!(!finnhub.supports(&Interval::Hour2));
+    assert!(!finnhub.supports(&Interval::new(3, IntervalUnit::Day).unwrap()));
+
+    let twelvedata = Twelvedata::new("demo").capabilities();
+    assert!(twelvedata.supports(&Interval::new(45, IntervalUnit::Minute).unwrap()));
+    assert!(!twelvedata.supports(&Interval::Quarterly));
+
+    assert!(!Capabilities::default().supports(&Interval::Daily));
+}
+
+#[test]
+fn capabilities_match_any_multiple_of_a_unit() {
+    let massive = Massive::new("demo").capabilities();
+    assert!(massive.supports(&Interval::Daily));
+    assert!(massive.supports(&Interval::new(3, IntervalUnit::Day).unwrap()));
+    assert!(massive.supports(&Interval::new(10, IntervalUnit::Minute).unwrap()));
+    assert!(massive.supports(&Interval::new(6, IntervalUnit::Month).unwrap()));
+
+    let capabilities = Capabilities {
+        intervals: vec![Interval::Daily],
+        any_multiple_of: vec![IntervalUnit::Minute],
+        ..Capabilities::default()
+    };
+    assert!(capabilities.supports(&Interval::new(7, IntervalUnit::Minute).unwrap()));
+    assert!(capabilities.supports(&Interval::Hour1));
+    assert!(!capabilities.supports(&Interval::new(2, IntervalUnit::Day).unwrap()));
+}
+
+#[test]
+fn twelvedata_output_size_is_clamped() {
+    let site = Twelvedata::new("demo");
+    let output_size = |bars: usize| {
+        let request = SeriesRequest::new("AAPL", Interval::Daily).with_bars(bars);
+        let url = site
+            .create_endpoint(&site.series_request(&request).unwrap())
+            .unwrap();
+        url.query_pairs()
+            .find(|(name, _)| name == "outputsize")
+            .map(|(_, value)| value.into_owned())
+            .unwrap()
+    };
+    assert_eq!(output_size(30), "30");
+    assert_eq!(output_size(0), "1");
+    assert_eq!(output_size(7000), "5000");
+    // above u32::MAX, the count doesn't wrap around to a small number
+    assert_eq!(output_size(u32::MAX as usize + 2), "5000");
+}
+
+#[test]
+fn massive_rejects_a_multiplier_above_its_range() {
+    let site = Massive::new("demo");
+    let huge = Interval::new(3_000_000_001, IntervalUnit::Minute).unwrap();
+    assert!(matches!(
+        site.series_request(&SeriesRequest::new("AAPL", huge).with_bars(10)),
+        Err(MarketError::UnsuportedInterval(_))
+    ));
+    assert!(site
+        .aggregates("AAPL", "2024-03-01", "2024-03-08", huge, 10)
+        .is_err());
+    assert!(site
+        .aggregates("AAPL", "2024-03-01", "2024-03-08", Interval::Hour2, 10)
+        .is_ok());
+}
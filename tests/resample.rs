@@ -0,0 +1,150 @@
+//! Resample a MarketSeries into coarser intervals
+
+use chrono::{NaiveDate, NaiveDateTime};
+use market_data::{Adjustment, Interval, MarketError, MarketSeries, Series, TradingCalendar, Tz};
+
+fn datetime(s: &str) -> NaiveDateTime {
+    NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M").unwrap()
+}
+
+fn bar(datetime: NaiveDateTime, open: f64, high: f64, low: f64, close: f64) -> Series {
+    Series {
+        datetime,
+        open,
+        close,
+        high,
+        low,
+        volume: 100.0,
+        adj_close: None,
+    }
+}
+
+fn series(interval: Interval, data: Vec<Series>) -> MarketSeries {
+    MarketSeries {
+        symbol: "AAPL".to_string(),
+        interval,
+        timezone: Tz::America__New_York,
+        data,
+        adjustment: Adjustment::Raw,
+        actions: Vec::new(),
+    }
+}
+
+#[test]
+fn intraday_to_hourly_aligned_on_session_open() {
+    let data = (0..18)
+        .map(|i| {
+            let start = datetime("2024-03-06 09:30") + chrono::Duration::minutes(5 * i);
+            bar(
+                start,
+                100.0 + i as f64,
+                101.0 + i as f64,
+                99.0 + i as f64,
+                100.5 + i as f64,
+            )
+        })
+        .collect();
+
+    let hourly = series(Interval::Min5, data)
+        .resample_with_calendar(Interval::Hour1, &TradingCalendar::us_equities())
+        .unwrap();
+    assert!(matches!(hourly.interval, Interval::Hour1));
+    assert_eq!(hourly.data.len(), 2);
+
+    let first = &hourly.data[0];
+    assert_eq!(first.datetime, datetime("2024-03-06 09:30"));
+    assert_eq!(first.open, 100.0);
+    assert_eq!(first.high, 112.0);
+    assert_eq!(first.low, 99.0);
+    assert_eq!(first.close, 111.5);
+    assert_eq!(first.volume, 1200.0);
+
+    let second = &hourly.data[1];
+    assert_eq!(second.datetime, datetime("2024-03-06 10:30"));
+    assert_eq!(second.open, 112.0);
+    assert_eq!(second.close, 117.5);
+    assert_eq!(second.volume, 600.0);
+}
+
+#[test]
+fn intraday_buckets_keep_their_anchor_when_the_first_bars_are_missing() {
+    // no bars until 9:50 on the 7th, and a pre-market bar at 9:00 on the 8th
+    let data = vec![
+        bar(datetime("2024-03-06 09:30"), 10.0, 11.0, 9.0, 10.5),
+        bar(datetime("2024-03-06 10:15"), 10.5, 12.0, 10.0, 11.0),
+        bar(datetime("2024-03-07 09:50"), 11.0, 11.5, 10.5, 11.2),
+        bar(datetime("2024-03-07 10:40"), 11.2, 11.8, 11.0, 11.6),
+        bar(datetime("2024-03-08 09:00"), 11.6, 11.9, 11.1, 11.5),
+    ];
+    let series = series(Interval::Min5, data);
+
+    let labels = |resampled: MarketSeries| -> Vec<NaiveDateTime> {
+        resampled.data.iter().map(|s| s.datetime).collect()
+    };
+    let on_session = series
+        .resample_with_calendar(Interval::Hour1, &TradingCalendar::us_equities())
+        .unwrap();
+    assert_eq!(
+        labels(on_session),
+        [
+            datetime("2024-03-06 09:30"),
+            datetime("2024-03-07 09:30"),
+            datetime("2024-03-07 10:30"),
+            datetime("2024-03-08 08:30"),
+        ]
+    );
+
+    let on_midnight = series.resample(Interval::Hour1).unwrap();
+    assert_eq!(
+        labels(on_midnight),
+        [
+            datetime("2024-03-06 09:00"),
+            datetime("2024-03-06 10:00"),
+            datetime("2024-03-07 09:00"),
+            datetime("2024-03-07 10:00"),
+            datetime("2024-03-08 09:00"),
+        ]
+    );
+}
+
+#[test]
+fn daily_to_weekly_and_monthly() {
+    let data = (0..25)
+        .map(|i| {
+            let date = NaiveDate::from_ymd_opt(2024, 2, 19).unwrap() + chrono::Duration::days(i);
+            bar(date.into(), 10.0, 10.0 + i as f64, 5.0, 10.0 + i as f64)
+        })
+        .filter(|s| s.datetime.format("%a").to_string() != "Sat")
+        .collect();
+    let daily = series(Interval::Daily, data);
+
+    let weekly = daily.resample(Interval::Weekly).unwrap();
+    let labels: Vec<NaiveDateTime> = weekly.data.iter().map(|s| s.datetime).collect();
+    assert_eq!(
+        labels,
+        vec![
+            datetime("2024-02-19 00:00"),
+            datetime("2024-02-26 00:00"),
+            datetime("2024-03-04 00:00"),
+            datetime("2024-03-11 00:00"),
+        ]
+    );
+    assert_eq!(weekly.data[0].close, 16.0);
+    assert_eq!(weekly.data[0].volume, 600.0);
+
+    let monthly = daily.resample(Interval::Monthly).unwrap();
+    assert_eq!(monthly.data.len(), 2);
+    assert_eq!(monthly.data[1].datetime, datetime("2024-03-01 00:00"));
+    assert_eq!(monthly.data[1].high, 34.0);
+}
+
+#[test]
+fn finer_interval_is_rejected() {
+    let daily = series(
+        Interval::Daily,
+        vec![bar(datetime("2024-03-06 00:00"), 1.0, 1.0, 1.0, 1.0)],
+    );
+
+    let result = daily.resample(Interval::Hour1);
+    assert!(matches!(result, Err(MarketError::ResampleError(_))));
+}
@@ -0,0 +1,54 @@
+//! The delays of the RetryPolicy between two attempts
+
+use market_data::RetryPolicy;
+use std::time::Duration;
+
+fn millis(ms: u64) -> Duration {
+    Duration::from_millis(ms)
+}
+
+#[test]
+fn backoff_doubles_up_to_the_max_delay() {
+    let policy = RetryPolicy::default()
+        .with_base_delay(millis(100))
+        .with_max_delay(millis(1000))
+        .with_jitter(false);
+    let delays: Vec<Duration> = (1..=6)
+        .map(|attempt| policy.delay(attempt, None).unwrap())
+        .collect();
+    assert_eq!(
+        delays,
+        [
+            millis(100),
+            millis(200),
+            millis(400),
+            millis(800),
+            millis(1000),
+            millis(1000)
+        ]
+    );
+    // the exponent doesn't overflow on a large attempt count
+    assert_eq!(policy.delay(u32::MAX, None), Some(millis(1000)));
+}
+
+#[test]
+fn jitter_stays_between_half_and_the_full_delay() {
+    let policy = RetryPolicy::default().with_base_delay(millis(100));
+    for _ in 0..100 {
+        let delay = policy.delay(3, None).unwrap();
+        assert!(delay >= millis(200) && delay <= millis(400), "{:?}", delay);
+    }
+}
+
+#[test]
+fn retry_after_takes_precedence_up_to_the_max_delay() {
+    let policy = RetryPolicy::default()
+        .with_base_delay(millis(100))
+        .with_max_delay(Duration::from_secs(30));
+    assert_eq!(
+        policy.delay(1, Some(Duration::from_secs(5))),
+        Some(Duration::from_secs(5))
+    );
+    // a longer wait than the max delay is not retried early
+    assert_eq!(policy.delay(1, Some(Duration::from_secs(60))), None);
+}
@@ -0,0 +1,89 @@
+//! Slice and look up the bars of MarketSeries and EnhancedMarketSeries
+
+use chrono::{NaiveDate, NaiveDateTime};
+use market_data::{Adjustment, Interval, MarketSeries, Series, Tz};
+
+fn datetime(day: u32) -> NaiveDateTime {
+    NaiveDate::from_ymd_opt(2024, 3, day)
+        .unwrap()
+        .and_hms_opt(0, 0, 0)
+        .unwrap()
+}
+
+fn series() -> MarketSeries {
+    MarketSeries {
+        symbol: "AAPL".to_string(),
+        interval: Interval::Daily,
+        timezone: Tz::America__New_York,
+        data: [4, 5, 6, 7, 8, 11, 12]
+            .into_iter()
+            .enumerate()
+            .map(|(i, day)| Series {
+                datetime: datetime(day),
+                open: 10.0,
+                close: 10.0 + i as f64,
+                high: 20.0,
+                low: 5.0,
+                volume: 100.0,
+                adj_close: None,
+            })
+            .collect(),
+        adjustment: Adjustment::Raw,
+        actions: Vec::new(),
+    }
+}
+
+#[test]
+fn range_as_of_head_and_tail() {
+    let series = series();
+
+    let days = |bars: &[Series]| bars.iter().map(|s| s.datetime).collect::<Vec<_>>();
+    assert_eq!(
+        days(series.range(datetime(5)..datetime(8))),
+        vec![datetime(5), datetime(6), datetime(7)]
+    );
+    assert_eq!(
+        days(series.range(datetime(9)..=datetime(11))),
+        vec![datetime(11)]
+    );
+    assert_eq!(series.range(datetime(12)..).len(), 1);
+    assert!(series.range(datetime(20)..).is_empty());
+
+    assert_eq!(series.as_of(datetime(10)).unwrap().datetime, datetime(8));
+    assert_eq!(series.as_of(datetime(11)).unwrap().datetime, datetime(11));
+    assert!(series.as_of(datetime(1)).is_none());
+
+    assert_eq!(days(series.head(2)), vec![datetime(4), datetime(5)]);
+    assert_eq!(days(series.tail(2)), vec![datetime(11), datetime(12)]);
+    assert_eq!(series.tail(100).len(), 7);
+
+    assert_eq!(series.windows(3).count(), 5);
+}
+
+#[test]
+fn enhanced_slices_keep_the_indicators_aligned() {
+    let enhanced = series()
+        .enhance_data()
+        .with_sma(2)
+        .with_macd(2, 3, 2)
+        .calculate();
+
+    let slice = enhanced.range(datetime(7)..);
+    assert_eq!(slice.offset(), 3);
+    assert_eq!(slice.len(), 4);
+    assert_eq!(slice.values("SMA 2").unwrap(), vec![12.5, 13.5, 14.5, 15.5]);
+    assert_eq!(slice.triples("MACD (2, 3, 2)").unwrap().len(), 4);
+    assert!(slice.values("SMA 50").is_none());
+
+    let last = enhanced.as_of(datetime(9)).unwrap();
+    assert_eq!(last.series[0].datetime, datetime(8));
+    assert_eq!(last.values("SMA 2").unwrap(), vec![13.5]);
+
+    let windows: Vec<Vec<f64>> = enhanced
+        .windows(6)
+        .map(|window| window.values("SMA 2").unwrap())
+        .collect();
+    assert_eq!(windows.len(), 2);
+    assert_eq!(windows[1][0], 10.5);
+    assert_eq!(enhanced.tail(1).values("SMA 2").unwrap(), vec![15.5]);
+}
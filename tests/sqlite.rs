@@ -0,0 +1,171 @@
+//! Persist MarketSeries to a local SQLite store, run with `--features sqlite`
+#![cfg(feature = "sqlite")]
+
+use chrono::{NaiveDate, NaiveDateTime};
+use market_data::{
+    Adjustment, CorporateAction, Interval, MarketError, MarketSeries, Series, SqliteStore, Tz,
+};
+
+fn datetime(day: u32) -> NaiveDateTime {
+    NaiveDate::from_ymd_opt(2024, 3, day)
+        .unwrap()
+        .and_hms_opt(0, 0, 0)
+        .unwrap()
+}
+
+fn series(days: std::ops::Range<u32>, close: f64) -> MarketSeries {
+    MarketSeries {
+        symbol: "AAPL".to_string(),
+        interval: Interval::Daily,
+        timezone: Tz::America__New_York,
+        data: days
+            .map(|day| Series {
+                datetime: datetime(day),
+                open: 10.0,
+                close,
+                high: 15.0,
+                low: 9.0,
+                volume: 1000.0,
+                adj_close: None,
+            })
+            .collect(),
+        adjustment: Adjustment::Raw,
+        actions: vec![CorporateAction::Dividend {
+            date: NaiveDate::from_ymd_opt(2024, 3, 6).unwrap(),
+            amount: 0.24,
+        }],
+    }
+}
+
+#[test]
+fn upserts_are_idempotent() {
+    let mut store = SqliteStore::open_in_memory().unwrap();
+    assert_eq!(store.upsert("yahoo", &series(4..9, 11.0)).unwrap(), 5);
+    store.upsert("yahoo", &series(4..9, 11.0)).unwrap();
+    // the overlapping bars are replaced by the newer ones
+    store.upsert("yahoo", &series(7..12, 12.0)).unwrap();
+
+    let read = store
+        .load("yahoo", "AAPL", Interval::Daily, ..)
+        .unwrap()
+        .unwrap();
+    assert_eq!(read.timezone, Tz::America__New_York);
+    assert_eq!(read.data.len(), 8);
+    assert_eq!(read.data[2].close, 11.0);
+    assert_eq!(read.data[3].close, 12.0);
+    assert_eq!(read.actions.len(), 1);
+
+    assert!(store
+        .load("finnhub", "AAPL", Interval::Daily, ..)
+        .unwrap()
+        .is_none());
+}
+
+#[test]
+fn range_queries_and_coverage() {
+    let mut store = SqliteStore::open_in_memory().unwrap();
+    store.upsert("yahoo", &series(4..12, 11.0)).unwrap();
+    let mut msft = series(5..7, 20.0);
+    msft.symbol = "MSFT".to_string();
+    msft.actions.clear();
+    store.upsert("massive", &msft).unwrap();
+
+    let read = store
+        .load("yahoo", "AAPL", Interval::Daily, datetime(5)..datetime(8))
+        .unwrap()
+        .unwrap();
+    assert_eq!(read.data.len(), 3);
+    assert_eq!(read.data[0].datetime, datetime(5));
+    assert_eq!(read.actions.len(), 1);
+    let read = store
+        .load("yahoo", "AAPL", Interval::Daily, datetime(10)..)
+        .unwrap()
+        .unwrap();
+    assert_eq!(read.data.len(), 2);
+    assert!(read.actions.is_empty());
+
+    assert_eq!(store.symbols().unwrap(), ["AAPL", "MSFT"]);
+    let coverage = store.coverage().unwrap();
+    assert_eq!(coverage.len(), 2);
+    assert_eq!(coverage[0].publisher, "yahoo");
+    assert_eq!(coverage[0].interval, Interval::Daily);
+    assert_eq!(coverage[0].first, datetime(4));
+    assert_eq!(coverage[0].last, datetime(11));
+    assert_eq!(coverage[0].bars, 8);
+    assert_eq!(coverage[1].symbol, "MSFT");
+}
+
+#[test]
+fn keeps_the_stored_timezone_and_adjustment() {
+    let path = std::env::temp_dir().join(format!("market-data-{}.sqlite", std::process::id()));
+    let mut store = SqliteStore::open(&path).unwrap();
+    let hourly = |days| MarketSeries {
+        interval: Interval::Hour1,
+        ..series(days, 11.0)
+    };
+    store.upsert("yahoo", &hourly(4..6)).unwrap();
+    store
+        .upsert("yahoo", &hourly(6..8).to_timezone(Tz::UTC))
+        .unwrap();
+
+    let mut adjusted = hourly(8..9);
+    adjusted.adjustment = Adjustment::Splits;
+    assert!(matches!(
+        store.upsert("yahoo", &adjusted),
+        Err(MarketError::StorageError(_))
+    ));
+    drop(store);
+
+    let store = SqliteStore::open(&path).unwrap();
+    let read = store
+        .load("yahoo", "AAPL", Interval::Hour1, ..)
+        .unwrap()
+        .unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(read.timezone, Tz::America__New_York);
+    assert_eq!(read.data.len(), 4);
+    assert_eq!(read.data[3].datetime, datetime(7));
+}
+
+#[test]
+fn daily_bars_are_not_moved_to_another_timezone() {
+    let mut store = SqliteStore::open_in_memory().unwrap();
+    store.upsert("yahoo", &series(4..6, 11.0)).unwrap();
+
+    // the same sessions, labelled at midnight in Tokyo, would land on the day before in New York
+    let mut tokyo = series(4..6, 12.0);
+    tokyo.timezone = Tz::Asia__Tokyo;
+    assert!(matches!(
+        store.upsert("yahoo", &tokyo),
+        Err(MarketError::StorageError(_))
+    ));
+    store.upsert("yahoo", &series(4..6, 12.0)).unwrap();
+
+    let read = store
+        .load("yahoo", "AAPL", Interval::Daily, ..)
+        .unwrap()
+        .unwrap();
+    let closes: Vec<f64> = read.data.iter().map(|s| s.close).collect();
+    assert_eq!(closes, [12.0, 12.0]);
+}
+
+#[test]
+fn unknown_stored_values_are_rejected() {
+    let path =
+        std::env::temp_dir().join(format!("market-data-unknown-{}.sqlite", std::process::id()));
+    let mut store = SqliteStore::open(&path).unwrap();
+    store.upsert("yahoo", &series(4..6, 11.0)).unwrap();
+    drop(store);
+
+    // written by another tool, or a later version
+    let conn = rusqlite::Connection::open(&path).unwrap();
+    conn.execute("UPDATE series SET adjustment = 'Dividends'", [])
+        .unwrap();
+    drop(conn);
+
+    let store = SqliteStore::open(&path).unwrap();
+    let read = store.load("yahoo", "AAPL", Interval::Daily, ..);
+    drop(store);
+    std::fs::remove_file(&path).unwrap();
+    assert!(matches!(read, Err(MarketError::ParsingError(_))));
+}
@@ -0,0 +1,23 @@
+//! The timezone of the series serialized before it was recorded defaults to UTC
+
+use market_data::{EnhancedMarketSeries, MarketSeries, Tz};
+
+const BARS: &str = r#"[{"datetime":"2024-03-06T00:00:00","open":171.06,"close":169.12,"high":171.24,"low":168.68,"volume":68587700.0}]"#;
+
+#[test]
+fn market_series_without_timezone() {
+    let json = format!(r#"{{"symbol":"AAPL","interval":"Daily","data":{}}}"#, BARS);
+    let series: MarketSeries = serde_json::from_str(&json).unwrap();
+    assert_eq!(series.timezone, Tz::UTC);
+}
+
+#[test]
+fn enhanced_series_without_timezone() {
+    let json = format!(
+        r#"{{"symbol":"AAPL","interval":"Daily","series":{},"asks":[{{"Sma":1}}],"indicators":{{"sma":{{"1":[169.12]}},"ema":{{}},"rsi":{{}},"stochastic":{{}},"macd":{{}},"bb":{{}}}}}}"#,
+        BARS
+    );
+    let series: EnhancedMarketSeries = serde_json::from_str(&json).unwrap();
+    assert_eq!(series.timezone, Tz::UTC);
+    assert_eq!(series.series.len(), 1);
+}